@@ -0,0 +1,33 @@
+#version 450
+
+in VS_OUT {
+    vec2 tex_coord;
+} vs_in;
+
+layout (location = 0) out vec4 out_color;
+layout (location = 1) out vec4 out_emissive;
+
+uniform sampler2D billboard_texture;
+// Textures are sRGB encoded, everything else is linear
+uniform bool srgb;
+
+vec3 srgb_to_linear(vec3 color) {
+    return mix(color / 12.92, pow((color + 0.055) / 1.055, vec3(2.4)), step(0.04045, color));
+}
+
+void main() {
+    vec4 color = texture(billboard_texture, vs_in.tex_coord);
+
+    // Cut out rather than blended, so that billboards do not have to be sorted
+    if (color.a < 0.5) {
+        discard;
+    }
+
+    if (srgb) {
+        color.rgb = srgb_to_linear(color.rgb);
+    }
+
+    out_color = vec4(color.rgb, 1.0);
+    // Also hides the emission of anything behind the billboard
+    out_emissive = vec4(0.0, 0.0, 0.0, 1.0);
+}
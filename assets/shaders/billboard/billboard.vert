@@ -0,0 +1,28 @@
+#version 450
+
+// Quad
+layout (location = 0) in vec2 corner;
+
+// Instance
+layout (location = 1) in vec3 center;
+layout (location = 2) in vec2 size;
+
+out VS_OUT {
+    vec2 tex_coord;
+} vs_out;
+
+uniform mat4 vp;
+// Directions in world space which point right and up on the screen
+uniform vec3 camera_right;
+uniform vec3 camera_up;
+
+void main() {
+    vec3 position = center
+        + camera_right * corner.x * size.x * 0.5
+        + camera_up * corner.y * size.y * 0.5;
+
+    // Images are loaded top row first, so the top of the quad samples v = 0
+    vs_out.tex_coord = vec2(corner.x * 0.5 + 0.5, 0.5 - corner.y * 0.5);
+
+    gl_Position = vp * vec4(position, 1.0);
+}
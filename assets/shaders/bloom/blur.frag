@@ -0,0 +1,26 @@
+#version 450
+
+in VS_OUT {
+    vec2 tex_coord;
+} vs_in;
+
+layout (location = 0) out vec4 out_color;
+
+uniform sampler2D image;
+// (1, 0) for the horizontal pass and (0, 1) for the vertical pass
+uniform vec2 direction;
+
+// One side of a 9 tap gaussian kernel
+const float weights[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+void main() {
+    vec2 texel = direction / vec2(textureSize(image, 0));
+
+    vec3 result = texture(image, vs_in.tex_coord).rgb * weights[0];
+    for (int i = 1; i < 5; i++) {
+        result += texture(image, vs_in.tex_coord + texel * i).rgb * weights[i];
+        result += texture(image, vs_in.tex_coord - texel * i).rgb * weights[i];
+    }
+
+    out_color = vec4(result, 1.0);
+}
@@ -0,0 +1,24 @@
+#version 450
+
+in VS_OUT {
+    vec2 tex_coord;
+} vs_in;
+
+layout (location = 0) out vec4 out_color;
+
+uniform sampler2D scene;
+// Zero alpha where nothing emissive was drawn
+uniform sampler2D emissive;
+uniform float threshold;
+
+void main() {
+    vec3 color = texture(scene, vs_in.tex_coord).rgb;
+
+    // Keep only the part of the color above the threshold, without shifting its hue
+    float brightness = max(color.r, max(color.g, color.b));
+    float contribution = max(brightness - threshold, 0.0) / max(brightness, 0.0001);
+
+    vec4 emission = texture(emissive, vs_in.tex_coord);
+
+    out_color = vec4(color * contribution + emission.rgb * emission.a, 1.0);
+}
@@ -1,41 +1,121 @@
 #version 450
 
 in VS_OUT {
-    vec3 position;
+    vec3 world_position;
     vec2 tex_coord;
-    vec3 normal;
+    mat3 tbn;
+    // Linear, so applied after the texture is decoded from sRGB
+    vec4 color;
 } vs_in;
 
 layout (location = 0) out vec4 out_color;
+layout (location = 1) out vec4 out_emissive;
 
 uniform sampler2D diffuse_texture;
 uniform sampler2D specular_texture;
+uniform sampler2D normal_texture;
+uniform sampler2D emissive_texture;
+uniform float emissive_strength;
 uniform vec3 light_color;
 uniform vec3 light_position;
+uniform float light_intensity;
+// Distance at which the light has faded out, or zero if it never does
+uniform float light_range;
 uniform vec3 camera_position;
+// Albedo textures and colors are sRGB encoded, everything else is linear
+uniform bool srgb;
+// Skip lighting and show the albedo as is
+uniform bool unlit;
+// Added to the mip level of every texture lookup, each step halves the resolution sampled
+uniform float texture_lod_bias;
+
+// 0 = none, 1 = linear, 2 = exponential
+uniform int fog_falloff;
+uniform vec3 fog_color;
+uniform float fog_start;
+uniform float fog_end;
+uniform float fog_density;
+
+// How much of a surface this far from the camera can be seen through the fog
+float fog_visibility(float view_distance) {
+    if (fog_falloff == 1) {
+        // Equal distances would divide by zero, this makes the fog a hard edge instead
+        float fog_depth = max(fog_end - fog_start, 0.0001);
+        return clamp((fog_end - view_distance) / fog_depth, 0.0, 1.0);
+    } else if (fog_falloff == 2) {
+        return exp(-fog_density * view_distance);
+    }
+
+    return 1.0;
+}
+
+float light_attenuation(float light_distance) {
+    if (light_range <= 0.0) {
+        return 1.0;
+    }
+
+    // Reaches exactly zero at the range, rather than only approaching it
+    float falloff = clamp(1.0 - pow(light_distance / light_range, 2.0), 0.0, 1.0);
+    return falloff * falloff;
+}
+
+vec3 srgb_to_linear(vec3 color) {
+    return mix(color / 12.92, pow((color + 0.055) / 1.055, vec3(2.4)), step(0.04045, color));
+}
 
 void main() {
+    vec3 light = (srgb ? srgb_to_linear(light_color) : light_color) * light_intensity;
+
+    // Tangent space normal from [0, 1] to [-1, 1]
+    vec3 normal = texture(normal_texture, vs_in.tex_coord, texture_lod_bias).rgb * 2.0 - 1.0;
+    normal = normalize(vs_in.tbn * normal);
+
     // Ambient
     float ambient_strength = 0.3;
-    vec3 ambient = ambient_strength * light_color;
+    vec3 ambient = ambient_strength * light;
 
     // Diffuse
-    vec3 light_direction = normalize(light_position - vs_in.position);
+    vec3 light_direction = normalize(light_position - vs_in.world_position);
+
+    // Ambient light is unaffected by the range, so that it still lights the whole scene
+    float attenuation = light_attenuation(distance(light_position, vs_in.world_position));
 
-    float diffuse_strength = max(dot(vs_in.normal, light_direction), 0.0);
-    vec3 diffuse = diffuse_strength * light_color;
+    float diffuse_strength = max(dot(normal, light_direction), 0.0);
+    vec3 diffuse = diffuse_strength * light * attenuation;
 
     // Specular
-    vec3 view_direction = normalize(camera_position - vs_in.position);
-    vec3 reflect_direction = reflect(-light_direction, vs_in.normal);
+    vec3 view_direction = normalize(camera_position - vs_in.world_position);
+    vec3 reflect_direction = reflect(-light_direction, normal);
 
-    vec4 specular_color = texture(specular_texture, vs_in.tex_coord);
+    vec4 specular_color = texture(specular_texture, vs_in.tex_coord, texture_lod_bias);
     int shininess = 32;
     float specular_factor = pow(max(dot(view_direction, reflect_direction), 0.0), shininess);
-    vec3 specular = specular_color.xyz * specular_factor * light_color;
+    vec3 specular = specular_color.xyz * specular_factor * light * attenuation;
+
+    vec4 diffuse_color = texture(diffuse_texture, vs_in.tex_coord, texture_lod_bias);
+    if (srgb) {
+        diffuse_color.rgb = srgb_to_linear(diffuse_color.rgb);
+    }
+    diffuse_color *= vs_in.color;
+
+    vec3 emissive = texture(emissive_texture, vs_in.tex_coord, texture_lod_bias).rgb;
+    if (srgb) {
+        emissive = srgb_to_linear(emissive);
+    }
+    emissive *= emissive_strength;
+
+    float visibility = fog_visibility(distance(camera_position, vs_in.world_position));
+    emissive *= visibility;
+
+    // Also hides the emission of anything behind this surface
+    out_emissive = vec4(emissive, 1.0);
 
-    vec4 diffuse_color = texture(diffuse_texture, vs_in.tex_coord);
+    if (unlit) {
+        out_color = diffuse_color;
+        return;
+    }
 
-    out_color = diffuse_color * vec4((ambient + diffuse + specular), 1.0);
+    out_color = diffuse_color * vec4((ambient + diffuse + specular), 1.0) + vec4(emissive, 0.0);
+    out_color.rgb = mix(fog_color, out_color.rgb, visibility);
     //    out_color = vec4(1.0, 1.0, 1.0, 1.0);
 }
\ No newline at end of file
@@ -4,14 +4,17 @@
 layout (location = 0) in vec3 position;
 layout (location = 1) in vec3 normal;
 layout (location = 2) in vec2 tex_coord;
+layout (location = 3) in vec4 tangent;
+layout (location = 8) in vec4 color;
 
 // Instance
-layout (location = 3) in mat4 transform;
+layout (location = 4) in mat4 transform;
 
 out VS_OUT {
-    vec3 position;
+    vec3 world_position;
     vec2 tex_coord;
-    vec3 normal;
+    mat3 tbn;
+    vec4 color;
 } vs_out;
 
 // TODO if anything bad happens listen to this guy https://stackoverflow.com/questions/38172696/should-i-ever-use-a-vec3-inside-of-a-uniform-buffer-or-shader-storage-buffer-o
@@ -19,12 +22,23 @@ out VS_OUT {
 // per frame
 uniform mat4 vp;
 
+// Must match the depth pre-pass shader exactly
+invariant gl_Position;
+
 void main() {
-    vs_out.position = position;
+    vs_out.world_position = vec3(transform * vec4(position, 1.0));
     vs_out.tex_coord = tex_coord;
+    vs_out.color = color;
 
     // TODO move calculation to uniform
-    vs_out.normal = normalize(transpose(inverse(mat3(transform))) * normal);
+    mat3 normal_matrix = transpose(inverse(mat3(transform)));
+    vec3 world_normal = normalize(normal_matrix * normal);
+    vec3 world_tangent = normalize(mat3(transform) * tangent.xyz);
+    // Re-orthogonalise as the tangent and normal are transformed by different matrices
+    world_tangent = normalize(world_tangent - dot(world_tangent, world_normal) * world_normal);
+    vec3 world_bitangent = cross(world_normal, world_tangent) * tangent.w;
+
+    vs_out.tbn = mat3(world_tangent, world_bitangent, world_normal);
 
     gl_Position = vp * transform * vec4(position, 1.0);
 }
\ No newline at end of file
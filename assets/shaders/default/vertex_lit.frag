@@ -0,0 +1,66 @@
+#version 450
+
+in VS_OUT {
+    vec3 world_position;
+    vec2 tex_coord;
+    vec3 light;
+    vec3 specular;
+    vec4 color;
+} vs_in;
+
+layout (location = 0) out vec4 out_color;
+layout (location = 1) out vec4 out_emissive;
+
+uniform sampler2D diffuse_texture;
+uniform sampler2D specular_texture;
+uniform sampler2D emissive_texture;
+uniform float emissive_strength;
+uniform bool srgb;
+// Added to the mip level of every texture lookup, each step halves the resolution sampled
+uniform float texture_lod_bias;
+uniform vec3 camera_position;
+
+// 0 = none, 1 = linear, 2 = exponential
+uniform int fog_falloff;
+uniform vec3 fog_color;
+uniform float fog_start;
+uniform float fog_end;
+uniform float fog_density;
+
+// How much of a surface this far from the camera can be seen through the fog
+float fog_visibility(float view_distance) {
+    if (fog_falloff == 1) {
+        // Equal distances would divide by zero, this makes the fog a hard edge instead
+        float fog_depth = max(fog_end - fog_start, 0.0001);
+        return clamp((fog_end - view_distance) / fog_depth, 0.0, 1.0);
+    } else if (fog_falloff == 2) {
+        return exp(-fog_density * view_distance);
+    }
+
+    return 1.0;
+}
+
+vec3 srgb_to_linear(vec3 color) {
+    return mix(color / 12.92, pow((color + 0.055) / 1.055, vec3(2.4)), step(0.04045, color));
+}
+
+void main() {
+    vec4 diffuse_color = texture(diffuse_texture, vs_in.tex_coord, texture_lod_bias);
+    vec3 emissive = texture(emissive_texture, vs_in.tex_coord, texture_lod_bias).rgb;
+
+    if (srgb) {
+        diffuse_color.rgb = srgb_to_linear(diffuse_color.rgb);
+        emissive = srgb_to_linear(emissive);
+    }
+    diffuse_color *= vs_in.color;
+    emissive *= emissive_strength;
+
+    float visibility = fog_visibility(distance(camera_position, vs_in.world_position));
+    emissive *= visibility;
+
+    vec3 specular = texture(specular_texture, vs_in.tex_coord, texture_lod_bias).rgb * vs_in.specular;
+
+    out_emissive = vec4(emissive, 1.0);
+    out_color = diffuse_color * vec4(vs_in.light, 1.0) + vec4(specular + emissive, 0.0);
+    out_color.rgb = mix(fog_color, out_color.rgb, visibility);
+}
@@ -0,0 +1,75 @@
+#version 450
+
+// Model
+layout (location = 0) in vec3 position;
+layout (location = 1) in vec3 normal;
+layout (location = 2) in vec2 tex_coord;
+layout (location = 8) in vec4 color;
+
+// Instance
+layout (location = 4) in mat4 transform;
+
+out VS_OUT {
+    vec3 world_position;
+    vec2 tex_coord;
+    vec3 light;
+    vec3 specular;
+    vec4 color;
+} vs_out;
+
+uniform mat4 vp;
+uniform vec3 light_color;
+uniform vec3 light_position;
+uniform float light_intensity;
+// Distance at which the light has faded out, or zero if it never does
+uniform float light_range;
+uniform vec3 camera_position;
+uniform bool srgb;
+
+// Must match the depth pre-pass shader exactly
+invariant gl_Position;
+
+float light_attenuation(float light_distance) {
+    if (light_range <= 0.0) {
+        return 1.0;
+    }
+
+    // Reaches exactly zero at the range, rather than only approaching it
+    float falloff = clamp(1.0 - pow(light_distance / light_range, 2.0), 0.0, 1.0);
+    return falloff * falloff;
+}
+
+vec3 srgb_to_linear(vec3 color) {
+    return mix(color / 12.92, pow((color + 0.055) / 1.055, vec3(2.4)), step(0.04045, color));
+}
+
+// The same lighting as the default shader, but evaluated once per vertex and without normal maps
+void main() {
+    vec3 light = (srgb ? srgb_to_linear(light_color) : light_color) * light_intensity;
+
+    vec3 world_position = vec3(transform * vec4(position, 1.0));
+    vec3 world_normal = normalize(transpose(inverse(mat3(transform))) * normal);
+
+    // Ambient
+    float ambient_strength = 0.3;
+    vec3 ambient = ambient_strength * light;
+
+    // Diffuse
+    vec3 light_direction = normalize(light_position - world_position);
+    float attenuation = light_attenuation(distance(light_position, world_position));
+    vec3 diffuse = max(dot(world_normal, light_direction), 0.0) * light * attenuation;
+
+    // Specular
+    vec3 view_direction = normalize(camera_position - world_position);
+    vec3 reflect_direction = reflect(-light_direction, world_normal);
+    int shininess = 32;
+    float specular_factor = pow(max(dot(view_direction, reflect_direction), 0.0), shininess);
+
+    vs_out.world_position = world_position;
+    vs_out.tex_coord = tex_coord;
+    vs_out.color = color;
+    vs_out.light = ambient + diffuse;
+    vs_out.specular = specular_factor * light * attenuation;
+
+    gl_Position = vp * transform * vec4(position, 1.0);
+}
@@ -0,0 +1,10 @@
+#version 450
+
+// Colour writes are masked off, but every output of the scene target has to exist
+layout (location = 0) out vec4 out_color;
+layout (location = 1) out vec4 out_emissive;
+
+void main() {
+    out_color = vec4(0.0);
+    out_emissive = vec4(0.0);
+}
\ No newline at end of file
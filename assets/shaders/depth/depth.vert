@@ -0,0 +1,16 @@
+#version 450
+
+// Model
+layout (location = 0) in vec3 position;
+
+// Instance
+layout (location = 4) in mat4 transform;
+
+uniform mat4 vp;
+
+// Must match the default shader exactly so the shaded pass can test for equal depth
+invariant gl_Position;
+
+void main() {
+    gl_Position = vp * transform * vec4(position, 1.0);
+}
\ No newline at end of file
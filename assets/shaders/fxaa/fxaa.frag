@@ -0,0 +1,55 @@
+#version 450
+
+in VS_OUT {
+    vec2 tex_coord;
+} vs_in;
+
+layout (location = 0) out vec4 out_color;
+
+// The tonemapped and gamma encoded image
+uniform sampler2D image;
+
+const float SPAN_MAX = 8.0;
+const float REDUCE_MUL = 1.0 / 8.0;
+const float REDUCE_MIN = 1.0 / 128.0;
+
+float luma(vec3 color) {
+    return dot(color, vec3(0.299, 0.587, 0.114));
+}
+
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(image, 0));
+    vec2 uv = vs_in.tex_coord;
+
+    float luma_north_west = luma(texture(image, uv + vec2(-1.0, -1.0) * texel).rgb);
+    float luma_north_east = luma(texture(image, uv + vec2(1.0, -1.0) * texel).rgb);
+    float luma_south_west = luma(texture(image, uv + vec2(-1.0, 1.0) * texel).rgb);
+    float luma_south_east = luma(texture(image, uv + vec2(1.0, 1.0) * texel).rgb);
+    float luma_middle = luma(texture(image, uv).rgb);
+
+    float luma_min = min(luma_middle, min(min(luma_north_west, luma_north_east), min(luma_south_west, luma_south_east)));
+    float luma_max = max(luma_middle, max(max(luma_north_west, luma_north_east), max(luma_south_west, luma_south_east)));
+
+    // Blur along the edge, which is perpendicular to the luma gradient
+    vec2 direction = vec2(
+        -((luma_north_west + luma_north_east) - (luma_south_west + luma_south_east)),
+        (luma_north_west + luma_south_west) - (luma_north_east + luma_south_east)
+    );
+
+    float direction_reduce = max((luma_north_west + luma_north_east + luma_south_west + luma_south_east) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    float inverse_direction_min = 1.0 / (min(abs(direction.x), abs(direction.y)) + direction_reduce);
+    direction = clamp(direction * inverse_direction_min, -SPAN_MAX, SPAN_MAX) * texel;
+
+    vec3 inner = 0.5 * (
+        texture(image, uv + direction * (1.0 / 3.0 - 0.5)).rgb +
+        texture(image, uv + direction * (2.0 / 3.0 - 0.5)).rgb
+    );
+    vec3 outer = inner * 0.5 + 0.25 * (
+        texture(image, uv - direction * 0.5).rgb +
+        texture(image, uv + direction * 0.5).rgb
+    );
+
+    // The wider sample crossed another edge, so fall back to the narrower one
+    float luma_outer = luma(outer);
+    out_color = vec4((luma_outer < luma_min || luma_outer > luma_max) ? inner : outer, 1.0);
+}
@@ -0,0 +1,90 @@
+#version 450
+
+in VS_OUT {
+    vec2 tex_coord;
+} vs_in;
+
+layout (location = 0) out vec4 out_color;
+layout (location = 1) out vec4 out_emissive;
+
+// Takes points on the screen back into the world, to find where they hit the ground
+uniform mat4 inverse_vp;
+// Projects the grid back onto the screen, so that the depth written matches the rest of the scene
+uniform mat4 vp;
+uniform vec3 camera_position;
+// Distance at which the grid has faded out completely
+uniform float fade_distance;
+
+const float MINOR_SPACING = 1.0;
+const float MAJOR_SPACING = 10.0;
+
+const vec3 MINOR_COLOR = vec3(0.35);
+const vec3 MAJOR_COLOR = vec3(0.6);
+const vec3 X_AXIS_COLOR = vec3(0.9, 0.2, 0.2);
+const vec3 Z_AXIS_COLOR = vec3(0.2, 0.3, 0.9);
+
+vec3 unproject(vec2 ndc, float depth) {
+    vec4 world = inverse_vp * vec4(ndc, depth, 1.0);
+    return world.xyz / world.w;
+}
+
+// 1 on a line, fading to 0 a pixel away from it
+float grid_line(vec2 coord, float spacing) {
+    vec2 scaled = coord / spacing;
+    vec2 pixels = abs(fract(scaled - 0.5) - 0.5) / fwidth(scaled);
+
+    return 1.0 - min(min(pixels.x, pixels.y), 1.0);
+}
+
+float axis_line(float coord) {
+    return 1.0 - min(abs(coord) / fwidth(coord), 1.0);
+}
+
+void main() {
+    vec2 ndc = vs_in.tex_coord * 2.0 - 1.0;
+
+    vec3 near_point = unproject(ndc, -1.0);
+    vec3 far_point = unproject(ndc, 1.0);
+
+    // Where the ray through this pixel crosses y = 0
+    float t = -near_point.y / (far_point.y - near_point.y);
+    if (t <= 0.0) {
+        discard;
+    }
+
+    vec3 position = near_point + t * (far_point - near_point);
+
+    vec4 clip_position = vp * vec4(position, 1.0);
+    gl_FragDepth = clip_position.z / clip_position.w * 0.5 + 0.5;
+
+    float minor = grid_line(position.xz, MINOR_SPACING);
+    float major = grid_line(position.xz, MAJOR_SPACING);
+    float x_axis = axis_line(position.z);
+    float z_axis = axis_line(position.x);
+
+    vec3 color = MINOR_COLOR;
+    float alpha = minor * 0.5;
+
+    if (major > 0.0) {
+        color = MAJOR_COLOR;
+        alpha = max(alpha, major);
+    }
+
+    if (x_axis > 0.0) {
+        color = X_AXIS_COLOR;
+        alpha = max(alpha, x_axis);
+    } else if (z_axis > 0.0) {
+        color = Z_AXIS_COLOR;
+        alpha = max(alpha, z_axis);
+    }
+
+    alpha *= 1.0 - smoothstep(fade_distance * 0.25, fade_distance, distance(camera_position, position));
+
+    if (alpha <= 0.0) {
+        discard;
+    }
+
+    out_color = vec4(color, alpha);
+    // Zero alpha, so that blending leaves the emission behind the grid alone
+    out_emissive = vec4(0.0);
+}
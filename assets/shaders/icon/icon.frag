@@ -0,0 +1,20 @@
+#version 450
+
+layout (location = 0) out vec4 out_color;
+layout (location = 1) out vec4 out_emissive;
+
+in VS_OUT {
+    vec3 color;
+    vec2 corner;
+} vs_in;
+
+void main() {
+    // Round icons
+    if (length(vs_in.corner) > 1.0) {
+        discard;
+    }
+
+    out_color = vec4(vs_in.color, 1.0);
+    // Lights glow
+    out_emissive = vec4(vs_in.color, 1.0);
+}
@@ -0,0 +1,28 @@
+#version 450
+
+// Quad
+layout (location = 0) in vec2 corner;
+
+// Instance
+layout (location = 1) in vec3 light_translation;
+layout (location = 2) in vec3 light_color;
+
+out VS_OUT {
+    vec3 color;
+    vec2 corner;
+} vs_out;
+
+uniform mat4 vp;
+// Both in pixels
+uniform float icon_size;
+uniform vec2 viewport_size;
+
+void main() {
+    vs_out.color = light_color;
+    vs_out.corner = corner;
+
+    gl_Position = vp * vec4(light_translation, 1.0);
+    // Offset after projecting, scaled by w to undo the perspective divide, so that the icon is the same size on
+    // screen at any distance
+    gl_Position.xy += corner * icon_size / viewport_size * gl_Position.w;
+}
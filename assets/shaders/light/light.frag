@@ -1,11 +0,0 @@
-#version 450
-
-layout (location = 0) out vec4 out_color;
-
-in VS_OUT {
-    vec3 color;
-} vs_in;
-
-void main() {
-    out_color = vec4(vs_in.color, 1.0);
-}
\ No newline at end of file
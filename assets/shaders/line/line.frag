@@ -1,11 +1,19 @@
 #version 450
 
 layout (location = 0) out vec4 out_color;
+layout (location = 1) out vec4 out_emissive;
 
 in VS_OUT {
     vec3 color;
+    float edge_distance;
+    float half_width;
 } vs_in;
 
 void main() {
-    out_color = vec4(vs_in.color, 1.0);
-}
\ No newline at end of file
+    // Fully opaque inside the line, fading out over the pixel either side of its edge
+    float coverage = clamp(vs_in.half_width + 0.5 - abs(vs_in.edge_distance), 0.0, 1.0);
+
+    out_color = vec4(vs_in.color, coverage);
+    // Zero alpha, so that blending leaves the emission behind the line alone
+    out_emissive = vec4(0.0);
+}
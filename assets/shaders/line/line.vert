@@ -1,16 +1,61 @@
 #version 450
 
-layout (location = 0) in vec3 position;
-layout (location = 1) in vec3 color;
+// Quad
+layout (location = 0) in vec2 corner;
+
+// Instance
+layout (location = 1) in vec3 start;
+layout (location = 2) in vec3 end;
+layout (location = 3) in vec3 color;
+layout (location = 4) in float width;
 
 out VS_OUT {
     vec3 color;
+    // Signed distance in pixels from the centre of the line
+    float edge_distance;
+    float half_width;
 } vs_out;
 
 uniform mat4 vp;
+// Size of the target in pixels
+uniform vec2 viewport_size;
+
+// Points closer than this to the plane of the camera are treated as being on it
+const float MIN_W = 0.0001;
+
+// Half a pixel either side of the line is faded out to anti-alias it
+const float FEATHER = 1.0;
 
 void main() {
+    vec4 clip_start = vp * vec4(start, 1.0);
+    vec4 clip_end = vp * vec4(end, 1.0);
+
+    // Move an end behind the camera onto the camera plane, otherwise its projection flips to the other side of
+    // the screen
+    if (clip_start.w < MIN_W) {
+        clip_start = mix(clip_start, clip_end, (MIN_W - clip_start.w) / (clip_end.w - clip_start.w));
+    } else if (clip_end.w < MIN_W) {
+        clip_end = mix(clip_end, clip_start, (MIN_W - clip_end.w) / (clip_start.w - clip_end.w));
+    }
+
+    vec2 screen_start = clip_start.xy / clip_start.w * viewport_size * 0.5;
+    vec2 screen_end = clip_end.xy / clip_end.w * viewport_size * 0.5;
+
+    vec2 direction = screen_end - screen_start;
+    direction = length(direction) > 0.0 ? normalize(direction) : vec2(1.0, 0.0);
+    vec2 normal = vec2(-direction.y, direction.x);
+
+    float half_width = width * 0.5 + FEATHER;
+
+    // The ends are extended by half the width as well, so that the segments of a polyline overlap at the joints
+    vec2 offset = normal * corner.y * half_width + direction * (corner.x * 2.0 - 1.0) * half_width;
+
+    vec4 position = mix(clip_start, clip_end, corner.x);
+    position.xy += offset / (viewport_size * 0.5) * position.w;
+
     vs_out.color = color;
+    vs_out.edge_distance = corner.y * half_width;
+    vs_out.half_width = width * 0.5;
 
-    gl_Position = vp * vec4(position, 1.0);
-}
\ No newline at end of file
+    gl_Position = position;
+}
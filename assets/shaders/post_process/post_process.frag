@@ -0,0 +1,57 @@
+#version 450
+
+in VS_OUT {
+    vec2 tex_coord;
+} vs_in;
+
+layout (location = 0) out vec4 out_color;
+
+uniform sampler2D scene;
+uniform sampler2D bloom;
+uniform float bloom_intensity;
+uniform float exposure;
+// 0 = none, 1 = Reinhard, 2 = ACES
+uniform int tonemapping;
+uniform float gamma;
+uniform float vignette;
+// When false the scene was lit in sRGB space and is already encoded
+uniform bool srgb;
+// Linear RGB as seen with a color vision deficiency, the identity when no filter is used
+uniform mat3 colorblind_simulation;
+
+// Krzysztof Narkowicz's fit of the ACES filmic curve
+vec3 aces(vec3 color) {
+    const float a = 2.51;
+    const float b = 0.03;
+    const float c = 2.43;
+    const float d = 0.59;
+    const float e = 0.14;
+
+    return clamp((color * (a * color + b)) / (color * (c * color + d) + e), 0.0, 1.0);
+}
+
+void main() {
+    vec3 color = texture(scene, vs_in.tex_coord).rgb;
+    color += texture(bloom, vs_in.tex_coord).rgb * bloom_intensity;
+    color *= exposure;
+
+    if (tonemapping == 1) {
+        color = color / (color + 1.0);
+    } else if (tonemapping == 2) {
+        color = aces(color);
+    }
+
+    // Squared distance from the centre is 0.5 in the corners
+    vec2 from_centre = vs_in.tex_coord - 0.5;
+    color *= 1.0 - vignette * 2.0 * dot(from_centre, from_centre);
+
+    // Daltonize, moving the detail lost to the deficiency into channels which can still be seen
+    vec3 lost = color - colorblind_simulation * color;
+    color += mat3(0.0, 0.7, 0.7, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0) * lost;
+
+    if (srgb) {
+        color = pow(max(color, 0.0), vec3(1.0 / gamma));
+    }
+
+    out_color = vec4(color, 1.0);
+}
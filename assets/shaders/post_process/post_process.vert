@@ -0,0 +1,13 @@
+#version 450
+
+out VS_OUT {
+    vec2 tex_coord;
+} vs_out;
+
+void main() {
+    // A single triangle which covers the whole screen, (0, 0), (2, 0), (0, 2)
+    vec2 position = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2);
+
+    vs_out.tex_coord = position;
+    gl_Position = vec4(position * 2.0 - 1.0, 0.0, 1.0);
+}
@@ -0,0 +1,22 @@
+#version 450
+
+layout (location = 0) out vec4 out_color;
+layout (location = 1) out vec4 out_emissive;
+
+in VS_OUT {
+    vec3 tex_coord;
+} vs_in;
+
+uniform vec3 horizon_color;
+uniform vec3 zenith_color;
+
+void main() {
+    float elevation = max(normalize(vs_in.tex_coord).y, 0.0);
+
+    // Light crosses more of the atmosphere towards the horizon, so the sky washes out quickly as it nears it
+    vec3 color = mix(horizon_color, zenith_color, sqrt(elevation));
+
+    // Zero alpha like the clear it replaces, so that the sky does not glow
+    out_color = vec4(color, 0.0);
+    out_emissive = vec4(0.0);
+}
@@ -1,13 +1,25 @@
 #version 450
 
 layout (location = 0) out vec4 out_color;
+layout (location = 1) out vec4 out_emissive;
 
 in VS_OUT {
     vec3 tex_coord;
 } vs_in;
 
 uniform samplerCube skybox;
+// The skybox images are sRGB encoded
+uniform bool srgb;
+
+vec3 srgb_to_linear(vec3 color) {
+    return mix(color / 12.92, pow((color + 0.055) / 1.055, vec3(2.4)), step(0.04045, color));
+}
 
 void main() {
     out_color = texture(skybox, vs_in.tex_coord);
+    if (srgb) {
+        out_color.rgb = srgb_to_linear(out_color.rgb);
+    }
+
+    out_emissive = vec4(0.0);
 }
\ No newline at end of file
@@ -1,9 +1,30 @@
 #version 450
 
 layout (location = 0) out vec4 out_color;
+layout (location = 1) out vec4 out_emissive;
 
 uniform vec3 camera_position;
 
+// 0 = none, 1 = linear, 2 = exponential
+uniform int fog_falloff;
+uniform vec3 fog_color;
+uniform float fog_start;
+uniform float fog_end;
+uniform float fog_density;
+
+// How much of a surface this far from the camera can be seen through the fog
+float fog_visibility(float view_distance) {
+    if (fog_falloff == 1) {
+        // Equal distances would divide by zero, this makes the fog a hard edge instead
+        float fog_depth = max(fog_end - fog_start, 0.0001);
+        return clamp((fog_end - view_distance) / fog_depth, 0.0, 1.0);
+    } else if (fog_falloff == 2) {
+        return exp(-fog_density * view_distance);
+    }
+
+    return 1.0;
+}
+
 in VS_OUT {
     vec3 position;
     vec3 normal;
@@ -26,4 +47,7 @@ void main() {
 
     // Combine
     out_color = diffuse_color * vec4((ambient + diffuse), 1.0);
+    out_color.rgb = mix(fog_color, out_color.rgb, fog_visibility(distance(camera_position, vs_in.position)));
+    // Hide the emission of anything behind the terrain
+    out_emissive = vec4(0.0, 0.0, 0.0, 1.0);
 }
\ No newline at end of file
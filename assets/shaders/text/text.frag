@@ -0,0 +1,16 @@
+#version 450
+
+layout (location = 0) out vec4 out_color;
+
+in VS_OUT {
+    vec2 tex_coord;
+} vs_in;
+
+uniform sampler2D atlas;
+uniform vec3 color;
+
+void main() {
+    float coverage = texture(atlas, vs_in.tex_coord).r;
+
+    out_color = vec4(color, coverage);
+}
\ No newline at end of file
@@ -0,0 +1,16 @@
+#version 450
+
+layout (location = 0) in vec2 position;
+layout (location = 1) in vec2 tex_coord;
+
+out VS_OUT {
+    vec2 tex_coord;
+} vs_out;
+
+uniform mat4 projection;
+
+void main() {
+    vs_out.tex_coord = tex_coord;
+
+    gl_Position = projection * vec4(position, 0.0, 1.0);
+}
\ No newline at end of file
@@ -0,0 +1,154 @@
+use color_eyre::Result;
+use itertools::Itertools;
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use uuid::Uuid;
+
+/// The directory assets are stored in, relative to the working directory
+const ASSETS_ROOT: &str = "assets";
+const DATABASE_PATH: &str = "assets/assets.json";
+
+static ASSET_DATABASE: LazyLock<Mutex<AssetDatabase>> =
+    LazyLock::new(|| Mutex::new(AssetDatabase::open(PathBuf::from(DATABASE_PATH))));
+
+#[derive(Serialize, Deserialize)]
+struct AssetEntry {
+    #[serde(with = "crate::serde::uuid")]
+    guid: Uuid,
+    path: PathBuf,
+}
+
+/// Stable ids for asset files, so that scenes still find their assets after the project has moved. The paths are
+/// stored relative to the assets directory
+pub struct AssetDatabase {
+    file: PathBuf,
+    paths: HashMap<Uuid, PathBuf>,
+    guids: HashMap<PathBuf, Uuid>,
+}
+
+impl AssetDatabase {
+    fn open(file: PathBuf) -> Self {
+        let entries = match std::fs::read_to_string(&file) {
+            Ok(json) => serde_json::from_str::<Vec<AssetEntry>>(&json).unwrap_or_else(|err| {
+                error!("Could not parse the asset database {:?}: {}", file, err);
+                Vec::new()
+            }),
+            // Nothing has been saved yet
+            Err(_) => Vec::new(),
+        };
+
+        Self {
+            file,
+            paths: entries
+                .iter()
+                .map(|entry| (entry.guid, entry.path.clone()))
+                .collect(),
+            guids: entries
+                .into_iter()
+                .map(|entry| (entry.path, entry.guid))
+                .collect(),
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        let entries = self
+            .paths
+            .iter()
+            .map(|(guid, path)| AssetEntry {
+                guid: *guid,
+                path: path.clone(),
+            })
+            // Sorted so that the file only changes where assets are added
+            .sorted_by(|a, b| a.path.cmp(&b.path))
+            .collect_vec();
+
+        std::fs::write(&self.file, serde_json::to_string_pretty(&entries)?)?;
+
+        Ok(())
+    }
+
+    /// Assign an id to the asset at `path` if it does not have one yet. Called when the asset is imported, so that
+    /// saving a scene only has to look the ids up. Generated assets have no path, so are not given one
+    pub fn register(path: &Path) {
+        if path.as_os_str().is_empty() {
+            return;
+        }
+
+        let relative = relative_to_root(path);
+        let mut database = ASSET_DATABASE.lock().unwrap();
+
+        if database.guids.contains_key(&relative) {
+            return;
+        }
+
+        let guid = Uuid::new_v4();
+        database.paths.insert(guid, relative.clone());
+        database.guids.insert(relative, guid);
+
+        if let Err(err) = database.save() {
+            error!("Could not save the asset database: {:?}", err);
+        }
+    }
+
+    /// The id of the asset at `path`, or None if it was never registered
+    pub fn guid(path: &Path) -> Option<Uuid> {
+        ASSET_DATABASE
+            .lock()
+            .unwrap()
+            .guids
+            .get(&relative_to_root(path))
+            .copied()
+    }
+
+    /// Where the asset with the id `guid` is, relative to the working directory
+    pub fn path(guid: Uuid) -> Option<PathBuf> {
+        ASSET_DATABASE
+            .lock()
+            .unwrap()
+            .paths
+            .get(&guid)
+            .map(|relative| Path::new(ASSETS_ROOT).join(relative))
+    }
+
+    /// Find an asset saved by its path before ids were assigned, which may be an absolute path from another machine
+    pub fn locate(path: &Path) -> PathBuf {
+        if path.exists() {
+            return path.to_path_buf();
+        }
+
+        Path::new(ASSETS_ROOT).join(relative_to_root(path))
+    }
+}
+
+/// `path` relative to the assets directory. Paths from other copies of the project cannot be found on disk, so are
+/// taken from their last `assets` component instead. Anything outside of it is left as it is
+fn relative_to_root(path: &Path) -> PathBuf {
+    // Handles `..` and symlinks, and directories inside the assets directory which are also named `assets`
+    let canonical = std::fs::canonicalize(ASSETS_ROOT)
+        .ok()
+        .zip(std::fs::canonicalize(path).ok());
+    if let Some((root, path)) = canonical {
+        if let Ok(relative) = path.strip_prefix(root) {
+            return relative.to_path_buf();
+        }
+    }
+
+    let components = path.components().collect_vec();
+
+    match components
+        .iter()
+        .rposition(|component| component.as_os_str() == ASSETS_ROOT)
+    {
+        Some(root) => components[root + 1..].iter().collect(),
+        None => {
+            warn!(
+                "{:?} is outside of the assets directory, so will not be found if the project moves",
+                path
+            );
+            path.to_path_buf()
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use crate::texture::Texture2D;
+use cgmath::Vector3;
+use glium::implement_vertex;
+use petgraph::stable_graph::NodeIndex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// A textured quad which always faces the camera, such as a health bar or a pickup marker
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Billboard {
+    pub name: String,
+    pub texture: Arc<Texture2D>,
+    /// Node which the billboard follows. The billboard is hidden if the node has been removed
+    pub parent: Option<NodeIndex>,
+    /// Position relative to the translation of the parent, or in world space without one
+    pub offset: Vector3<f32>,
+    /// Width and height in world units
+    pub size: [f32; 2],
+}
+
+impl Billboard {
+    pub fn new(texture: Arc<Texture2D>, parent: Option<NodeIndex>, offset: Vector3<f32>) -> Self {
+        Self {
+            name: texture
+                .path
+                .file_stem()
+                .map_or("Billboard".to_owned(), |stem| {
+                    stem.to_string_lossy().into_owned()
+                }),
+            texture,
+            parent,
+            offset,
+            size: [1.0, 1.0],
+        }
+    }
+}
+
+/// A corner of the quad drawn for each billboard, from -1 to 1 on both axes
+#[derive(Copy, Clone)]
+pub struct BillboardCorner {
+    pub corner: [f32; 2],
+}
+implement_vertex!(BillboardCorner, corner);
+
+pub const BILLBOARD_QUAD: [BillboardCorner; 6] = [
+    BillboardCorner {
+        corner: [-1.0, -1.0],
+    },
+    BillboardCorner {
+        corner: [1.0, -1.0],
+    },
+    BillboardCorner { corner: [1.0, 1.0] },
+    BillboardCorner {
+        corner: [-1.0, -1.0],
+    },
+    BillboardCorner { corner: [1.0, 1.0] },
+    BillboardCorner {
+        corner: [-1.0, 1.0],
+    },
+];
+
+#[derive(Copy, Clone)]
+pub struct BillboardInstance {
+    /// Centre of the billboard in world space
+    pub center: [f32; 3],
+    pub size: [f32; 2],
+}
+implement_vertex!(BillboardInstance, center, size);
@@ -0,0 +1,97 @@
+use color_eyre::Result;
+use glium::glutin::surface::WindowSurface;
+use glium::vertex::VertexBufferSlice;
+use glium::{Display, Vertex, VertexBuffer};
+use std::rc::Rc;
+
+/// Frames of buffers kept, so that the buffers being written were last read by the GPU this many frames ago
+const FRAMES_IN_FLIGHT: usize = 3;
+
+/// A buffer handed out by a `VertexBufferPool`, of which only the first `len` vertices were written
+pub struct PooledVertexBuffer<T: Copy> {
+    buffer: Rc<VertexBuffer<T>>,
+    len: usize,
+}
+
+impl<T: Copy> PooledVertexBuffer<T> {
+    pub fn slice(&self) -> VertexBufferSlice<T> {
+        self.buffer.slice(0..self.len).unwrap()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Dynamic vertex buffers reused across frames rather than allocated for every upload. Each frame writes to its
+/// own set of buffers, which rotate so that uploads do not wait on draws from the frames before
+pub struct VertexBufferPool<T: Copy> {
+    frames: [Vec<Rc<VertexBuffer<T>>>; FRAMES_IN_FLIGHT],
+    frame: usize,
+    used: usize,
+}
+
+impl<T: Vertex + Copy + Send + 'static> VertexBufferPool<T> {
+    pub fn new() -> Self {
+        Self {
+            frames: Default::default(),
+            frame: 0,
+            used: 0,
+        }
+    }
+
+    /// Start handing out the buffers of the next frame, must be called once at the start of every frame
+    pub fn next_frame(&mut self) {
+        self.frame = (self.frame + 1) % FRAMES_IN_FLIGHT;
+        self.used = 0;
+    }
+
+    /// Write `vertices` into the next free buffer of this frame, growing it if they do not fit
+    pub fn upload(
+        &mut self,
+        vertices: &[T],
+        display: &Display<WindowSurface>,
+    ) -> Result<PooledVertexBuffer<T>> {
+        let buffers = &mut self.frames[self.frame];
+
+        let fits = buffers
+            .get(self.used)
+            .is_some_and(|buffer| buffer.len() >= vertices.len());
+
+        if !fits {
+            // Rounded up so that buffers which grow slowly are not reallocated every frame
+            let buffer = Rc::new(VertexBuffer::empty_dynamic(
+                display,
+                vertices.len().next_power_of_two(),
+            )?);
+
+            if self.used < buffers.len() {
+                buffers[self.used] = buffer;
+            } else {
+                buffers.push(buffer);
+            }
+        }
+
+        let buffer = buffers[self.used].clone();
+        self.used += 1;
+
+        // Orphan the previous contents so the driver can hand back fresh storage instead of waiting on the GPU
+        buffer.invalidate();
+        buffer.slice(0..vertices.len()).unwrap().write(vertices);
+
+        Ok(PooledVertexBuffer {
+            buffer,
+            len: vertices.len(),
+        })
+    }
+}
+
+impl<T: Vertex + Copy + Send + 'static> Default for VertexBufferPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
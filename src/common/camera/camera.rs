@@ -1,15 +1,65 @@
 use crate::input::Input;
 use cgmath::{Matrix4, Point3, Rad};
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_NEAR: f32 = 0.01;
+pub const DEFAULT_FAR: f32 = 100.0;
+/// Vertical field of view in radians
+pub const DEFAULT_FOV: f32 = std::f32::consts::FRAC_PI_2;
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq)]
+pub enum Projection {
+    #[default]
+    Perspective,
+    /// Parallel lines stay parallel, so distances can be compared anywhere on screen
+    Orthographic,
+}
 
 pub trait Camera {
     fn update(&mut self, input: &Input, deltatime: f32);
     fn set_aspect_ratio(&mut self, ratio: f32);
+    fn set_clipping_planes(&mut self, near: f32, far: f32);
+    /// Set the vertical field of view in radians
+    fn set_fov(&mut self, fov: f32);
 
     fn position(&self) -> Point3<f32>;
     fn projection(&self) -> Matrix4<f32>;
     fn view(&self) -> Matrix4<f32>;
+    fn near(&self) -> f32;
+    fn far(&self) -> f32;
+    fn fov(&self) -> f32;
+}
+
+pub fn perspective(fov: f32, ratio: f32, near: f32, far: f32) -> Matrix4<f32> {
+    cgmath::perspective(Rad(fov), ratio, near, far)
+}
+
+/// An orthographic projection showing `half_height` world units above and below the centre of the screen
+pub fn orthographic(half_height: f32, ratio: f32, near: f32, far: f32) -> Matrix4<f32> {
+    let half_width = half_height * ratio;
+
+    cgmath::ortho(
+        -half_width,
+        half_width,
+        -half_height,
+        half_height,
+        near,
+        far,
+    )
+}
+
+pub(crate) fn default_near() -> f32 {
+    DEFAULT_NEAR
+}
+
+pub(crate) fn default_far() -> f32 {
+    DEFAULT_FAR
+}
+
+pub(crate) fn default_fov() -> f32 {
+    DEFAULT_FOV
 }
 
-pub fn perspective(ratio: f32) -> Matrix4<f32> {
-    cgmath::perspective(Rad(std::f32::consts::FRAC_PI_2), ratio, 0.01, 100.0)
+pub(crate) fn default_aspect_ratio() -> f32 {
+    1920.0 / 1080.0
 }
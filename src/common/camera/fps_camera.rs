@@ -12,6 +12,14 @@ pub struct FpsCamera {
     position: Point3<f32>,
     yaw: f32,
     pitch: f32,
+    #[serde(default = "camera::default_aspect_ratio")]
+    aspect_ratio: f32,
+    #[serde(default = "camera::default_near")]
+    near: f32,
+    #[serde(default = "camera::default_far")]
+    far: f32,
+    #[serde(default = "camera::default_fov")]
+    fov: f32,
     looking_direction: Vector3<f32>,
 }
 
@@ -19,12 +27,25 @@ impl FpsCamera {
     fn new(position: Point3<f32>, ratio: f32) -> Self {
         Self {
             position,
-            projection: camera::perspective(ratio),
+            projection: camera::perspective(
+                camera::DEFAULT_FOV,
+                ratio,
+                camera::DEFAULT_NEAR,
+                camera::DEFAULT_FAR,
+            ),
             yaw: 0.0,
             pitch: std::f32::consts::FRAC_PI_2,
+            aspect_ratio: ratio,
+            near: camera::DEFAULT_NEAR,
+            far: camera::DEFAULT_FAR,
+            fov: camera::DEFAULT_FOV,
             looking_direction: Vector3::unit_x(),
         }
     }
+
+    fn update_projection(&mut self) {
+        self.projection = camera::perspective(self.fov, self.aspect_ratio, self.near, self.far);
+    }
 }
 
 impl Camera for FpsCamera {
@@ -75,7 +96,19 @@ impl Camera for FpsCamera {
     }
 
     fn set_aspect_ratio(&mut self, ratio: f32) {
-        self.projection = camera::perspective(ratio);
+        self.aspect_ratio = ratio;
+        self.update_projection();
+    }
+
+    fn set_clipping_planes(&mut self, near: f32, far: f32) {
+        self.near = near;
+        self.far = far;
+        self.update_projection();
+    }
+
+    fn set_fov(&mut self, fov: f32) {
+        self.fov = fov;
+        self.update_projection();
     }
 
     fn position(&self) -> Point3<f32> {
@@ -93,6 +126,18 @@ impl Camera for FpsCamera {
     fn projection(&self) -> Matrix4<f32> {
         self.projection
     }
+
+    fn near(&self) -> f32 {
+        self.near
+    }
+
+    fn far(&self) -> f32 {
+        self.far
+    }
+
+    fn fov(&self) -> f32 {
+        self.fov
+    }
 }
 
 impl Default for FpsCamera {
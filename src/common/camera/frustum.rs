@@ -0,0 +1,42 @@
+use crate::colliders::BoundingSphere;
+use cgmath::{InnerSpace, Matrix4, Vector4};
+
+/// The volume a camera can see, as six planes facing inwards
+pub struct Frustum {
+    planes: [Vector4<f32>; 6],
+}
+
+impl Frustum {
+    pub fn from_view_projection(view_projection: &Matrix4<f32>) -> Self {
+        // Matrices are stored as columns
+        let row = |i: usize| {
+            Vector4::new(
+                view_projection.x[i],
+                view_projection.y[i],
+                view_projection.z[i],
+                view_projection.w[i],
+            )
+        };
+
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+
+        let planes = [w + x, w - x, w + y, w - y, w + z, w - z].map(|plane| {
+            // Normalised so that plane distances are in world units
+            let length = plane.truncate().magnitude();
+            if length > f32::EPSILON {
+                plane / length
+            } else {
+                plane
+            }
+        });
+
+        Self { planes }
+    }
+
+    /// Whether any of the sphere might be visible
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.truncate().dot(sphere.center) + plane.w >= -sphere.radius)
+    }
+}
@@ -1,7 +1,9 @@
 mod camera;
 mod fps_camera;
+mod frustum;
 mod orbital_camera;
 
-pub use camera::Camera;
+pub use camera::{Camera, Projection, DEFAULT_FOV};
 pub use fps_camera::FpsCamera;
-pub use orbital_camera::OrbitalCamera;
+pub use frustum::Frustum;
+pub use orbital_camera::{OrbitalCamera, ViewAxis};
@@ -1,11 +1,33 @@
 use crate::input::Input;
 
 use crate::camera::camera;
-use crate::camera::camera::Camera;
+use crate::camera::camera::{Camera, Projection};
+use crate::maths;
 use cgmath::{Matrix4, Point3, Vector3};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize)]
+/// Views looking straight along an axis, towards the target
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ViewAxis {
+    /// Looking down the y axis
+    Top,
+    /// Looking down the z axis
+    Front,
+    /// Looking down the x axis
+    Right,
+}
+
+/// Half of the remaining zoom and rotation is caught up with in this many seconds
+const SMOOTHING_HALF_LIFE: f32 = 0.04;
+
+#[derive(Copy, Clone)]
+struct Orbit {
+    radius: f32,
+    yaw: f32,
+    pitch: f32,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct OrbitalCamera {
     pub target: Point3<f32>,
     pub radius: f32,
@@ -14,6 +36,19 @@ pub struct OrbitalCamera {
     position: Point3<f32>,
     yaw: f32,
     pitch: f32,
+    #[serde(default = "camera::default_aspect_ratio")]
+    aspect_ratio: f32,
+    #[serde(default = "camera::default_near")]
+    near: f32,
+    #[serde(default = "camera::default_far")]
+    far: f32,
+    #[serde(default = "camera::default_fov")]
+    fov: f32,
+    #[serde(default)]
+    projection_mode: Projection,
+    /// Where the camera actually is, following the radius, yaw and pitch above smoothly
+    #[serde(skip)]
+    smoothed: Option<Orbit>,
 }
 
 impl OrbitalCamera {
@@ -22,12 +57,48 @@ impl OrbitalCamera {
             position: Point3::new(radius, 0.0, 0.0),
             radius,
             target,
-            projection: camera::perspective(ratio),
+            projection: camera::perspective(
+                camera::DEFAULT_FOV,
+                ratio,
+                camera::DEFAULT_NEAR,
+                camera::DEFAULT_FAR,
+            ),
             yaw: 0.0,
             pitch: std::f32::consts::FRAC_PI_2,
+            aspect_ratio: ratio,
+            near: camera::DEFAULT_NEAR,
+            far: camera::DEFAULT_FAR,
+            fov: camera::DEFAULT_FOV,
+            projection_mode: Projection::default(),
+            smoothed: None,
         }
     }
 
+    /// Catch up with the zoom and rotation, which must be done every frame
+    pub fn smooth(&mut self, deltatime: f32) {
+        let current = self.orbit();
+
+        self.smoothed = Some(Orbit {
+            radius: maths::damp(current.radius, self.radius, SMOOTHING_HALF_LIFE, deltatime),
+            yaw: maths::damp(current.yaw, self.yaw, SMOOTHING_HALF_LIFE, deltatime),
+            pitch: maths::damp(current.pitch, self.pitch, SMOOTHING_HALF_LIFE, deltatime),
+        });
+
+        self.update_position();
+
+        if self.projection_mode == Projection::Orthographic {
+            self.update_projection();
+        }
+    }
+
+    fn orbit(&self) -> Orbit {
+        self.smoothed.unwrap_or(Orbit {
+            radius: self.radius,
+            yaw: self.yaw,
+            pitch: self.pitch,
+        })
+    }
+
     pub fn update_zoom(&mut self, input: &Input) {
         let mouse_wheel_offset = input.mouse_wheel_offset();
 
@@ -35,16 +106,60 @@ impl OrbitalCamera {
         self.radius -= mouse_wheel_offset * zoom_step;
 
         self.update_position();
+
+        // Orthographic projections zoom by showing less of the scene, rather than by moving closer
+        if self.projection_mode == Projection::Orthographic {
+            self.update_projection();
+        }
+    }
+
+    pub fn projection_mode(&self) -> Projection {
+        self.projection_mode
+    }
+
+    pub fn set_projection_mode(&mut self, projection_mode: Projection) {
+        self.projection_mode = projection_mode;
+        self.update_projection();
+    }
+
+    pub fn look_along(&mut self, axis: ViewAxis) {
+        // Looking straight down would leave no direction for the top of the screen
+        let epsilon = 0.001;
+
+        (self.yaw, self.pitch) = match axis {
+            ViewAxis::Top => (std::f32::consts::FRAC_PI_2, epsilon),
+            ViewAxis::Front => (std::f32::consts::FRAC_PI_2, std::f32::consts::FRAC_PI_2),
+            ViewAxis::Right => (0.0, std::f32::consts::FRAC_PI_2),
+        };
+
+        self.update_position();
     }
 
     fn update_position(&mut self) {
+        let Orbit { radius, yaw, pitch } = self.orbit();
+
         self.position = self.target
             + Vector3::new(
-                self.radius * self.pitch.sin() * self.yaw.cos(),
-                self.radius * self.pitch.cos(),
-                self.radius * self.pitch.sin() * self.yaw.sin(),
+                radius * pitch.sin() * yaw.cos(),
+                radius * pitch.cos(),
+                radius * pitch.sin() * yaw.sin(),
             );
     }
+
+    fn update_projection(&mut self) {
+        self.projection = match self.projection_mode {
+            Projection::Perspective => {
+                camera::perspective(self.fov, self.aspect_ratio, self.near, self.far)
+            }
+            // Frames the target the same as the perspective projection does
+            Projection::Orthographic => camera::orthographic(
+                self.orbit().radius * (self.fov / 2.0).tan(),
+                self.aspect_ratio,
+                self.near,
+                self.far,
+            ),
+        };
+    }
 }
 
 impl Camera for OrbitalCamera {
@@ -54,7 +169,12 @@ impl Camera for OrbitalCamera {
         let offset = input.device_offset() * deltatime * sensitivity;
 
         self.yaw += offset.x;
-        self.yaw %= 2.0 * std::f32::consts::PI;
+        let wrapped_yaw = self.yaw % (2.0 * std::f32::consts::PI);
+        // Otherwise the smoothing would spin all the way back around
+        if let Some(smoothed) = self.smoothed.as_mut() {
+            smoothed.yaw += wrapped_yaw - self.yaw;
+        }
+        self.yaw = wrapped_yaw;
 
         self.pitch -= offset.y;
         let epsilon = 0.000000001;
@@ -64,7 +184,19 @@ impl Camera for OrbitalCamera {
     }
 
     fn set_aspect_ratio(&mut self, ratio: f32) {
-        self.projection = camera::perspective(ratio);
+        self.aspect_ratio = ratio;
+        self.update_projection();
+    }
+
+    fn set_clipping_planes(&mut self, near: f32, far: f32) {
+        self.near = near;
+        self.far = far;
+        self.update_projection();
+    }
+
+    fn set_fov(&mut self, fov: f32) {
+        self.fov = fov;
+        self.update_projection();
     }
 
     fn position(&self) -> Point3<f32> {
@@ -78,6 +210,18 @@ impl Camera for OrbitalCamera {
     fn projection(&self) -> Matrix4<f32> {
         self.projection
     }
+
+    fn near(&self) -> f32 {
+        self.near
+    }
+
+    fn far(&self) -> f32 {
+        self.far
+    }
+
+    fn fov(&self) -> f32 {
+        self.fov
+    }
 }
 
 impl Default for OrbitalCamera {
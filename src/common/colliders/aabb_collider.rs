@@ -1,16 +1,56 @@
 use crate::colliders::collider::Collider;
 use cgmath::Vector3;
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct AABBCollider {
     pub min: Vector3<f32>,
     pub max: Vector3<f32>,
 }
 
+impl AABBCollider {
+    /// The smallest box containing every point, or `None` if there are none
+    pub fn from_points(points: &[Vector3<f32>]) -> Option<Self> {
+        let first = *points.first()?;
+
+        Some(points.iter().fold(
+            Self {
+                min: first,
+                max: first,
+            },
+            |aabb, point| Self {
+                min: Vector3::new(
+                    aabb.min.x.min(point.x),
+                    aabb.min.y.min(point.y),
+                    aabb.min.z.min(point.z),
+                ),
+                max: Vector3::new(
+                    aabb.max.x.max(point.x),
+                    aabb.max.y.max(point.y),
+                    aabb.max.z.max(point.z),
+                ),
+            },
+        ))
+    }
+
+    pub fn center(&self) -> Vector3<f32> {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn corners(&self) -> [Vector3<f32>; 8] {
+        [0, 1, 2, 3, 4, 5, 6, 7].map(|i| {
+            Vector3::new(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            )
+        })
+    }
+}
+
 impl Collider for AABBCollider {
     fn colliding(&self, other: &AABBCollider) -> bool {
         self.min.x <= other.max.x
-            && self.min.x >= other.min.x
+            && self.max.x >= other.min.x
             && self.min.y <= other.max.y
             && self.max.y >= other.min.y
             && self.min.z <= other.max.z
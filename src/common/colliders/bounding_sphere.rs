@@ -0,0 +1,66 @@
+use crate::colliders::AABBCollider;
+use crate::transform::Transform;
+use cgmath::{InnerSpace, MetricSpace, Vector3};
+
+#[derive(Clone, Debug)]
+pub struct BoundingSphere {
+    pub center: Vector3<f32>,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// A sphere around the centre of the points' bounding box, which is close to the smallest for most models.
+    /// `None` if there are no points
+    pub fn from_points(points: &[Vector3<f32>]) -> Option<Self> {
+        let center = AABBCollider::from_points(points)?.center();
+
+        let radius = points
+            .iter()
+            .map(|point| (point - center).magnitude())
+            .fold(0.0, f32::max);
+
+        Some(Self { center, radius })
+    }
+
+    pub fn transformed(&self, transform: &Transform) -> Self {
+        Self {
+            center: transform.translation + transform.rotation * (self.center * transform.scale),
+            radius: self.radius * transform.scale.abs(),
+        }
+    }
+
+    pub fn intersects(&self, other: &BoundingSphere) -> bool {
+        self.center.distance(other.center) <= self.radius + other.radius
+    }
+
+    pub fn intersects_aabb(&self, aabb: &AABBCollider) -> bool {
+        let closest = Vector3::new(
+            self.center.x.clamp(aabb.min.x, aabb.max.x),
+            self.center.y.clamp(aabb.min.y, aabb.max.y),
+            self.center.z.clamp(aabb.min.z, aabb.max.z),
+        );
+
+        self.center.distance(closest) <= self.radius
+    }
+
+    /// The smallest sphere containing both
+    pub fn union(&self, other: &BoundingSphere) -> Self {
+        let offset = other.center - self.center;
+        let distance = offset.magnitude();
+
+        if distance + other.radius <= self.radius {
+            return self.clone();
+        }
+
+        if distance + self.radius <= other.radius {
+            return other.clone();
+        }
+
+        let radius = (distance + self.radius + other.radius) / 2.0;
+
+        Self {
+            center: self.center + offset * ((radius - self.radius) / distance),
+            radius,
+        }
+    }
+}
@@ -1,5 +1,3 @@
-use cgmath::Vector3;
-
 pub trait Collider {
     fn colliding(&self, other: &Self) -> bool;
 }
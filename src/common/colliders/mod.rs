@@ -1,2 +1,9 @@
 pub mod aabb_collider;
+pub mod bounding_sphere;
 pub mod collider;
+pub mod ray;
+
+pub use aabb_collider::AABBCollider;
+pub use bounding_sphere::BoundingSphere;
+pub use collider::Collider;
+pub use ray::Ray;
@@ -0,0 +1,81 @@
+use crate::colliders::{AABBCollider, BoundingSphere};
+use crate::transform::Transform;
+use cgmath::{InnerSpace, Matrix4, SquareMatrix, Vector3, Vector4};
+
+#[derive(Clone, Debug)]
+pub struct Ray {
+    pub origin: Vector3<f32>,
+    /// Not always normalised, hits are measured in multiples of it
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    /// The ray from the near plane to the far plane through a point on screen, where (0, 0) is the top left and
+    /// (1, 1) the bottom right. Works for both perspective and orthographic projections
+    pub fn from_screen(position: (f32, f32), view_projection: &Matrix4<f32>) -> Option<Self> {
+        let inverse = view_projection.invert()?;
+
+        let x = position.0 * 2.0 - 1.0;
+        let y = 1.0 - position.1 * 2.0;
+
+        let near = inverse * Vector4::new(x, y, -1.0, 1.0);
+        let far = inverse * Vector4::new(x, y, 1.0, 1.0);
+        let near = near.truncate() / near.w;
+        let far = far.truncate() / far.w;
+
+        Some(Self {
+            origin: near,
+            direction: (far - near).normalize(),
+        })
+    }
+
+    /// The same ray with `transform` applied. Distances along the ray are unchanged, so a hit found in the
+    /// model space of an instance is the same distance along the world space ray
+    pub fn transformed(&self, transform: &Transform) -> Self {
+        Self {
+            origin: transform.translation + transform.rotation * (self.origin * transform.scale),
+            direction: transform.rotation * (self.direction * transform.scale),
+        }
+    }
+
+    /// How far along the ray it first enters the sphere, or zero if it starts inside
+    pub fn intersect_sphere(&self, sphere: &BoundingSphere) -> Option<f32> {
+        let offset = self.origin - sphere.center;
+
+        // Solving |origin + t * direction - center| = radius for t
+        let a = self.direction.magnitude2();
+        let b = offset.dot(self.direction);
+        let c = offset.magnitude2() - sphere.radius * sphere.radius;
+
+        let discriminant = b * b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let root = discriminant.sqrt();
+        if (-b + root) / a < 0.0 {
+            // The sphere is behind the ray
+            return None;
+        }
+
+        Some(((-b - root) / a).max(0.0))
+    }
+
+    /// How far along the ray it first enters the box, or zero if it starts inside
+    pub fn intersect_aabb(&self, aabb: &AABBCollider) -> Option<f32> {
+        let mut near = 0.0_f32;
+        let mut far = f32::INFINITY;
+
+        // The overlap of the ranges where the ray is between each pair of planes
+        for axis in 0..3 {
+            let inverse = 1.0 / self.direction[axis];
+            let a = (aabb.min[axis] - self.origin[axis]) * inverse;
+            let b = (aabb.max[axis] - self.origin[axis]) * inverse;
+
+            near = near.max(a.min(b));
+            far = far.min(a.max(b));
+        }
+
+        (near <= far).then_some(near)
+    }
+}
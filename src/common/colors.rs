@@ -1,5 +1,5 @@
 use cgmath::{Vector3, Vector4};
-use palette::{FromColor, IntoColor, Lch, ShiftHue, Srgb};
+use palette::{FromColor, IntoColor, Lch, LinSrgb, ShiftHue, Srgb};
 
 pub type Color = Lch;
 
@@ -8,6 +8,7 @@ pub trait ColorExt {
     fn from_named(color: Srgb<u8>) -> Self;
     fn to_rgb_vector4(self) -> Vector4<f32>;
     fn to_rgb_vector3(self) -> Vector3<f32>;
+    fn to_linear_rgb_vector4(self) -> Vector4<f32>;
 }
 
 impl ColorExt for Color {
@@ -31,4 +32,10 @@ impl ColorExt for Color {
 
         Vector3::new(rgb.red, rgb.green, rgb.blue)
     }
+
+    fn to_linear_rgb_vector4(self) -> Vector4<f32> {
+        let rgb: LinSrgb = self.into_color();
+
+        Vector4::new(rgb.red, rgb.green, rgb.blue, 1.0)
+    }
 }
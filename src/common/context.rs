@@ -3,6 +3,7 @@ use std::fs;
 use color_eyre::Result;
 use glium::backend::glutin::SimpleWindowBuilder;
 use glium::glutin::surface::WindowSurface;
+use glium::program::ProgramCreationInput;
 use glium::{Display, Program};
 use winit::dpi::LogicalPosition;
 use winit::event_loop::EventLoop;
@@ -55,15 +56,54 @@ pub fn new_program(
     fragment_source_path: &str,
     geometry_source_path: Option<&str>,
     display: &Display<WindowSurface>,
+) -> Result<Program> {
+    program_from_files(
+        vertex_source_path,
+        fragment_source_path,
+        geometry_source_path,
+        false,
+        display,
+    )
+}
+
+/// Create a program which writes sRGB encoded colors itself, so OpenGL must not encode them again
+pub fn new_srgb_output_program(
+    vertex_source_path: &str,
+    fragment_source_path: &str,
+    geometry_source_path: Option<&str>,
+    display: &Display<WindowSurface>,
+) -> Result<Program> {
+    program_from_files(
+        vertex_source_path,
+        fragment_source_path,
+        geometry_source_path,
+        true,
+        display,
+    )
+}
+
+fn program_from_files(
+    vertex_source_path: &str,
+    fragment_source_path: &str,
+    geometry_source_path: Option<&str>,
+    outputs_srgb: bool,
+    display: &Display<WindowSurface>,
 ) -> Result<Program> {
     let vertex_source = fs::read_to_string(vertex_source_path)?;
     let fragment_source = fs::read_to_string(fragment_source_path)?;
     let geometry_source = geometry_source_path.map(|path| fs::read_to_string(path).unwrap());
 
-    Ok(Program::from_source(
+    Ok(Program::new(
         display,
-        vertex_source.as_str(),
-        fragment_source.as_str(),
-        geometry_source.as_deref(),
+        ProgramCreationInput::SourceCode {
+            vertex_shader: vertex_source.as_str(),
+            tessellation_control_shader: None,
+            tessellation_evaluation_shader: None,
+            geometry_shader: geometry_source.as_deref(),
+            fragment_shader: fragment_source.as_str(),
+            transform_feedback_varyings: None,
+            outputs_srgb,
+            uses_point_size: false,
+        },
     )?)
 }
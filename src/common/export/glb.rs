@@ -0,0 +1,553 @@
+use crate::camera::Camera;
+use crate::colors::ColorExt;
+use crate::models::model_vertex::ModelVertex;
+use crate::models::{Material, Model, ModelInstance};
+use crate::scene::Scene;
+use crate::texture::Texture2D;
+use cgmath::{Matrix4, SquareMatrix};
+use color_eyre::Result;
+use gltf::binary::{Glb, Header};
+use gltf::json;
+use gltf::json::accessor::{ComponentType, GenericComponentType, Type};
+use gltf::json::buffer::{Stride, Target};
+use gltf::json::extensions::scene::khr_lights_punctual;
+use gltf::json::validation::Checked::Valid;
+use gltf::json::validation::USize64;
+use gltf::json::Index;
+use itertools::Itertools;
+use log::{info, warn};
+use petgraph::stable_graph::NodeIndex;
+use petgraph::Direction;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::mem::{offset_of, size_of, size_of_val};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Write the scene out as a binary glTF, so that it can be opened in other tools. Meshes are read again from
+/// their model files and textures are embedded
+pub fn export_scene(scene: &Scene) -> Result<Vec<u8>> {
+    info!("Exporting scene \"{}\"...", scene.title);
+
+    let mut exporter = Exporter::default();
+    let mut scene_nodes = exporter.export_graph(scene)?;
+
+    scene_nodes.push(exporter.export_camera(scene));
+    scene_nodes.extend(exporter.export_lights(scene));
+
+    let root_scene = exporter.root.push(json::Scene {
+        extensions: Default::default(),
+        extras: Default::default(),
+        name: Some(scene.title.clone()),
+        nodes: scene_nodes,
+    });
+    exporter.root.scene = Some(root_scene);
+
+    exporter.finish()
+}
+
+/// Write a single primitive out as a binary glTF of one node, so that geometry made in the editor can be loaded like
+/// any model file
+pub fn export_mesh(name: &str, vertices: &[ModelVertex], indices: &[u32]) -> Result<Vec<u8>> {
+    let mut exporter = Exporter::default();
+
+    let primitive = exporter.primitive(vertices, indices, None);
+    let mesh = exporter.root.push(json::Mesh {
+        extensions: Default::default(),
+        extras: Default::default(),
+        name: Some(name.to_owned()),
+        primitives: vec![primitive],
+        weights: None,
+    });
+
+    let node = exporter.root.push(json::Node {
+        mesh: Some(mesh),
+        name: Some(name.to_owned()),
+        ..Default::default()
+    });
+
+    let root_scene = exporter.root.push(json::Scene {
+        extensions: Default::default(),
+        extras: Default::default(),
+        name: None,
+        nodes: vec![node],
+    });
+    exporter.root.scene = Some(root_scene);
+
+    exporter.finish()
+}
+
+#[derive(Default)]
+struct Exporter {
+    root: json::Root,
+    /// Contents of the single binary chunk
+    buffer: Vec<u8>,
+    /// glTF materials belong to primitives rather than nodes, so each model needs a mesh per material
+    meshes: HashMap<(Arc<Model>, Option<Material>), Index<json::Mesh>>,
+    textures: HashMap<PathBuf, Index<json::Texture>>,
+}
+
+impl Exporter {
+    fn export_graph(&mut self, scene: &Scene) -> Result<Vec<Index<json::Node>>> {
+        let graph = &scene.graph;
+        let mut nodes = HashMap::<NodeIndex, Index<json::Node>>::new();
+
+        for node_index in graph.node_indices() {
+            let model_instance = &graph[node_index];
+            let mesh = self.mesh(model_instance)?;
+
+            // Transforms are stored in world space, but glTF nodes are relative to their parent
+            let world = Matrix4::from(model_instance.transform.clone());
+            let local = match graph
+                .neighbors_directed(node_index, Direction::Incoming)
+                .next()
+            {
+                Some(parent) => {
+                    Matrix4::from(graph[parent].transform.clone())
+                        .invert()
+                        .unwrap_or(Matrix4::identity())
+                        * world
+                }
+                None => world,
+            };
+
+            let node = self.root.push(json::Node {
+                matrix: Some(flatten(local)),
+                mesh: Some(mesh),
+                name: Some(model_instance.name.clone()),
+                ..Default::default()
+            });
+
+            nodes.insert(node_index, node);
+        }
+
+        for (node_index, node) in nodes.iter() {
+            let children = graph
+                .neighbors_directed(*node_index, Direction::Outgoing)
+                .map(|child| nodes[&child])
+                .collect_vec();
+
+            if !children.is_empty() {
+                self.root.nodes[node.value()].children = Some(children);
+            }
+        }
+
+        Ok(graph
+            .node_indices()
+            .filter(|node_index| {
+                graph
+                    .neighbors_directed(*node_index, Direction::Incoming)
+                    .next()
+                    .is_none()
+            })
+            .map(|node_index| nodes[&node_index])
+            .collect_vec())
+    }
+
+    fn export_camera(&mut self, scene: &Scene) -> Index<json::Node> {
+        let camera = self.root.push(json::Camera {
+            name: Some("Camera".to_owned()),
+            type_: Valid(json::camera::Type::Perspective),
+            orthographic: None,
+            perspective: Some(json::camera::Perspective {
+                aspect_ratio: None,
+                yfov: scene.camera.fov(),
+                zfar: Some(scene.camera.far()),
+                znear: scene.camera.near(),
+                extensions: Default::default(),
+                extras: Default::default(),
+            }),
+            extensions: Default::default(),
+            extras: Default::default(),
+        });
+
+        self.root.push(json::Node {
+            camera: Some(camera),
+            matrix: Some(flatten(
+                scene.camera.view().invert().unwrap_or(Matrix4::identity()),
+            )),
+            name: Some("Camera".to_owned()),
+            ..Default::default()
+        })
+    }
+
+    /// Lights are written with the KHR_lights_punctual extension
+    fn export_lights(&mut self, scene: &Scene) -> Vec<Index<json::Node>> {
+        let lights = scene.world_lights();
+        if lights.is_empty() {
+            return vec![];
+        }
+
+        let mut extension = json::extensions::root::Root::default();
+        extension.khr_lights_punctual = Some(json::extensions::root::KhrLightsPunctual {
+            lights: lights
+                .iter()
+                .map(|light| {
+                    let color = light.color.to_linear_rgb_vector4();
+
+                    khr_lights_punctual::Light {
+                        color: [color.x, color.y, color.z],
+                        extensions: Default::default(),
+                        extras: Default::default(),
+                        intensity: light.intensity,
+                        name: None,
+                        range: light.range,
+                        spot: None,
+                        type_: Valid(khr_lights_punctual::Type::Point),
+                    }
+                })
+                .collect_vec(),
+        });
+
+        self.root.extensions = Some(extension);
+        self.root
+            .extensions_used
+            .push("KHR_lights_punctual".to_owned());
+
+        lights
+            .iter()
+            .enumerate()
+            .map(|(index, light)| {
+                let mut extension = json::extensions::scene::Node::default();
+                extension.khr_lights_punctual = Some(khr_lights_punctual::KhrLightsPunctual {
+                    light: Index::new(index as u32),
+                });
+
+                self.root.push(json::Node {
+                    extensions: Some(extension),
+                    name: Some("Light".to_owned()),
+                    translation: Some(light.position.into()),
+                    ..Default::default()
+                })
+            })
+            .collect_vec()
+    }
+
+    fn mesh(&mut self, model_instance: &ModelInstance) -> Result<Index<json::Mesh>> {
+        let key = (
+            model_instance.model.clone(),
+            model_instance.material.clone(),
+        );
+
+        if let Some(mesh) = self.meshes.get(&key) {
+            return Ok(*mesh);
+        }
+
+        let material = model_instance
+            .material
+            .as_ref()
+            .map(|material| self.material(material))
+            .transpose()?;
+
+        let primitives = model_instance
+            .model
+            .read_primitives()?
+            .iter()
+            .map(|primitive| self.primitive(&primitive.vertices, &primitive.indices, material))
+            .collect_vec();
+
+        let mesh = self.root.push(json::Mesh {
+            extensions: Default::default(),
+            extras: Default::default(),
+            name: model_instance
+                .model
+                .path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned()),
+            primitives,
+            weights: None,
+        });
+
+        self.meshes.insert(key, mesh);
+
+        Ok(mesh)
+    }
+
+    fn primitive(
+        &mut self,
+        vertices: &[ModelVertex],
+        indices: &[u32],
+        material: Option<Index<json::Material>>,
+    ) -> json::mesh::Primitive {
+        // Safety: ModelVertex is made only of f32s, so it has no padding
+        let vertex_bytes = unsafe {
+            std::slice::from_raw_parts(vertices.as_ptr() as *const u8, size_of_val(vertices))
+        };
+        let vertex_view = self.push_view(
+            vertex_bytes,
+            Some(size_of::<ModelVertex>()),
+            Target::ArrayBuffer,
+        );
+
+        // glTF requires the bounds of positions
+        let (min, max) = vertices.iter().fold(
+            ([f32::MAX; 3], [f32::MIN; 3]),
+            |(mut min, mut max), vertex| {
+                for axis in 0..3 {
+                    min[axis] = min[axis].min(vertex.position[axis]);
+                    max[axis] = max[axis].max(vertex.position[axis]);
+                }
+
+                (min, max)
+            },
+        );
+
+        let mut attribute = |offset, type_, bounds: Option<([f32; 3], [f32; 3])>| {
+            self.root.push(json::Accessor {
+                buffer_view: Some(vertex_view),
+                byte_offset: Some(USize64::from(offset)),
+                count: USize64::from(vertices.len()),
+                component_type: Valid(GenericComponentType(ComponentType::F32)),
+                extensions: Default::default(),
+                extras: Default::default(),
+                type_: Valid(type_),
+                min: bounds.map(|(min, _)| json::Value::from(min.to_vec())),
+                max: bounds.map(|(_, max)| json::Value::from(max.to_vec())),
+                name: None,
+                normalized: false,
+                sparse: None,
+            })
+        };
+
+        let attributes = BTreeMap::from([
+            (
+                Valid(json::mesh::Semantic::Positions),
+                attribute(
+                    offset_of!(ModelVertex, position),
+                    Type::Vec3,
+                    Some((min, max)),
+                ),
+            ),
+            (
+                Valid(json::mesh::Semantic::Normals),
+                attribute(offset_of!(ModelVertex, normal), Type::Vec3, None),
+            ),
+            (
+                Valid(json::mesh::Semantic::TexCoords(0)),
+                attribute(offset_of!(ModelVertex, tex_coord), Type::Vec2, None),
+            ),
+            (
+                Valid(json::mesh::Semantic::Tangents),
+                attribute(offset_of!(ModelVertex, tangent), Type::Vec4, None),
+            ),
+            (
+                Valid(json::mesh::Semantic::Colors(0)),
+                attribute(offset_of!(ModelVertex, color), Type::Vec4, None),
+            ),
+        ]);
+
+        // The smallest index type which can address every vertex
+        let (index_bytes, index_type) = if vertices.len() <= u16::MAX as usize + 1 {
+            (
+                indices
+                    .iter()
+                    .flat_map(|index| (*index as u16).to_le_bytes())
+                    .collect_vec(),
+                ComponentType::U16,
+            )
+        } else {
+            (
+                indices
+                    .iter()
+                    .flat_map(|index| index.to_le_bytes())
+                    .collect_vec(),
+                ComponentType::U32,
+            )
+        };
+        let index_view = self.push_view(&index_bytes, None, Target::ElementArrayBuffer);
+
+        let indices = self.root.push(json::Accessor {
+            buffer_view: Some(index_view),
+            byte_offset: Some(USize64(0)),
+            count: USize64::from(indices.len()),
+            component_type: Valid(GenericComponentType(index_type)),
+            extensions: Default::default(),
+            extras: Default::default(),
+            type_: Valid(Type::Scalar),
+            min: None,
+            max: None,
+            name: None,
+            normalized: false,
+            sparse: None,
+        });
+
+        json::mesh::Primitive {
+            attributes,
+            extensions: Default::default(),
+            extras: Default::default(),
+            indices: Some(indices),
+            material,
+            mode: Valid(json::mesh::Mode::Triangles),
+            targets: None,
+        }
+    }
+
+    fn material(&mut self, material: &Material) -> Result<Index<json::Material>> {
+        let texture_info = |index| json::texture::Info {
+            index,
+            tex_coord: 0,
+            extensions: Default::default(),
+            extras: Default::default(),
+        };
+
+        let base_color = self.texture(&material.diffuse)?;
+
+        let normal = match &material.normal {
+            Some(normal) => self.texture(normal)?,
+            None => None,
+        };
+
+        let emissive = match &material.emissive {
+            Some(emissive) => self.texture(emissive)?,
+            None => None,
+        };
+
+        Ok(self.root.push(json::Material {
+            alpha_mode: Valid(if material.transparent {
+                json::material::AlphaMode::Blend
+            } else {
+                json::material::AlphaMode::Opaque
+            }),
+            pbr_metallic_roughness: json::material::PbrMetallicRoughness {
+                base_color_texture: base_color.map(texture_info),
+                ..Default::default()
+            },
+            normal_texture: normal.map(|index| json::material::NormalTexture {
+                index,
+                scale: 1.0,
+                tex_coord: 0,
+                extensions: Default::default(),
+                extras: Default::default(),
+            }),
+            emissive_texture: emissive.map(texture_info),
+            emissive_factor: json::material::EmissiveFactor(if emissive.is_some() {
+                [material.emissive_strength.0.min(1.0); 3]
+            } else {
+                [0.0; 3]
+            }),
+            ..Default::default()
+        }))
+    }
+
+    /// Textures are embedded so that the file does not depend on where the scene's assets live. Only PNG and JPEG
+    /// files can be, so generated textures, images embedded in models and other formats are left out
+    fn texture(&mut self, texture: &Texture2D) -> Result<Option<Index<json::Texture>>> {
+        let path = texture.path.as_path();
+
+        if path.as_os_str().is_empty() {
+            warn!("Leaving out a generated texture, which has no file to embed");
+            return Ok(None);
+        }
+
+        if texture.image.is_some() {
+            warn!("Leaving out an image embedded in {:?}", path);
+            return Ok(None);
+        }
+
+        if let Some(texture) = self.textures.get(path) {
+            return Ok(Some(*texture));
+        }
+
+        let mime_type = match path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_lowercase)
+            .as_deref()
+        {
+            Some("png") => "image/png",
+            Some("jpg" | "jpeg") => "image/jpeg",
+            _ => {
+                warn!(
+                    "Leaving out {:?}, as only PNG and JPEG textures can be embedded",
+                    path
+                );
+                return Ok(None);
+            }
+        };
+
+        let image_view = self.push_view(&std::fs::read(path)?, None, Target::ArrayBuffer);
+        // Images must not have a target
+        self.root.buffer_views[image_view.value()].target = None;
+
+        let image = self.root.push(json::Image {
+            buffer_view: Some(image_view),
+            mime_type: Some(json::image::MimeType(mime_type.to_owned())),
+            name: None,
+            uri: None,
+            extensions: Default::default(),
+            extras: Default::default(),
+        });
+
+        let texture = self.root.push(json::Texture {
+            name: None,
+            sampler: None,
+            source: image,
+            extensions: Default::default(),
+            extras: Default::default(),
+        });
+
+        self.textures.insert(path.to_path_buf(), texture);
+
+        Ok(Some(texture))
+    }
+
+    fn push_view(
+        &mut self,
+        bytes: &[u8],
+        byte_stride: Option<usize>,
+        target: Target,
+    ) -> Index<json::buffer::View> {
+        let byte_offset = self.buffer.len();
+
+        self.buffer.extend_from_slice(bytes);
+        // Every view must start on a 4 byte boundary
+        self.buffer.resize(self.buffer.len().next_multiple_of(4), 0);
+
+        self.root.push(json::buffer::View {
+            buffer: Index::new(0),
+            byte_length: USize64::from(bytes.len()),
+            byte_offset: Some(USize64::from(byte_offset)),
+            byte_stride: byte_stride.map(Stride),
+            extensions: Default::default(),
+            extras: Default::default(),
+            name: None,
+            target: Some(Valid(target)),
+        })
+    }
+
+    fn finish(mut self) -> Result<Vec<u8>> {
+        self.root.push(json::Buffer {
+            byte_length: USize64::from(self.buffer.len()),
+            extensions: Default::default(),
+            extras: Default::default(),
+            name: None,
+            uri: None,
+        });
+
+        let mut json = json::serialize::to_vec(&self.root)?;
+        // Chunks are padded to 4 bytes, JSON with spaces
+        json.resize(json.len().next_multiple_of(4), b' ');
+
+        // Header, then the length and type of each chunk before its contents
+        let length = 12 + 8 + json.len() + 8 + self.buffer.len();
+
+        let glb = Glb {
+            header: Header {
+                magic: *b"glTF",
+                version: 2,
+                length: length as u32,
+            },
+            json: Cow::Owned(json),
+            bin: Some(Cow::Owned(self.buffer)),
+        };
+
+        Ok(glb.to_vec()?)
+    }
+}
+
+/// Column major, as glTF expects
+fn flatten(matrix: Matrix4<f32>) -> [f32; 16] {
+    let columns: [[f32; 4]; 4] = matrix.into();
+
+    columns.concat().try_into().unwrap()
+}
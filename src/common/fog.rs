@@ -0,0 +1,59 @@
+use crate::colors::{Color, ColorExt};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq)]
+pub enum FogFalloff {
+    #[default]
+    None,
+    /// Thickens evenly from the start distance until it hides everything at the end distance
+    Linear,
+    /// Thickens quickly close to the camera and never fully hides anything
+    Exponential,
+}
+
+impl FogFalloff {
+    pub const ALL: [FogFalloff; 3] = [Self::None, Self::Linear, Self::Exponential];
+
+    /// Must match the branches in the fog function of the shaders
+    pub fn shader_index(self) -> i32 {
+        match self {
+            Self::None => 0,
+            Self::Linear => 1,
+            Self::Exponential => 2,
+        }
+    }
+}
+
+impl Display for FogFalloff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::Linear => write!(f, "Linear"),
+            Self::Exponential => write!(f, "Exponential"),
+        }
+    }
+}
+
+/// Blends surfaces into a color the further they are from the camera
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Fog {
+    pub falloff: FogFalloff,
+    pub color: Color,
+    pub start: f32,
+    pub end: f32,
+    pub density: f32,
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Self {
+            falloff: FogFalloff::default(),
+            color: Color::from_named(palette::named::GRAY),
+            start: 10.0,
+            end: 50.0,
+            density: 0.05,
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Finds asset files which have changed on disk by polling their modification times
+#[derive(Default)]
+pub struct AssetWatcher {
+    modified: HashMap<PathBuf, SystemTime>,
+}
+
+impl AssetWatcher {
+    /// The files out of `paths` which were modified since the last call. Files seen for the first time are not
+    /// reported, as there is nothing to compare them against
+    pub fn changed(&mut self, paths: impl IntoIterator<Item = PathBuf>) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+
+        for path in paths {
+            // Files which are mid-write or have been deleted are checked again next time
+            let Ok(modified) = std::fs::metadata(&path).and_then(|metadata| metadata.modified())
+            else {
+                continue;
+            };
+
+            if let Some(previous) = self.modified.insert(path.clone(), modified) {
+                if previous != modified {
+                    changed.push(path);
+                }
+            }
+        }
+
+        changed
+    }
+}
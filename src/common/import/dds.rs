@@ -0,0 +1,189 @@
+use glium::texture::CompressedFormat;
+use log::info;
+use std::path::{Path, PathBuf};
+
+const MAGIC: &[u8; 4] = b"DDS ";
+/// The magic number followed by the header
+const HEADER_SIZE: usize = 128;
+/// The extra header of files whose format is given as a DXGI format
+const DX10_HEADER_SIZE: usize = 20;
+/// Set in the header flags when the mipmap count is filled in
+const MIPMAP_COUNT_FLAG: u32 = 0x20000;
+
+#[derive(Debug, Clone)]
+pub enum DdsLoadError {
+    DdsNotFound(PathBuf),
+    /// The file does not start with a DDS header
+    InvalidHeader,
+    /// The four character code or DXGI format of a format other than BC1-7
+    UnsupportedFormat(String),
+    /// The mipmap levels need more bytes than the file has
+    MissingData {
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl std::fmt::Display for DdsLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DdsNotFound(path) => write!(f, "The DDS file {:?} could not be found", path),
+            Self::InvalidHeader => write!(f, "The DDS header is invalid"),
+            Self::UnsupportedFormat(format) => write!(
+                f,
+                "The DDS format {} is not supported, only BC1-7 are",
+                format
+            ),
+            Self::MissingData { expected, found } => write!(
+                f,
+                "The DDS file should have {} bytes of texture data, found {}",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DdsLoadError {}
+
+/// A block compressed texture, which is uploaded as it is rather than being decoded
+#[derive(Debug, Clone)]
+pub struct Dds {
+    pub width: u32,
+    pub height: u32,
+    pub format: CompressedFormat,
+    /// The full size image first, then each mipmap
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl Dds {
+    pub fn parse(bytes: &[u8]) -> Result<Self, DdsLoadError> {
+        if bytes.len() < HEADER_SIZE || &bytes[..4] != MAGIC || read_u32(bytes, 4) != 124 {
+            return Err(DdsLoadError::InvalidHeader);
+        }
+
+        let flags = read_u32(bytes, 8);
+        let height = read_u32(bytes, 12);
+        let width = read_u32(bytes, 16);
+        let mipmap_count = if flags & MIPMAP_COUNT_FLAG != 0 {
+            read_u32(bytes, 28).max(1)
+        } else {
+            1
+        };
+        let four_cc = &bytes[84..88];
+
+        let (format, data_start) = if four_cc == b"DX10" {
+            if bytes.len() < HEADER_SIZE + DX10_HEADER_SIZE {
+                return Err(DdsLoadError::InvalidHeader);
+            }
+
+            (
+                dxgi_format(read_u32(bytes, HEADER_SIZE))?,
+                HEADER_SIZE + DX10_HEADER_SIZE,
+            )
+        } else {
+            (four_cc_format(four_cc)?, HEADER_SIZE)
+        };
+
+        let mut levels = Vec::new();
+        let mut offset = data_start;
+
+        for level in 0..mipmap_count {
+            let size = level_size(width >> level, height >> level, format);
+
+            let data = bytes
+                .get(offset..offset + size)
+                .ok_or(DdsLoadError::MissingData {
+                    expected: offset + size - data_start,
+                    found: bytes.len() - data_start,
+                })?;
+
+            levels.push(data.to_vec());
+            offset += size;
+
+            // Files may list more mipmaps than a 1x1 level needs
+            if width >> level <= 1 && height >> level <= 1 {
+                break;
+            }
+        }
+
+        Ok(Self {
+            width,
+            height,
+            format,
+            levels,
+        })
+    }
+}
+
+pub fn load_dds(path: &Path) -> Result<Dds, DdsLoadError> {
+    info!("Loading DDS {:?}", path);
+
+    let bytes = std::fs::read(path).map_err(|_| DdsLoadError::DdsNotFound(path.to_path_buf()))?;
+
+    Dds::parse(&bytes)
+}
+
+/// Is the file at `path` a DDS file, rather than an image to be decoded
+pub fn is_dds(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("dds"))
+}
+
+/// Bytes taken by one level of the given size, which is stored in blocks of 4x4 pixels
+pub fn level_size(width: u32, height: u32, format: CompressedFormat) -> usize {
+    let blocks_wide = width.max(1).div_ceil(4) as usize;
+    let blocks_high = height.max(1).div_ceil(4) as usize;
+
+    let block_size = match format {
+        CompressedFormat::S3tcDxt1NoAlpha
+        | CompressedFormat::S3tcDxt1Alpha
+        | CompressedFormat::RgtcFormatU
+        | CompressedFormat::RgtcFormatI => 8,
+        _ => 16,
+    };
+
+    blocks_wide * blocks_high * block_size
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn four_cc_format(four_cc: &[u8]) -> Result<CompressedFormat, DdsLoadError> {
+    Ok(match four_cc {
+        b"DXT1" => CompressedFormat::S3tcDxt1Alpha,
+        b"DXT3" => CompressedFormat::S3tcDxt3Alpha,
+        b"DXT5" => CompressedFormat::S3tcDxt5Alpha,
+        b"ATI1" | b"BC4U" => CompressedFormat::RgtcFormatU,
+        b"BC4S" => CompressedFormat::RgtcFormatI,
+        b"ATI2" | b"BC5U" => CompressedFormat::RgtcFormatUU,
+        b"BC5S" => CompressedFormat::RgtcFormatII,
+        _ => {
+            return Err(DdsLoadError::UnsupportedFormat(
+                String::from_utf8_lossy(four_cc).into_owned(),
+            ))
+        }
+    })
+}
+
+/// The sRGB formats are read as linear, as the shaders decode sRGB themselves
+fn dxgi_format(dxgi_format: u32) -> Result<CompressedFormat, DdsLoadError> {
+    Ok(match dxgi_format {
+        71 | 72 => CompressedFormat::S3tcDxt1Alpha,
+        74 | 75 => CompressedFormat::S3tcDxt3Alpha,
+        77 | 78 => CompressedFormat::S3tcDxt5Alpha,
+        80 => CompressedFormat::RgtcFormatU,
+        81 => CompressedFormat::RgtcFormatI,
+        83 => CompressedFormat::RgtcFormatUU,
+        84 => CompressedFormat::RgtcFormatII,
+        95 => CompressedFormat::BptcUnsignedFloat3,
+        96 => CompressedFormat::BptcSignedFloat3,
+        98 | 99 => CompressedFormat::BptcUnorm4,
+        _ => {
+            return Err(DdsLoadError::UnsupportedFormat(format!(
+                "DXGI {}",
+                dxgi_format
+            )))
+        }
+    })
+}
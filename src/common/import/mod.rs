@@ -1 +1,2 @@
+pub mod dds;
 pub mod image;
@@ -1,17 +1,29 @@
 pub mod app;
+pub mod assets;
+pub mod billboard;
+pub mod buffer_pool;
 pub mod camera;
+pub mod colliders;
 pub mod colors;
 pub mod context;
 pub mod debug;
+pub mod export;
+pub mod fog;
+pub mod hot_reload;
 pub mod import;
 pub mod input;
 pub mod light;
 pub mod line;
 pub mod maths;
+pub mod metrics;
 pub mod models;
+pub mod post_processing;
+pub mod profiler;
+pub mod render_target;
 pub mod renderer;
 pub mod scene;
 pub mod serde;
 pub mod terrain;
+pub mod text;
 pub mod texture;
 pub mod transform;
@@ -1,19 +1,40 @@
 use crate::colors::{Color, ColorExt};
 use cgmath::Point3;
 use glium::implement_vertex;
+use petgraph::stable_graph::NodeIndex;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Light {
+    /// Node which the light follows. The light is removed along with the node
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<NodeIndex>,
+    /// Position relative to the translation of the parent, or in world space without one
     pub position: Point3<f32>,
     pub color: Color,
+    /// Multiplies the color, values above 1 can make surfaces bloom
+    #[serde(default = "default_intensity")]
+    pub intensity: f32,
+    /// Distance at which the light has faded out completely, or unlimited if None
+    #[serde(default)]
+    pub range: Option<f32>,
+    #[serde(skip)]
+    pub selected: bool,
+}
+
+fn default_intensity() -> f32 {
+    1.0
 }
 
 impl Default for Light {
     fn default() -> Self {
         Self {
+            parent: None,
             position: Point3::new(0.0, 0.0, 0.0),
             color: Color::from_named(palette::named::WHITE),
+            intensity: default_intensity(),
+            range: None,
+            selected: false,
         }
     }
 }
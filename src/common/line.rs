@@ -1,6 +1,6 @@
 use cgmath::Point3;
 use glium::implement_vertex;
-use log::warn;
+use itertools::Itertools;
 use palette::Srgb;
 
 #[derive(Clone)]
@@ -8,15 +8,12 @@ pub struct Line {
     pub p1: Point3<f32>,
     pub p2: Point3<f32>,
     pub color: Srgb,
-    pub width: u8,
+    /// In pixels, so the line is the same width however far away it is
+    pub width: f32,
 }
 
 impl Line {
-    pub fn new(p1: Point3<f32>, p2: Point3<f32>, color: Srgb, width: u8) -> Self {
-        if width > 10 {
-            warn!("Line width can only be integer values between 1 and 10.");
-        }
-
+    pub fn new(p1: Point3<f32>, p2: Point3<f32>, color: Srgb, width: f32) -> Self {
         Self {
             p1,
             p2,
@@ -26,10 +23,71 @@ impl Line {
     }
 }
 
+/// Connected lines through a list of points, such as a trajectory preview
+#[derive(Clone)]
+pub struct Polyline {
+    pub points: Vec<Point3<f32>>,
+    pub color: Srgb,
+    /// In pixels
+    pub width: f32,
+}
+
+impl Polyline {
+    pub fn new(points: Vec<Point3<f32>>, color: Srgb, width: f32) -> Self {
+        Self {
+            points,
+            color,
+            width,
+        }
+    }
+
+    pub fn segments(&self) -> impl Iterator<Item = Line> + '_ {
+        self.points
+            .iter()
+            .tuple_windows()
+            .map(|(p1, p2)| Line::new(*p1, *p2, self.color, self.width))
+    }
+}
+
+/// A corner of the quad which each line is expanded into. x runs along the line from 0 to 1, and y across it from
+/// -1 to 1
 #[derive(Copy, Clone)]
-pub struct LinePoint {
-    pub position: [f32; 3],
+pub struct LineCorner {
+    pub corner: [f32; 2],
+}
+implement_vertex!(LineCorner, corner);
+
+pub const LINE_QUAD: [LineCorner; 6] = [
+    LineCorner {
+        corner: [0.0, -1.0],
+    },
+    LineCorner {
+        corner: [1.0, -1.0],
+    },
+    LineCorner { corner: [1.0, 1.0] },
+    LineCorner {
+        corner: [0.0, -1.0],
+    },
+    LineCorner { corner: [1.0, 1.0] },
+    LineCorner { corner: [0.0, 1.0] },
+];
+
+#[derive(Copy, Clone)]
+pub struct LineInstance {
+    pub start: [f32; 3],
+    pub end: [f32; 3],
     pub color: [f32; 3],
+    pub width: f32,
 }
+implement_vertex!(LineInstance, start, end, color, width);
 
-implement_vertex!(LinePoint, position, color);
+impl From<Line> for LineInstance {
+    fn from(line: Line) -> Self {
+        Self {
+            start: <[f32; 3]>::from(line.p1),
+            end: <[f32; 3]>::from(line.p2),
+            color: [line.color.red, line.color.green, line.color.blue],
+            width: line.width,
+        }
+    }
+}
@@ -10,10 +10,51 @@ pub fn linear_map(
     ((x - original_min) * (target_max - target_min) / (original_max - original_min)) + target_min
 }
 
+/// Move `current` towards `target` by the same amount in a given time whatever the frame rate, covering half of the
+/// remaining distance every `half_life` seconds
+pub fn damp(current: f32, target: f32, half_life: f32, deltatime: f32) -> f32 {
+    if half_life <= 0.0 {
+        return target;
+    }
+
+    target + (current - target) * (-deltatime / half_life).exp2()
+}
+
+/// A critically damped spring, which settles on `target` as quickly as it can without overshooting. Unlike [`damp`]
+/// it eases in as well as out, as `velocity` is carried between frames
+pub fn spring_damp(
+    current: f32,
+    target: f32,
+    velocity: &mut f32,
+    half_life: f32,
+    deltatime: f32,
+) -> f32 {
+    if half_life <= 0.0 {
+        *velocity = 0.0;
+        return target;
+    }
+
+    // Chosen so that half of the offset is left after roughly `half_life`
+    let damping = 2.0 * std::f32::consts::LN_2 / half_life;
+
+    let offset = current - target;
+    let j = *velocity + offset * damping;
+    let decay = (-damping * deltatime).exp();
+
+    *velocity = decay * (*velocity - j * damping * deltatime);
+
+    target + decay * (offset + j * deltatime)
+}
+
 pub fn raw_matrix(matrix: Matrix4<f32>) -> [[f32; 4]; 4] {
     <[[f32; 4]; 4]>::from(matrix)
 }
 
+/// Maps pixel coordinates, with the origin at the top left of the window, to normalised device coordinates
+pub fn orthographic_projection(width: u32, height: u32) -> Matrix4<f32> {
+    cgmath::ortho(0.0, width as f32, height as f32, 0.0, -1.0, 1.0)
+}
+
 pub trait Matrix4Ext {
     fn to_matrix3(self) -> Matrix3<f32>;
 }
@@ -0,0 +1,76 @@
+use crate::renderer::PassTimings;
+use color_eyre::Result;
+use log::info;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Measurements taken over a single frame
+pub struct FrameMetrics {
+    pub frame_time: Duration,
+    pub cpu: PassTimings,
+    /// Zero unless GPU timing is enabled
+    pub gpu: PassTimings,
+    pub draw_calls: usize,
+    pub triangles: usize,
+    pub nodes: usize,
+}
+
+/// Writes the metrics of every frame to a CSV file, so that the performance of playtests can be compared
+pub struct MetricsRecorder {
+    writer: BufWriter<File>,
+    start: Instant,
+}
+
+impl MetricsRecorder {
+    pub fn create(path: &Path) -> Result<Self> {
+        info!("Recording metrics to {:?}", path);
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(
+            writer,
+            "time_s,frame_time_ms,{},{},draw_calls,triangles,nodes",
+            pass_columns("cpu"),
+            pass_columns("gpu"),
+        )?;
+
+        Ok(Self {
+            writer,
+            start: Instant::now(),
+        })
+    }
+
+    pub fn record(&mut self, metrics: &FrameMetrics) -> Result<()> {
+        writeln!(
+            self.writer,
+            "{:.3},{:.3},{},{},{},{},{}",
+            self.start.elapsed().as_secs_f64(),
+            metrics.frame_time.as_secs_f64() * 1000.0,
+            pass_values(&metrics.cpu),
+            pass_values(&metrics.gpu),
+            metrics.draw_calls,
+            metrics.triangles,
+            metrics.nodes,
+        )?;
+
+        Ok(())
+    }
+}
+
+fn pass_columns(prefix: &str) -> String {
+    ["depth_prepass", "color_pass", "lines", "post_process"]
+        .map(|pass| format!("{prefix}_{pass}_ms"))
+        .join(",")
+}
+
+fn pass_values(timings: &PassTimings) -> String {
+    [
+        timings.depth_prepass,
+        timings.color_pass,
+        timings.lines,
+        timings.post_process,
+    ]
+    .map(|duration| format!("{:.3}", duration.as_secs_f64() * 1000.0))
+    .join(",")
+}
@@ -1,14 +1,62 @@
-use crate::texture::Texture2D;
-use color_eyre::eyre::Result;
+use crate::texture::{DecodedTexture2D, Texture2D, TextureFiltering, TextureWrap};
+use color_eyre::eyre::{Result, WrapErr};
 use glium::glutin::surface::WindowSurface;
 use glium::Display;
+use log::warn;
 use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+use std::path::Path;
 use std::sync::Arc;
 
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(transparent)]
+pub struct EmissiveStrength(pub f32);
+
+impl Default for EmissiveStrength {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+// Materials are hashed to batch instances which share them
+impl Eq for EmissiveStrength {}
+
+impl Hash for EmissiveStrength {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state)
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Eq, Hash, PartialEq)]
 pub struct Material {
     pub diffuse: Arc<Texture2D>,
     pub specular: Arc<Texture2D>,
+    /// Overrides the normal map imported with the model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normal: Option<Arc<Texture2D>>,
+    /// Overrides the global texture filtering, e.g. to keep pixel art sharp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filtering: Option<TextureFiltering>,
+    /// Wrapping of every texture of the material, the default of mirroring matches scenes saved before it could be
+    /// changed
+    #[serde(default)]
+    pub wrap: TextureWrap,
+    /// Light given off by the surface, independent of the lights in the scene
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emissive: Option<Arc<Texture2D>>,
+    /// Multiplies the emissive texture, values above 1 make it bloom
+    #[serde(default)]
+    pub emissive_strength: EmissiveStrength,
+    /// Blend using the alpha of the diffuse texture, instead of writing over what is behind
+    #[serde(default)]
+    pub transparent: bool,
+}
+
+/// The textures of a glTF material, read but not yet uploaded
+pub struct DecodedMaterial {
+    diffuse: Option<DecodedTexture2D>,
+    emissive: Option<DecodedTexture2D>,
+    transparent: bool,
 }
 
 impl Material {
@@ -19,6 +67,112 @@ impl Material {
         Ok(Self {
             diffuse: default_diffuse,
             specular: Texture2D::solid(width, height, display)?,
+            normal: None,
+            filtering: None,
+            wrap: TextureWrap::default(),
+            emissive: None,
+            emissive_strength: EmissiveStrength::default(),
+            transparent: false,
         })
     }
+
+    /// Read a material asset saved with [`Material::to_asset`]
+    pub fn load_asset(path: &Path, display: &Display<WindowSurface>) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Could not read the material {:?}", path))?;
+
+        let mut material = serde_json::from_str::<Self>(&json)
+            .wrap_err_with(|| format!("Could not parse the material {:?}", path))?;
+
+        material.load_textures(display)?;
+
+        Ok(material)
+    }
+
+    pub fn to_asset(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Only the paths of textures are saved, so they must be loaded after deserializing
+    fn load_textures(&mut self, display: &Display<WindowSurface>) -> Result<()> {
+        self.diffuse = self.diffuse.load_saved(display)?;
+
+        // The default specular texture is generated rather than read from a file
+        self.specular = if self.specular.path.as_os_str().is_empty() {
+            let (width, height) = self.diffuse.inner_texture.as_ref().unwrap().dimensions();
+            Texture2D::solid(width, height, display)?
+        } else {
+            self.specular.load_saved(display)?
+        };
+
+        for texture in self.normal.iter_mut().chain(self.emissive.iter_mut()) {
+            *texture = texture.load_saved(display)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn textures(&self) -> impl Iterator<Item = &Arc<Texture2D>> {
+        [&self.diffuse, &self.specular]
+            .into_iter()
+            .chain(self.normal.as_ref())
+            .chain(self.emissive.as_ref())
+    }
+
+    pub fn textures_mut(&mut self) -> impl Iterator<Item = &mut Arc<Texture2D>> {
+        [&mut self.diffuse, &mut self.specular]
+            .into_iter()
+            .chain(self.normal.as_mut())
+            .chain(self.emissive.as_mut())
+    }
+
+    /// Read the textures of a material of the model at `model_path` without touching the GPU, so that it can be done
+    /// on another thread. `images` are those decoded from the model, see [`Texture2D::decode_gltf`]. Images which
+    /// cannot be read are left out with a warning, so that the rest of the model can still be imported
+    pub fn decode_gltf(
+        material: &gltf::Material,
+        model_path: &Path,
+        images: &[gltf::image::Data],
+    ) -> DecodedMaterial {
+        let decode = |info: Option<gltf::texture::Info>| {
+            Texture2D::decode_gltf(&info?.texture(), model_path, images)
+                .inspect_err(|err| warn!("Could not load a texture of {:?}: {}", model_path, err))
+                .ok()
+        };
+
+        DecodedMaterial {
+            diffuse: decode(material.pbr_metallic_roughness().base_color_texture()),
+            emissive: decode(material.emissive_texture()),
+            transparent: material.alpha_mode() == gltf::material::AlphaMode::Blend,
+        }
+    }
+
+    /// Upload a material read with [`Material::decode_gltf`], or `None` if it has no textures and the default should
+    /// be used
+    pub fn from_decoded(
+        decoded: DecodedMaterial,
+        display: &Display<WindowSurface>,
+    ) -> Result<Option<Self>> {
+        let upload = |texture: Option<DecodedTexture2D>| {
+            Texture2D::from_decoded(texture?, display)
+                .inspect_err(|err| warn!("Could not upload a material's texture: {}", err))
+                .ok()
+        };
+
+        let diffuse = upload(decoded.diffuse);
+        let emissive = upload(decoded.emissive);
+
+        if diffuse.is_none() && emissive.is_none() {
+            return Ok(None);
+        }
+
+        let mut imported = Self::default(display)?;
+        if let Some(diffuse) = diffuse {
+            imported.diffuse = diffuse;
+        }
+        imported.emissive = emissive;
+        imported.transparent = decoded.transparent;
+
+        Ok(Some(imported))
+    }
 }
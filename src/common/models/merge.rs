@@ -0,0 +1,67 @@
+use crate::models::model_vertex::ModelVertex;
+use cgmath::{InnerSpace, Matrix, Matrix3, Matrix4, SquareMatrix, Vector3, Vector4};
+
+/// The vertices and indices of a primitive, and the transform which places it in the world
+pub struct MergePart<'a> {
+    pub transform: Matrix4<f32>,
+    pub vertices: &'a [ModelVertex],
+    pub indices: &'a [u32],
+}
+
+/// Combine primitives into one in world space, so that they can be drawn with a single call. Everything but the
+/// positions, normals and tangents is copied as it is
+pub fn merge_primitives(parts: &[MergePart]) -> (Vec<ModelVertex>, Vec<u32>) {
+    let mut vertices = Vec::with_capacity(parts.iter().map(|part| part.vertices.len()).sum());
+    let mut indices = Vec::with_capacity(parts.iter().map(|part| part.indices.len()).sum());
+
+    for part in parts {
+        let offset = vertices.len() as u32;
+
+        let linear = Matrix3::from_cols(
+            part.transform.x.truncate(),
+            part.transform.y.truncate(),
+            part.transform.z.truncate(),
+        );
+        let normal_matrix = linear
+            .invert()
+            .map_or(linear, |inverse| inverse.transpose());
+
+        vertices.extend(part.vertices.iter().map(|vertex| {
+            let position = part.transform * Vector3::from(vertex.position).extend(1.0);
+            let normal = normalize_or_zero(normal_matrix * Vector3::from(vertex.normal));
+            let tangent = Vector4::from(vertex.tangent);
+
+            ModelVertex {
+                position: position.truncate().into(),
+                normal: normal.into(),
+                tangent: normalize_or_zero(linear * tangent.truncate())
+                    .extend(tangent.w)
+                    .into(),
+                ..*vertex
+            }
+        }));
+
+        // A mirroring transform turns the triangles inside out, so their winding is reversed to keep them facing out
+        if linear.determinant() < 0.0 {
+            indices.extend(
+                part.indices
+                    .chunks(3)
+                    .flat_map(|triangle| triangle.iter().rev())
+                    .map(|index| index + offset),
+            );
+        } else {
+            indices.extend(part.indices.iter().map(|index| index + offset));
+        }
+    }
+
+    (vertices, indices)
+}
+
+/// Some models have zero normals or tangents where they are not needed, which cannot be normalized
+fn normalize_or_zero(vector: Vector3<f32>) -> Vector3<f32> {
+    if vector.magnitude2() > 0.0 {
+        vector.normalize()
+    } else {
+        vector
+    }
+}
@@ -1,9 +1,12 @@
 mod material;
+pub mod merge;
+mod metadata;
 mod model;
 mod model_instance;
 pub mod model_vertex;
 pub mod primitives;
 
-pub use material::Material;
-pub use model::Model;
-pub use model_instance::ModelInstance;
+pub use material::{DecodedMaterial, EmissiveStrength, Material};
+pub use metadata::{Metadata, MetadataValue};
+pub use model::{Bounds, DecodedMesh, Model};
+pub use model_instance::{LevelOfDetail, ModelInstance};
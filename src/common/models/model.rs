@@ -1,30 +1,113 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::{Hash, Hasher};
-use std::mem::offset_of;
-use std::path::PathBuf;
+use std::mem::{offset_of, size_of};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::{fmt, ptr};
 
+use cgmath::{InnerSpace, Vector2, Vector3, Zero};
 use color_eyre::Result;
 use glium::glutin::surface::WindowSurface;
-use glium::index::PrimitiveType;
+use glium::index::{IndicesSource, PrimitiveType};
 use glium::{Display, IndexBuffer, VertexBuffer};
+use gltf::accessor::sparse::IndexType;
 use gltf::buffer::Data;
 use gltf::json::accessor::ComponentType;
 use gltf::{Accessor, Semantic};
 use itertools::Itertools;
 use log::{debug, info, warn};
-use memoize::memoize;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::assets::AssetDatabase;
+use crate::colliders::{AABBCollider, BoundingSphere};
 use crate::models::model_vertex::ModelVertex;
+use crate::texture::{DecodedTexture2D, Texture2D};
 
 use crate::maths;
 
 pub struct Primitive {
     pub vertex_buffer: VertexBuffer<ModelVertex>,
-    pub index_buffer: IndexBuffer<u16>,
+    pub index_buffer: PrimitiveIndexBuffer,
+    pub normal_texture: Option<Arc<Texture2D>>,
+}
+
+/// A primitive's geometry, read back from its file
+pub struct PrimitiveGeometry {
+    pub vertices: Vec<ModelVertex>,
+    pub indices: Vec<u32>,
+    pub normal_texture: Option<Arc<Texture2D>>,
+}
+
+/// An index buffer of the same index type as the file, so that small meshes stay small and large meshes can
+/// address all of their vertices
+pub enum PrimitiveIndexBuffer {
+    U8(IndexBuffer<u8>),
+    U16(IndexBuffer<u16>),
+    U32(IndexBuffer<u32>),
+}
+
+impl PrimitiveIndexBuffer {
+    fn new(
+        indices: &[u32],
+        component_type: ComponentType,
+        display: &Display<WindowSurface>,
+    ) -> Result<Self> {
+        let primitive_type = PrimitiveType::TrianglesList;
+
+        // Every index fits, as they were read from an accessor of this component type
+        Ok(match component_type {
+            ComponentType::U8 => Self::U8(IndexBuffer::new(
+                display,
+                primitive_type,
+                &indices.iter().map(|index| *index as u8).collect_vec(),
+            )?),
+            ComponentType::U16 => Self::U16(IndexBuffer::new(
+                display,
+                primitive_type,
+                &indices.iter().map(|index| *index as u16).collect_vec(),
+            )?),
+            _ => Self::U32(IndexBuffer::new(display, primitive_type, indices)?),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Self::U8(index_buffer) => index_buffer.len(),
+            Self::U16(index_buffer) => index_buffer.len(),
+            Self::U32(index_buffer) => index_buffer.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<'a> From<&'a PrimitiveIndexBuffer> for IndicesSource<'a> {
+    fn from(index_buffer: &'a PrimitiveIndexBuffer) -> Self {
+        match index_buffer {
+            PrimitiveIndexBuffer::U8(index_buffer) => index_buffer.into(),
+            PrimitiveIndexBuffer::U16(index_buffer) => index_buffer.into(),
+            PrimitiveIndexBuffer::U32(index_buffer) => index_buffer.into(),
+        }
+    }
+}
+
+/// A primitive read from its file but not yet uploaded
+struct DecodedPrimitive {
+    vertices: Vec<ModelVertex>,
+    indices: Vec<u32>,
+    component_type: ComponentType,
+    normal_texture: Option<DecodedTexture2D>,
+}
+
+/// A mesh read from its file on any thread, which only needs uploading on the main thread to become a [`Mesh`]
+pub struct DecodedMesh {
+    name: Option<String>,
+    primitives: Vec<DecodedPrimitive>,
 }
 
 // TODO could move all vertices / indices into one buffer and then have an offset into this for each primitive
@@ -58,11 +141,34 @@ impl fmt::Display for ModelLoadError {
 pub struct Model {
     #[serde(with = "crate::serde::uuid")]
     pub uuid: Uuid,
+    #[serde(with = "crate::serde::asset_path")]
     pub path: PathBuf,
+    /// Only this mesh of the file is loaded, otherwise every mesh is
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mesh: Option<usize>,
     #[serde(skip)]
     // This is in a mutex for interior mutability
     // TODO figure out how to make this not like this
     pub meshes: Mutex<Option<Vec<Mesh>>>,
+    /// Of every mesh, computed whenever they are loaded
+    #[serde(skip)]
+    pub bounds: Mutex<Option<Bounds>>,
+}
+
+/// Bounding volumes of a model in its own space
+#[derive(Clone, Debug)]
+pub struct Bounds {
+    pub aabb: AABBCollider,
+    pub sphere: BoundingSphere,
+}
+
+impl Bounds {
+    fn from_points(points: &[Vector3<f32>]) -> Option<Self> {
+        Some(Self {
+            aabb: AABBCollider::from_points(points)?,
+            sphere: BoundingSphere::from_points(points)?,
+        })
+    }
 }
 
 impl Model {
@@ -70,49 +176,246 @@ impl Model {
         path: PathBuf,
         display: &Display<WindowSurface>,
     ) -> Result<Arc<Self>, ModelLoadError> {
-        load(path, display)
+        load(path, None, display)
+    }
+
+    /// Free the models which are only held by the cache, returning how many there were
+    pub fn collect_garbage() -> usize {
+        MODELS.with_borrow_mut(|models| {
+            let count = models.len();
+            models.retain(|_, model| Arc::strong_count(model) > 1);
+
+            count - models.len()
+        })
+    }
+
+    /// Load a single mesh out of the file at `path`, as referenced by one of its nodes
+    pub fn load_mesh(
+        path: PathBuf,
+        mesh: usize,
+        display: &Display<WindowSurface>,
+    ) -> Result<Arc<Self>, ModelLoadError> {
+        load(path, Some(mesh), display)
+    }
+
+    /// OBJ files are read with tobj, everything else is assumed to be glTF
+    pub fn is_obj(path: &Path) -> bool {
+        path.extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("obj"))
+    }
+
+    fn includes_mesh(&self, index: usize) -> bool {
+        self.mesh.map_or(true, |mesh| mesh == index)
     }
 
     pub fn load_meshes(&self, display: &Display<WindowSurface>) -> Result<(), ModelLoadError> {
-        // TODO parse materials
-        let (document, file_buffers, _images) = gltf::import(&self.path)
-            .map_err(|_| ModelLoadError::ModelDoesNotExist(self.path.clone()))?;
-
-        let mut meshes = Vec::new();
-        for mesh in document.meshes() {
-            let mut primitives = Vec::new();
-            for primitive in mesh.primitives() {
-                primitives.push(
-                    Primitive::from(primitive, &file_buffers, display)
-                        .map_err(|_| ModelLoadError::CreateBufferError(self.path.clone()))?,
-                );
-            }
+        let (_, meshes, _) = Self::decode(&self.path)?;
 
-            meshes.push(Mesh {
-                name: mesh.name().map(str::to_owned),
-                primitives,
-            });
+        let meshes = meshes
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| self.includes_mesh(*index))
+            .map(|(_, mesh)| mesh)
+            .collect_vec();
+
+        self.upload(meshes, display)
+    }
+
+    /// A model of the meshes already read from `path`, see [`Model::decode`]
+    pub fn from_decoded(
+        path: PathBuf,
+        mesh: Option<usize>,
+        meshes: Vec<DecodedMesh>,
+        display: &Display<WindowSurface>,
+    ) -> Result<Arc<Self>, ModelLoadError> {
+        let model = Model {
+            uuid: Uuid::new_v4(),
+            path,
+            mesh,
+            meshes: Mutex::new(None),
+            bounds: Mutex::new(None),
+        };
+
+        model.upload(meshes, display)?;
+
+        Ok(Arc::new(model))
+    }
+
+    /// Read every mesh in the file at `path` without touching the GPU, so that it can be done off the main thread.
+    /// The glTF document is returned alongside, as it holds the node hierarchy and materials, along with the images
+    /// decoded from it for the materials to use
+    #[allow(clippy::type_complexity)]
+    pub fn decode(
+        path: &Path,
+    ) -> Result<
+        (
+            Option<gltf::Document>,
+            Vec<DecodedMesh>,
+            Vec<gltf::image::Data>,
+        ),
+        ModelLoadError,
+    > {
+        AssetDatabase::register(path);
+
+        if Self::is_obj(path) {
+            return Ok((None, Self::decode_obj(path)?, Vec::new()));
         }
 
+        let (document, file_buffers, images) = gltf::import(path)
+            .map_err(|_| ModelLoadError::ModelDoesNotExist(path.to_path_buf()))?;
+
+        let meshes = document
+            .meshes()
+            .map(|mesh| DecodedMesh {
+                name: mesh.name().map(str::to_owned),
+                primitives: mesh
+                    .primitives()
+                    .map(|primitive| {
+                        DecodedPrimitive::read(&primitive, &file_buffers, path, &images)
+                    })
+                    .collect_vec(),
+            })
+            .collect_vec();
+
+        Ok((Some(document), meshes, images))
+    }
+
+    /// OBJ files have no hierarchy, so each object in the file becomes a mesh of a single primitive
+    fn decode_obj(path: &Path) -> Result<Vec<DecodedMesh>, ModelLoadError> {
+        let (models, _materials) = tobj::load_obj(path, &tobj::GPU_LOAD_OPTIONS)
+            .map_err(|_| ModelLoadError::ModelDoesNotExist(path.to_path_buf()))?;
+
+        Ok(models
+            .into_iter()
+            .map(|model| {
+                let (vertices, indices) = Primitive::read_obj(&model.mesh);
+
+                DecodedMesh {
+                    name: Some(model.name),
+                    primitives: vec![DecodedPrimitive {
+                        vertices,
+                        indices,
+                        component_type: ComponentType::U32,
+                        normal_texture: None,
+                    }],
+                }
+            })
+            .collect_vec())
+    }
+
+    fn upload(
+        &self,
+        meshes: Vec<DecodedMesh>,
+        display: &Display<WindowSurface>,
+    ) -> Result<(), ModelLoadError> {
+        let positions = meshes
+            .iter()
+            .flat_map(|mesh| mesh.primitives.iter())
+            .flat_map(|primitive| primitive.vertices.iter())
+            .map(|vertex| Vector3::from(vertex.position))
+            .collect_vec();
+        *self.bounds.lock().unwrap() = Bounds::from_points(&positions);
+
+        let meshes = meshes
+            .into_iter()
+            .map(|mesh| {
+                let primitives = mesh
+                    .primitives
+                    .into_iter()
+                    .map(|primitive| primitive.upload(display))
+                    .collect::<Result<Vec<_>>>()
+                    .map_err(|_| ModelLoadError::CreateBufferError(self.path.clone()))?;
+
+                Ok(Mesh {
+                    name: mesh.name,
+                    primitives,
+                })
+            })
+            .collect::<Result<Vec<_>, ModelLoadError>>()?;
+
         *self.meshes.lock().unwrap() = Some(meshes);
 
         Ok(())
     }
+
+    pub fn bounds(&self) -> Option<Bounds> {
+        self.bounds.lock().unwrap().clone()
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.meshes
+            .lock()
+            .unwrap()
+            .iter()
+            .flatten()
+            .flat_map(|mesh| mesh.primitives.iter())
+            .map(|primitive| primitive.index_buffer.len() / 3)
+            .sum()
+    }
+
+    /// The vertices and indices of every primitive, read from the file again as only the GPU copies are kept. Each
+    /// comes with the normal map it was loaded with
+    pub fn read_primitives(&self) -> Result<Vec<PrimitiveGeometry>, ModelLoadError> {
+        let (_, meshes, _) = Self::decode(&self.path)?;
+
+        let loaded = self.meshes.lock().unwrap();
+        let loaded = loaded.as_deref().unwrap_or_default();
+
+        Ok(meshes
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| self.includes_mesh(*index))
+            .map(|(_, mesh)| mesh)
+            .enumerate()
+            .flat_map(|(mesh_index, mesh)| {
+                mesh.primitives
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(primitive_index, primitive)| PrimitiveGeometry {
+                        vertices: primitive.vertices,
+                        indices: primitive.indices,
+                        normal_texture: loaded
+                            .get(mesh_index)
+                            .and_then(|mesh| mesh.primitives.get(primitive_index))
+                            .and_then(|primitive| primitive.normal_texture.clone()),
+                    })
+            })
+            .collect_vec())
+    }
+}
+
+thread_local! {
+    /// Models by path and mesh, so that instances of the same file share it. These are kept until
+    /// [`Model::collect_garbage`] finds that nothing else uses them
+    static MODELS: RefCell<HashMap<(PathBuf, Option<usize>), Arc<Model>>> = RefCell::new(HashMap::new());
 }
 
-#[memoize(Ignore: display)]
-fn load(path: PathBuf, display: &Display<WindowSurface>) -> Result<Arc<Model>, ModelLoadError> {
-    info!("Loading models {:?}...", path);
+fn load(
+    path: PathBuf,
+    mesh: Option<usize>,
+    display: &Display<WindowSurface>,
+) -> Result<Arc<Model>, ModelLoadError> {
+    let key = (path, mesh);
+    if let Some(model) = MODELS.with_borrow(|models| models.get(&key).cloned()) {
+        return Ok(model);
+    }
+
+    info!("Loading models {:?}...", key.0);
 
     let model = Model {
         uuid: Uuid::new_v4(),
-        path: path.clone(),
+        path: key.0.clone(),
+        mesh,
         meshes: Mutex::new(None),
+        bounds: Mutex::new(None),
     };
 
     model.load_meshes(display)?;
 
-    Ok(Arc::new(model))
+    let model = Arc::new(model);
+    MODELS.with_borrow_mut(|models| models.insert(key, model.clone()));
+
+    Ok(model)
 }
 
 impl PartialEq<Self> for Model {
@@ -129,12 +432,13 @@ impl Hash for Model {
     }
 }
 
-impl Primitive {
-    fn from(
-        primitive: gltf::Primitive,
+impl DecodedPrimitive {
+    fn read(
+        primitive: &gltf::Primitive,
         file_buffers: &[Data],
-        display: &Display<WindowSurface>,
-    ) -> Result<Self> {
+        path: &Path,
+        images: &[gltf::image::Data],
+    ) -> Self {
         let available_attributes = primitive
             .attributes()
             .map(|(semantic, _)| semantic)
@@ -147,9 +451,109 @@ impl Primitive {
             "No position data for primitive!"
         );
 
+        let (vertices, indices) = Primitive::read(primitive, file_buffers);
+
+        // Indices generated for primitives without any need the full range
+        let component_type = primitive
+            .indices()
+            .map_or(ComponentType::U32, |accessor| accessor.data_type());
+
+        // A model without its normal map is still usable, so it is left out rather than failing the import
+        let normal_texture = primitive
+            .material()
+            .normal_texture()
+            .and_then(|normal_texture| {
+                Texture2D::decode_gltf(&normal_texture.texture(), path, images)
+                    .inspect_err(|err| {
+                        warn!("Could not load the normal map of {:?}: {}", path, err)
+                    })
+                    .ok()
+            });
+
+        Self {
+            vertices,
+            indices,
+            component_type,
+            normal_texture,
+        }
+    }
+
+    fn upload(self, display: &Display<WindowSurface>) -> Result<Primitive> {
+        let normal_texture = self
+            .normal_texture
+            .map(|decoded| Texture2D::from_decoded(decoded, display))
+            .transpose()?;
+
+        Primitive::new(
+            &self.vertices,
+            &self.indices,
+            self.component_type,
+            normal_texture,
+            display,
+        )
+    }
+}
+
+impl Primitive {
+    fn new(
+        vertices: &[ModelVertex],
+        indices: &[u32],
+        component_type: ComponentType,
+        normal_texture: Option<Arc<Texture2D>>,
+        display: &Display<WindowSurface>,
+    ) -> Result<Self> {
+        Ok(Primitive {
+            vertex_buffer: VertexBuffer::new(display, vertices)?,
+            index_buffer: PrimitiveIndexBuffer::new(indices, component_type, display)?,
+            normal_texture,
+        })
+    }
+
+    /// Vertices and indices of an OBJ mesh, where each attribute is a flat array indexed alongside the positions
+    fn read_obj(mesh: &tobj::Mesh) -> (Vec<ModelVertex>, Vec<u32>) {
+        let mut vertices = mesh
+            .positions
+            .chunks_exact(3)
+            .map(|position| ModelVertex {
+                position: [position[0], position[1], position[2]],
+                ..ModelVertex::default()
+            })
+            .collect_vec();
+
+        if mesh.normals.is_empty() {
+            warn!("OBJ mesh does not include normals, so it will not be lit correctly");
+        }
+
+        for (vertex, normal) in vertices.iter_mut().zip(mesh.normals.chunks_exact(3)) {
+            vertex.normal = [normal[0], normal[1], normal[2]];
+        }
+
+        if mesh.texcoords.is_empty() {
+            warn!("OBJ mesh does not include texture coordinates! Generating...");
+            generate_tex_coords(&mut vertices);
+        }
+
+        // OBJ texture coordinates start at the bottom of the image, whereas images are loaded top row first
+        for (vertex, tex_coord) in vertices.iter_mut().zip(mesh.texcoords.chunks_exact(2)) {
+            vertex.tex_coord = [tex_coord[0], 1.0 - tex_coord[1]];
+        }
+
+        // OBJ has no tangents
+        generate_tangents(&mut vertices, &mesh.indices);
+
+        (vertices, mesh.indices.clone())
+    }
+
+    /// Vertices and indices of the primitive, generating any attributes the file is missing
+    fn read(primitive: &gltf::Primitive, file_buffers: &[Data]) -> (Vec<ModelVertex>, Vec<u32>) {
+        let available_attributes = primitive
+            .attributes()
+            .map(|(semantic, _)| semantic)
+            .collect_vec();
+
         // TODO look into gltf::Reader::read_indices, vertices etc
-        let mut vertices = Self::extract_vertices(&primitive, file_buffers);
-        let indices = Self::extract_indices(&primitive, file_buffers);
+        let mut vertices = Self::extract_vertices(primitive, file_buffers);
+        let indices = Self::extract_indices(primitive, file_buffers, vertices.len());
 
         // TODO understand tex coord set index
         if !available_attributes.contains(&Semantic::TexCoords(0)) {
@@ -157,30 +561,26 @@ impl Primitive {
             generate_tex_coords(&mut vertices);
         }
 
-        let vertex_buffer = VertexBuffer::new(display, &vertices)?;
-
-        let index_buffer = IndexBuffer::new(display, PrimitiveType::TrianglesList, &indices)?;
+        if !available_attributes.contains(&Semantic::Tangents) {
+            debug!("Mesh primitive does not include tangents. Generating...");
+            generate_tangents(&mut vertices, &indices);
+        }
 
-        Ok(Primitive {
-            vertex_buffer,
-            index_buffer,
-        })
+        (vertices, indices)
     }
 
-    fn extract_indices(primitive: &gltf::Primitive, file_buffers: &[Data]) -> Vec<u16> {
-        let num_indices = primitive.indices().expect("No indices? Help, bad.").count();
-        // TODO allow differently sized indices
-        let mut indices = vec![0_u16; num_indices];
-
-        map_accessor_data_to_buffer(
-            &mut indices,
-            // No offset as indices are scalar
-            0,
-            &primitive.indices().unwrap(),
-            file_buffers,
-        );
+    /// Indices of any size widened to u32, or every vertex in order if the primitive is not indexed
+    fn extract_indices(
+        primitive: &gltf::Primitive,
+        file_buffers: &[Data],
+        num_vertices: usize,
+    ) -> Vec<u32> {
+        let reader = primitive.reader(|buffer| Some(&file_buffers[buffer.index()].0[..]));
 
-        indices
+        match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect_vec(),
+            None => (0..num_vertices as u32).collect_vec(),
+        }
     }
 
     fn extract_vertices(primitive: &gltf::Primitive, file_buffers: &[Data]) -> Vec<ModelVertex> {
@@ -213,7 +613,27 @@ impl Primitive {
                         file_buffers,
                     );
                 }
-                _ => unimplemented!("{semantic:?}"),
+                Semantic::Tangents => {
+                    map_accessor_data_to_buffer(
+                        &mut vertices,
+                        offset_of!(ModelVertex, tangent),
+                        &accessor,
+                        file_buffers,
+                    );
+                }
+                Semantic::Colors(0) => {
+                    // Colors may be stored as RGB or RGBA, in floats or normalised integers, so are converted by
+                    // the reader rather than copied
+                    let reader =
+                        primitive.reader(|buffer| Some(&file_buffers[buffer.index()].0[..]));
+
+                    if let Some(colors) = reader.read_colors(0) {
+                        for (vertex, color) in vertices.iter_mut().zip(colors.into_rgba_f32()) {
+                            vertex.color = color;
+                        }
+                    }
+                }
+                _ => warn!("Ignoring unsupported attribute {semantic:?}"),
             }
         }
 
@@ -228,42 +648,86 @@ fn map_accessor_data_to_buffer<T: Debug>(
     accessor: &Accessor,
     file_buffers: &[Data],
 ) {
-    let buffer_view = accessor
-        .view()
-        .expect("Sparse accessor not yet implemented HELP");
-
-    let file_buffer = &file_buffers[buffer_view.buffer().index()];
-
-    let byte_stride = buffer_view
-        .stride()
-        .unwrap_or(calculate_bit_stride(accessor))
-        / 8;
-
-    let file_buffer_offset = buffer_view.offset();
-
-    for (index, element_start_index) in (file_buffer_offset
-        ..file_buffer_offset + buffer_view.length())
-        .step_by(byte_stride)
-        .enumerate()
-    {
-        unsafe {
-            // Cast to pointer to stop the borrow checker from freaking out then cast to u8
-            let current_destination_pointer: *mut u8 =
-                &mut destination_buffer[index] as *mut T as *mut u8;
-            let member_destination_pointer = current_destination_pointer.add(byte_offset);
-
-            // Extract slice from the loaded file buffer
-            let member_source_pointer: *const u8 = &file_buffer[element_start_index];
-
-            ptr::copy(
-                member_source_pointer,
-                member_destination_pointer,
-                byte_stride,
+    let element_size = calculate_bit_stride(accessor) / 8;
+
+    match accessor.view() {
+        Some(buffer_view) => {
+            let file_buffer = &file_buffers[buffer_view.buffer().index()];
+            // Elements may be interleaved with other attributes
+            let byte_stride = buffer_view.stride().unwrap_or(element_size);
+            let start = buffer_view.offset() + accessor.offset().unwrap_or(0);
+
+            for index in 0..accessor.count() {
+                write_member(
+                    destination_buffer,
+                    index,
+                    byte_offset,
+                    &file_buffer[start + index * byte_stride..][..element_size],
+                );
+            }
+        }
+        // Accessors without a view are all zeros, apart from any sparse values
+        None => {
+            let zeros = vec![0_u8; element_size];
+
+            for index in 0..accessor.count() {
+                write_member(destination_buffer, index, byte_offset, &zeros);
+            }
+        }
+    }
+
+    // Sparse accessors replace a few of the elements, such as those moved by a morph target
+    if let Some(sparse) = accessor.sparse() {
+        let indices = sparse.indices();
+        let indices_buffer = &file_buffers[indices.view().buffer().index()];
+        let indices_start = indices.view().offset() + indices.offset();
+
+        let values = sparse.values();
+        let values_buffer = &file_buffers[values.view().buffer().index()];
+        let values_start = values.view().offset() + values.offset();
+
+        for i in 0..sparse.count() {
+            let index = match indices.index_type() {
+                IndexType::U8 => indices_buffer[indices_start + i] as usize,
+                IndexType::U16 => {
+                    let bytes = &indices_buffer[indices_start + i * 2..][..2];
+                    u16::from_le_bytes([bytes[0], bytes[1]]) as usize
+                }
+                IndexType::U32 => {
+                    let bytes = &indices_buffer[indices_start + i * 4..][..4];
+                    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize
+                }
+            };
+
+            write_member(
+                destination_buffer,
+                index,
+                byte_offset,
+                &values_buffer[values_start + i * element_size..][..element_size],
             );
         }
     }
 }
 
+/// Copy `source` over the member at `byte_offset` of the element at `index`
+fn write_member<T>(destination_buffer: &mut [T], index: usize, byte_offset: usize, source: &[u8]) {
+    assert!(
+        byte_offset + source.len() <= size_of::<T>(),
+        "Accessor data does not fit in the member"
+    );
+
+    unsafe {
+        // Cast to pointer to stop the borrow checker from freaking out then cast to u8
+        let element_pointer: *mut u8 = &mut destination_buffer[index] as *mut T as *mut u8;
+
+        ptr::copy(
+            source.as_ptr(),
+            element_pointer.add(byte_offset),
+            source.len(),
+        );
+    }
+}
+
 fn generate_tex_coords(vertices: &mut [ModelVertex]) {
     let mut x_min = f32::MAX;
     let mut x_max = f32::MIN;
@@ -287,6 +751,60 @@ fn generate_tex_coords(vertices: &mut [ModelVertex]) {
     }
 }
 
+/// Accumulates the tangent of each triangle onto its vertices, then orthogonalises them against the normals
+fn generate_tangents(vertices: &mut [ModelVertex], indices: &[u32]) {
+    let mut tangents = vec![Vector3::<f32>::zero(); vertices.len()];
+    let mut bitangents = vec![Vector3::<f32>::zero(); vertices.len()];
+
+    for triangle in indices.chunks_exact(3) {
+        let [i0, i1, i2] = [
+            triangle[0] as usize,
+            triangle[1] as usize,
+            triangle[2] as usize,
+        ];
+
+        let edge_1 = Vector3::from(vertices[i1].position) - Vector3::from(vertices[i0].position);
+        let edge_2 = Vector3::from(vertices[i2].position) - Vector3::from(vertices[i0].position);
+
+        let delta_uv_1 =
+            Vector2::from(vertices[i1].tex_coord) - Vector2::from(vertices[i0].tex_coord);
+        let delta_uv_2 =
+            Vector2::from(vertices[i2].tex_coord) - Vector2::from(vertices[i0].tex_coord);
+
+        let determinant = delta_uv_1.x * delta_uv_2.y - delta_uv_2.x * delta_uv_1.y;
+        if determinant.abs() < f32::EPSILON {
+            continue;
+        }
+
+        let tangent = (edge_1 * delta_uv_2.y - edge_2 * delta_uv_1.y) / determinant;
+        let bitangent = (edge_2 * delta_uv_1.x - edge_1 * delta_uv_2.x) / determinant;
+
+        for index in [i0, i1, i2] {
+            tangents[index] += tangent;
+            bitangents[index] += bitangent;
+        }
+    }
+
+    for (index, vertex) in vertices.iter_mut().enumerate() {
+        let normal = Vector3::from(vertex.normal);
+        let tangent = tangents[index] - normal * normal.dot(tangents[index]);
+
+        // Degenerate UVs, keep the default tangent
+        if tangent.magnitude2() < f32::EPSILON {
+            continue;
+        }
+
+        let tangent = tangent.normalize();
+        let handedness = if normal.cross(tangent).dot(bitangents[index]) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+
+        vertex.tangent = [tangent.x, tangent.y, tangent.z, handedness];
+    }
+}
+
 fn calculate_bit_stride(accessor: &Accessor) -> usize {
     let component_size = match accessor.data_type() {
         ComponentType::U8 | ComponentType::I8 => 8,
@@ -1,8 +1,18 @@
-use crate::models::{Material, Model};
+use crate::colliders::BoundingSphere;
+use crate::models::{Material, Metadata, Model};
 use crate::transform::Transform;
+use cgmath::{EuclideanSpace, MetricSpace, Point3};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+/// A simpler version of a model, drawn in its place when the camera is far away
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LevelOfDetail {
+    pub model: Arc<Model>,
+    /// Used when the camera is at least this far from the instance
+    pub distance: f32,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ModelInstance {
     pub model: Arc<Model>,
@@ -10,6 +20,16 @@ pub struct ModelInstance {
     pub transform: Transform,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub material: Option<Material>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub levels_of_detail: Vec<LevelOfDetail>,
+    #[serde(default, skip_serializing_if = "Metadata::is_empty")]
+    pub metadata: Metadata,
+    /// Hidden instances are not drawn, but stay in the scene
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub hidden: bool,
+    /// Locked instances cannot be picked in the viewport or moved, so that they are not changed by accident
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub locked: bool,
     #[serde(skip)]
     pub selected: bool,
 }
@@ -20,8 +40,63 @@ impl From<Arc<Model>> for ModelInstance {
             model,
             name: "Model".to_owned(),
             material: None,
+            levels_of_detail: vec![],
+            metadata: Metadata::new(),
             transform: Transform::default(),
+            hidden: false,
+            locked: false,
             selected: false,
         }
     }
 }
+
+impl ModelInstance {
+    /// Whether the instance can be selected by clicking on it in a viewport
+    pub fn is_pickable(&self) -> bool {
+        !self.hidden && !self.locked
+    }
+
+    /// The model to draw when the camera is `distance` away, the furthest level of detail in range
+    pub fn model_at_distance(&self, distance: f32) -> &Arc<Model> {
+        self.levels_of_detail
+            .iter()
+            .filter(|level_of_detail| distance >= level_of_detail.distance)
+            .max_by(|a, b| a.distance.total_cmp(&b.distance))
+            .map(|level_of_detail| &level_of_detail.model)
+            .unwrap_or(&self.model)
+    }
+
+    /// The model to draw for a camera at `camera_position`. The distance is measured to the edge of the bounding
+    /// sphere, so that large models switch level of detail at the same distance as small ones
+    pub fn model_for_camera(&self, camera_position: Point3<f32>) -> &Arc<Model> {
+        let distance = match self.bounding_sphere() {
+            Some(sphere) => {
+                (Point3::from_vec(sphere.center).distance(camera_position) - sphere.radius).max(0.0)
+            }
+            None => self.distance_to(camera_position),
+        };
+
+        self.model_at_distance(distance)
+    }
+
+    /// The bounding sphere of the model in world space, once it has loaded
+    pub fn bounding_sphere(&self) -> Option<BoundingSphere> {
+        Some(self.model.bounds()?.sphere.transformed(&self.transform))
+    }
+
+    pub fn distance_to(&self, point: Point3<f32>) -> f32 {
+        // The centre of the geometry, which is not always where the model's origin is
+        let center = self
+            .bounding_sphere()
+            .map_or(self.transform.translation, |sphere| sphere.center);
+
+        Point3::from_vec(center).distance(point)
+    }
+
+    /// Instances without a material use the default, which is opaque
+    pub fn is_transparent(&self) -> bool {
+        self.material
+            .as_ref()
+            .is_some_and(|material| material.transparent)
+    }
+}
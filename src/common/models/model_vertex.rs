@@ -5,6 +5,10 @@ pub struct ModelVertex {
     pub position: [f32; 3],
     pub normal: [f32; 3],
     pub tex_coord: [f32; 2],
+    /// The w component holds the handedness of the bitangent
+    pub tangent: [f32; 4],
+    /// Linear RGBA, multiplied into the diffuse color
+    pub color: [f32; 4],
 }
 
 impl Default for ModelVertex {
@@ -13,8 +17,10 @@ impl Default for ModelVertex {
             position: [0.0, 0.0, 0.0],
             normal: [0.0, 0.0, 0.0],
             tex_coord: [0.0, 0.0],
+            tangent: [1.0, 0.0, 0.0, 1.0],
+            color: [1.0, 1.0, 1.0, 1.0],
         }
     }
 }
 
-implement_vertex!(ModelVertex, position, normal, tex_coord);
+implement_vertex!(ModelVertex, position, normal, tex_coord, tangent, color);
@@ -0,0 +1,123 @@
+use cgmath::{Matrix, Matrix3};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq)]
+pub enum Tonemapping {
+    /// Colors above 1 are clipped
+    None,
+    Reinhard,
+    #[default]
+    Aces,
+}
+
+impl Tonemapping {
+    pub const ALL: [Tonemapping; 3] = [Self::None, Self::Reinhard, Self::Aces];
+
+    /// Must match the branches in post_process.frag
+    pub fn shader_index(self) -> i32 {
+        match self {
+            Self::None => 0,
+            Self::Reinhard => 1,
+            Self::Aces => 2,
+        }
+    }
+}
+
+impl Display for Tonemapping {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::Reinhard => write!(f, "Reinhard"),
+            Self::Aces => write!(f, "ACES"),
+        }
+    }
+}
+
+/// Recolors the final image so that colors which look alike with a color vision deficiency are told apart
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq)]
+pub enum ColorblindFilter {
+    #[default]
+    None,
+    /// Red blindness
+    Protanopia,
+    /// Green blindness
+    Deuteranopia,
+    /// Blue blindness
+    Tritanopia,
+}
+
+impl ColorblindFilter {
+    pub const ALL: [ColorblindFilter; 4] = [
+        Self::None,
+        Self::Protanopia,
+        Self::Deuteranopia,
+        Self::Tritanopia,
+    ];
+
+    /// Linear RGB as seen with the deficiency, from Machado et al. 2009 at full severity. Rows are output channels
+    fn simulation_rows(self) -> [[f32; 3]; 3] {
+        match self {
+            Self::None => [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            Self::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            Self::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            Self::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+
+    /// The simulation matrix in the column major layout of GLSL
+    pub fn simulation_matrix(self) -> [[f32; 3]; 3] {
+        Matrix3::from(self.simulation_rows()).transpose().into()
+    }
+}
+
+impl Display for ColorblindFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::Protanopia => write!(f, "Protanopia"),
+            Self::Deuteranopia => write!(f, "Deuteranopia"),
+            Self::Tritanopia => write!(f, "Tritanopia"),
+        }
+    }
+}
+
+/// Applied in order to the HDR scene before it is presented
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PostProcessSettings {
+    /// Brightness above which pixels start to glow
+    pub bloom_threshold: f32,
+    /// How much of the glow is added back onto the scene, 0 disables bloom
+    pub bloom_intensity: f32,
+    pub exposure: f32,
+    pub tonemapping: Tonemapping,
+    pub gamma: f32,
+    /// How much the corners of the screen are darkened, 0 disables the vignette
+    pub vignette: f32,
+}
+
+impl Default for PostProcessSettings {
+    fn default() -> Self {
+        Self {
+            bloom_threshold: 0.9,
+            bloom_intensity: 0.5,
+            exposure: 1.0,
+            tonemapping: Tonemapping::default(),
+            gamma: 2.2,
+            vignette: 0.0,
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use std::time::{Duration, Instant};
+
+/// A named section of a frame. Scopes with the same name and parent are merged, so that work done in many small
+/// pieces, such as handling each event, shows up as one block
+#[derive(Clone, Debug)]
+pub struct Scope {
+    pub name: &'static str,
+    /// Index of the enclosing scope in the same frame
+    pub parent: Option<usize>,
+    pub duration: Duration,
+    /// How many times the scope was entered
+    pub calls: usize,
+}
+
+/// Measures how long each part of a frame takes, so that a slow frame can be attributed to the system responsible.
+/// Scopes are opened with `begin` and closed with `end`, and can be nested
+#[derive(Default)]
+pub struct Profiler {
+    scopes: Vec<Scope>,
+    /// The open scopes, innermost last, with when each was entered
+    open: Vec<(usize, Instant)>,
+    last_frame: Vec<Scope>,
+}
+
+impl Profiler {
+    pub fn begin(&mut self, name: &'static str) {
+        let parent = self.open.last().map(|(index, _)| *index);
+
+        let index = match self
+            .scopes
+            .iter()
+            .position(|scope| scope.name == name && scope.parent == parent)
+        {
+            Some(index) => index,
+            None => {
+                self.scopes.push(Scope {
+                    name,
+                    parent,
+                    duration: Duration::ZERO,
+                    calls: 0,
+                });
+
+                self.scopes.len() - 1
+            }
+        };
+
+        self.scopes[index].calls += 1;
+        self.open.push((index, Instant::now()));
+    }
+
+    /// Close the innermost open scope
+    pub fn end(&mut self) {
+        if let Some((index, start)) = self.open.pop() {
+            self.scopes[index].duration += start.elapsed();
+        }
+    }
+
+    /// Keep this frame's scopes to be shown, and start measuring the next. Any scopes left open are discarded
+    pub fn finish_frame(&mut self) {
+        self.open.clear();
+        self.last_frame = std::mem::take(&mut self.scopes);
+    }
+
+    /// The scopes of the last complete frame, each after its parent
+    pub fn last_frame(&self) -> &[Scope] {
+        &self.last_frame
+    }
+}
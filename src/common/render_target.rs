@@ -0,0 +1,134 @@
+use color_eyre::Result;
+use glium::framebuffer::{MultiOutputFrameBuffer, SimpleFrameBuffer};
+use glium::glutin::surface::WindowSurface;
+use glium::texture::{
+    DepthFormat, DepthTexture2d, MipmapsOption, Texture2d, UncompressedFloatFormat,
+};
+use glium::Display;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct RenderTargetFormat {
+    pub color: UncompressedFloatFormat,
+    pub depth: Option<DepthFormat>,
+}
+
+/// Offscreen textures drawn to by one pass and read by another
+#[derive(Clone)]
+pub struct RenderTarget {
+    pub color: Rc<Texture2d>,
+    pub depth: Option<Rc<DepthTexture2d>>,
+}
+
+impl RenderTarget {
+    fn new(
+        format: RenderTargetFormat,
+        (width, height): (u32, u32),
+        display: &Display<WindowSurface>,
+    ) -> Result<Self> {
+        let color = Texture2d::empty_with_format(
+            display,
+            format.color,
+            MipmapsOption::NoMipmap,
+            width,
+            height,
+        )?;
+
+        let depth = format
+            .depth
+            .map(|depth_format| {
+                DepthTexture2d::empty_with_format(
+                    display,
+                    depth_format,
+                    MipmapsOption::NoMipmap,
+                    width,
+                    height,
+                )
+            })
+            .transpose()?;
+
+        Ok(Self {
+            color: Rc::new(color),
+            depth: depth.map(Rc::new),
+        })
+    }
+
+    pub fn framebuffer(&self, display: &Display<WindowSurface>) -> Result<SimpleFrameBuffer> {
+        Ok(match &self.depth {
+            Some(depth) => SimpleFrameBuffer::with_depth_buffer(display, &*self.color, &**depth)?,
+            None => SimpleFrameBuffer::new(display, &*self.color)?,
+        })
+    }
+}
+
+/// The HDR targets which the scene is drawn into before post-processing
+#[derive(Clone)]
+pub struct SceneTarget {
+    /// Lit color, along with the depth of the scene
+    pub color: RenderTarget,
+    /// Light given off by emissive surfaces, which always blooms. Pixels with zero alpha were not written to
+    pub emissive: RenderTarget,
+}
+
+impl SceneTarget {
+    /// Shaders write to the attachments through the `out_color` and `out_emissive` outputs
+    pub fn framebuffer(&self, display: &Display<WindowSurface>) -> Result<MultiOutputFrameBuffer> {
+        Ok(MultiOutputFrameBuffer::with_depth_buffer(
+            display,
+            [
+                ("out_color", &*self.color.color),
+                ("out_emissive", &*self.emissive.color),
+            ],
+            &**self.color.depth.as_ref().unwrap(),
+        )?)
+    }
+}
+
+/// Named offscreen targets which are kept between frames and recreated when they are resized
+pub struct RenderTargetCache {
+    dimensions: (u32, u32),
+    targets: HashMap<&'static str, (RenderTargetFormat, RenderTarget)>,
+}
+
+impl RenderTargetCache {
+    pub fn new() -> Self {
+        Self {
+            dimensions: (0, 0),
+            targets: HashMap::new(),
+        }
+    }
+
+    /// Set the size of every target, recreating them all if it has changed
+    pub fn resize(&mut self, dimensions: (u32, u32)) {
+        if dimensions != self.dimensions {
+            self.dimensions = dimensions;
+            self.targets.clear();
+        }
+    }
+
+    /// Get the target with the given name, creating it if it does not exist or its format has changed
+    pub fn get(
+        &mut self,
+        name: &'static str,
+        format: RenderTargetFormat,
+        display: &Display<WindowSurface>,
+    ) -> Result<RenderTarget> {
+        if let Some((existing_format, target)) = self.targets.get(name) {
+            if *existing_format == format {
+                return Ok(target.clone());
+            }
+        }
+
+        let target = RenderTarget::new(format, self.dimensions, display)?;
+        self.targets.insert(name, (format, target.clone()));
+
+        Ok(target)
+    }
+}
+
+impl Default for RenderTargetCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,37 +1,218 @@
-use crate::colors::ColorExt;
+use crate::billboard::{BillboardCorner, BillboardInstance, BILLBOARD_QUAD};
+use crate::buffer_pool::{PooledVertexBuffer, VertexBufferPool};
+use crate::camera::Frustum;
+use crate::colors::{Color, ColorExt};
+use crate::fog::Fog;
 use crate::light::{Light, ShaderLight};
-use crate::line::{Line, LinePoint};
+use crate::line::{Line, LineCorner, LineInstance, Polyline, LINE_QUAD};
 use crate::models::primitives::SimplePoint;
 use crate::models::{primitives, Model};
 use crate::models::{Material, ModelInstance};
+use crate::post_processing::{ColorblindFilter, PostProcessSettings};
+use crate::render_target::{RenderTarget, RenderTargetCache, RenderTargetFormat, SceneTarget};
 use crate::terrain::Terrain;
-use crate::texture::Cubemap;
+use crate::text::Font;
+use crate::texture::{Cubemap, Texture2D, TextureFiltering, TextureSampler, TextureWrap};
 use crate::{context, maths};
-use cgmath::{Matrix3, Matrix4, Point3};
+use cgmath::{Matrix3, Matrix4, Point3, SquareMatrix};
 use color_eyre::Result;
+use glium::draw_parameters::TimeElapsedQuery;
 use glium::glutin::surface::WindowSurface;
 use glium::index::{NoIndices, PrimitiveType};
-use glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter, Sampler, SamplerBehavior};
+use glium::texture::{DepthFormat, UncompressedFloatFormat};
+use glium::uniforms::{
+    MagnifySamplerFilter, MinifySamplerFilter, Sampler, SamplerBehavior, SamplerWrapFunction,
+    Uniforms,
+};
+use glium::vertex::EmptyVertexAttributes;
 use glium::{
-    implement_vertex, uniform, Depth, DepthTest, Display, DrawParameters, Frame, Program, Surface,
-    VertexBuffer,
+    implement_vertex, uniform, Blend, Depth, DepthTest, Display, DrawError, DrawParameters,
+    Program, Surface, VertexBuffer,
 };
 use itertools::Itertools;
+use palette::Srgb;
 use petgraph::stable_graph::NodeReferences;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Each pass blurs horizontally then vertically, widening the glow
+const BLOOM_BLUR_PASSES: usize = 4;
+/// Diameter of the editor's light icons in pixels, which does not change with distance
+pub const LIGHT_ICON_SIZE: f32 = 16.0;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Shading {
+    #[default]
+    Lit,
+    /// Albedo only, for inspecting textures without lighting
+    Unlit,
+    /// Lighting evaluated per vertex without normal maps, for low end GPUs
+    Vertex,
+}
+
+impl Shading {
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::Lit | Self::Vertex => Self::Unlit,
+            Self::Unlit => Self::Lit,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GraphicsPreset {
+    /// Keeps the game playable on integrated GPUs
+    Low,
+    High,
+}
+
+impl GraphicsPreset {
+    pub const ALL: [GraphicsPreset; 2] = [Self::Low, Self::High];
+}
+
+impl std::fmt::Display for GraphicsPreset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Low => write!(f, "Low"),
+            Self::High => write!(f, "High"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum AntiAliasing {
+    None,
+    /// Fast approximate anti-aliasing, blurs along edges found in the final image
+    #[default]
+    Fxaa,
+}
+
+impl AntiAliasing {
+    pub const ALL: [AntiAliasing; 2] = [Self::None, Self::Fxaa];
+}
+
+impl std::fmt::Display for AntiAliasing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::Fxaa => write!(f, "FXAA"),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RenderSettings {
+    /// Lay down the depth of opaque geometry before shading it, so that each pixel is only shaded once
+    pub depth_prepass: bool,
+    /// Decode albedo textures and light colors from sRGB before lighting, and encode the result back to sRGB
+    /// when post-processing. Turning this off lights the raw sRGB values, for comparison
+    pub srgb: bool,
+    /// Used by every material which does not override it
+    pub filtering: TextureFiltering,
+    pub max_anisotropy: u16,
+    pub shading: Shading,
+    pub anti_aliasing: AntiAliasing,
+    /// Bloom, vignetting and anti-aliasing. Tone mapping and gamma are always applied
+    pub post_processing: bool,
+    /// Added to the mip level of every material texture lookup, each step halves the resolution sampled
+    pub texture_lod_bias: f32,
+    /// Applied even when post-processing is off, as it is an accessibility setting
+    pub colorblind_filter: ColorblindFilter,
+    /// Measure the GPU time of each pass. Waits for the GPU to finish each pass, so slows down the frame
+    pub gpu_timing: bool,
+}
+
+impl RenderSettings {
+    pub fn preset(preset: GraphicsPreset) -> Self {
+        match preset {
+            GraphicsPreset::Low => Self {
+                depth_prepass: false,
+                filtering: TextureFiltering::Bilinear,
+                max_anisotropy: 1,
+                shading: Shading::Vertex,
+                anti_aliasing: AntiAliasing::None,
+                post_processing: false,
+                texture_lod_bias: 1.0,
+                ..Self::default()
+            },
+            GraphicsPreset::High => Self::default(),
+        }
+    }
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            depth_prepass: false,
+            srgb: true,
+            filtering: TextureFiltering::default(),
+            max_anisotropy: 8,
+            shading: Shading::default(),
+            anti_aliasing: AntiAliasing::default(),
+            post_processing: true,
+            texture_lod_bias: 0.0,
+            colorblind_filter: ColorblindFilter::default(),
+            gpu_timing: false,
+        }
+    }
+}
+
+/// Time spent on each pass of the last frame
+#[derive(Clone, Default)]
+pub struct PassTimings {
+    pub depth_prepass: Duration,
+    /// Opaque and transparent model instances
+    pub color_pass: Duration,
+    pub lines: Duration,
+    pub post_process: Duration,
+}
+
+#[derive(Clone, Default)]
+pub struct RenderStats {
+    /// Time spent submitting each pass
+    pub cpu: PassTimings,
+    /// Time the GPU spent executing each pass, only measured when `RenderSettings::gpu_timing` is on
+    pub gpu: PassTimings,
+    /// Draws of model instances, including the depth pre-pass
+    pub draw_calls: usize,
+    pub instances: usize,
+    /// Triangles drawn by the color pass
+    pub triangles: usize,
+}
 
 pub struct Renderer {
+    pub settings: RenderSettings,
+    pub stats: RenderStats,
+
     default_program: Program,
+    vertex_lit_program: Program,
+    depth_program: Program,
 
     skybox_program: Program,
-    light_program: Program,
+    sky_gradient_program: Program,
+    grid_program: Program,
+    billboard_program: Program,
+    billboard_quad_buffer: VertexBuffer<BillboardCorner>,
+    billboard_instance_buffers: VertexBufferPool<BillboardInstance>,
+    icon_program: Program,
     cube_vertex_buffer: VertexBuffer<SimplePoint>,
 
     lines_program: Program,
-    line_vertex_buffers: HashMap<u8, VertexBuffer<LinePoint>>,
+    line_quad_buffer: VertexBuffer<LineCorner>,
+    line_instance_buffers: VertexBufferPool<LineInstance>,
+    instance_buffers: VertexBufferPool<Instance>,
 
     terrain_program: Program,
+
+    text_program: Program,
+
+    post_process_program: Program,
+    bloom_threshold_program: Program,
+    blur_program: Program,
+    fxaa_program: Program,
+
+    render_targets: RenderTargetCache,
 }
 
 impl Renderer {
@@ -43,6 +224,20 @@ impl Renderer {
             display,
         )?;
 
+        let vertex_lit_program = context::new_program(
+            "assets/shaders/default/vertex_lit.vert",
+            "assets/shaders/default/vertex_lit.frag",
+            None,
+            display,
+        )?;
+
+        let depth_program = context::new_program(
+            "assets/shaders/depth/depth.vert",
+            "assets/shaders/depth/depth.frag",
+            None,
+            display,
+        )?;
+
         let lines_program = context::new_program(
             "assets/shaders/line/line.vert",
             "assets/shaders/line/line.frag",
@@ -57,9 +252,30 @@ impl Renderer {
             display,
         )?;
 
-        let light_program = context::new_program(
-            "assets/shaders/light/light.vert",
-            "assets/shaders/light/light.frag",
+        let grid_program = context::new_program(
+            "assets/shaders/post_process/post_process.vert",
+            "assets/shaders/grid/grid.frag",
+            None,
+            display,
+        )?;
+
+        let billboard_program = context::new_program(
+            "assets/shaders/billboard/billboard.vert",
+            "assets/shaders/billboard/billboard.frag",
+            None,
+            display,
+        )?;
+
+        let sky_gradient_program = context::new_program(
+            "assets/shaders/skybox/skybox.vert",
+            "assets/shaders/skybox/gradient.frag",
+            None,
+            display,
+        )?;
+
+        let icon_program = context::new_program(
+            "assets/shaders/icon/icon.vert",
+            "assets/shaders/icon/icon.frag",
             None,
             display,
         )?;
@@ -71,61 +287,419 @@ impl Renderer {
             display,
         )?;
 
-        // This will be used by the skybox and debug lights
+        let text_program = context::new_program(
+            "assets/shaders/text/text.vert",
+            "assets/shaders/text/text.frag",
+            None,
+            display,
+        )?;
+
+        let post_process_program = context::new_srgb_output_program(
+            "assets/shaders/post_process/post_process.vert",
+            "assets/shaders/post_process/post_process.frag",
+            None,
+            display,
+        )?;
+
+        let bloom_threshold_program = context::new_program(
+            "assets/shaders/post_process/post_process.vert",
+            "assets/shaders/bloom/threshold.frag",
+            None,
+            display,
+        )?;
+
+        let blur_program = context::new_program(
+            "assets/shaders/post_process/post_process.vert",
+            "assets/shaders/bloom/blur.frag",
+            None,
+            display,
+        )?;
+
+        let fxaa_program = context::new_srgb_output_program(
+            "assets/shaders/post_process/post_process.vert",
+            "assets/shaders/fxaa/fxaa.frag",
+            None,
+            display,
+        )?;
+
+        // Used by the skybox
         let cube_vertex_buffer = VertexBuffer::new(display, &primitives::CUBE)?;
 
         Ok(Self {
+            settings: RenderSettings::default(),
+            stats: RenderStats::default(),
             default_program,
+            vertex_lit_program,
+            depth_program,
             skybox_program,
-            light_program,
+            sky_gradient_program,
+            grid_program,
+            billboard_program,
+            billboard_quad_buffer: VertexBuffer::new(display, &BILLBOARD_QUAD)?,
+            billboard_instance_buffers: VertexBufferPool::new(),
+            icon_program,
             cube_vertex_buffer,
             lines_program,
-            line_vertex_buffers: HashMap::new(),
+            line_quad_buffer: VertexBuffer::new(display, &LINE_QUAD)?,
+            line_instance_buffers: VertexBufferPool::new(),
+            instance_buffers: VertexBufferPool::new(),
             terrain_program,
+            text_program,
+            post_process_program,
+            bloom_threshold_program,
+            blur_program,
+            fxaa_program,
+            render_targets: RenderTargetCache::new(),
         })
     }
 
+    /// Move the buffer pools on to the buffers of the next frame. Must be called once per presented frame, before
+    /// anything is drawn, however many times the scene is rendered in it
+    pub fn begin_frame(&mut self) {
+        self.instance_buffers.next_frame();
+        self.billboard_instance_buffers.next_frame();
+        self.line_instance_buffers.next_frame();
+    }
+
+    /// Render the opaque model instances, transparent ones are drawn by `render_transparent_model_instances`
+    #[allow(clippy::too_many_arguments)]
     pub fn render_model_instances(
         &mut self,
         model_instances: NodeReferences<ModelInstance>,
         camera_view_projection: &Matrix4<f32>,
         camera_position: Point3<f32>,
         lights: &[Light],
+        fog: &Fog,
         display: &Display<WindowSurface>,
-        target: &mut Frame,
+        target: &mut impl Surface,
     ) {
-        let batched_instances = Self::batch_model_instances(model_instances, display);
+        let frustum = Frustum::from_view_projection(camera_view_projection);
+
+        let batched_instances = self.batch_model_instances(
+            model_instances
+                .map(|(_, model_instance)| model_instance)
+                .filter(|model_instance| !model_instance.hidden && !model_instance.is_transparent())
+                .filter(|model_instance| is_in_frustum(model_instance, &frustum)),
+            camera_position,
+            display,
+        );
 
         let vp = maths::raw_matrix(*camera_view_projection);
-        let camera_position = <[f32; 3]>::from(camera_position);
 
-        let sample_behaviour = SamplerBehavior {
-            minify_filter: MinifySamplerFilter::Nearest,
-            magnify_filter: MagnifySamplerFilter::Nearest,
-            ..SamplerBehavior::default()
+        self.stats.draw_calls = 0;
+        self.stats.instances = 0;
+        self.stats.triangles = 0;
+
+        let color_pass_depth = if self.settings.depth_prepass {
+            let depth_prepass_start = Instant::now();
+            let query = self.time_elapsed_query(display);
+
+            let draw_calls =
+                self.render_depth_prepass(&batched_instances, &vp, query.as_ref(), target);
+
+            self.stats.draw_calls += draw_calls;
+            self.stats.cpu.depth_prepass = depth_prepass_start.elapsed();
+            self.stats.gpu.depth_prepass = gpu_time(query, draw_calls);
+
+            // Depth is already laid down, so only the closest fragment of each pixel is shaded
+            Depth {
+                test: DepthTest::IfEqual,
+                write: false,
+                ..Default::default()
+            }
+        } else {
+            self.stats.cpu.depth_prepass = Duration::ZERO;
+            self.stats.gpu.depth_prepass = Duration::ZERO;
+
+            Depth {
+                test: DepthTest::IfLess,
+                write: true,
+                ..Default::default()
+            }
         };
 
+        let color_pass_start = Instant::now();
+        let query = self.time_elapsed_query(display);
+
+        let draw_parameters = DrawParameters {
+            depth: color_pass_depth,
+            time_elapsed_query: query.as_ref(),
+            ..DrawParameters::default()
+        };
+
+        let mut draw_calls = 0;
+
         for (model, material, instance_buffer) in batched_instances {
+            self.stats.instances += instance_buffer.len();
+            self.stats.triangles += model.triangle_count() * instance_buffer.len();
+
+            draw_calls += self.draw_model(
+                &model,
+                &material,
+                &instance_buffer,
+                &vp,
+                camera_position,
+                lights,
+                fog,
+                &draw_parameters,
+                display,
+                target,
+            );
+        }
+
+        self.stats.draw_calls += draw_calls;
+        self.stats.cpu.color_pass = color_pass_start.elapsed();
+        self.stats.gpu.color_pass = gpu_time(query, draw_calls);
+    }
+
+    /// Render camera facing quads, batched so that each texture is one draw call
+    #[allow(clippy::mutable_key_type)]
+    pub fn render_billboards(
+        &mut self,
+        billboards: impl Iterator<Item = (Arc<Texture2D>, BillboardInstance)>,
+        view: &Matrix4<f32>,
+        projection: &Matrix4<f32>,
+        display: &Display<WindowSurface>,
+        target: &mut impl Surface,
+    ) {
+        let mut batched_billboards = HashMap::<Arc<Texture2D>, Vec<BillboardInstance>>::new();
+        for (texture, instance) in billboards {
+            batched_billboards
+                .entry(texture)
+                .or_default()
+                .push(instance);
+        }
+
+        if batched_billboards.is_empty() {
+            return;
+        }
+
+        let draw_parameters = DrawParameters {
+            depth: Depth {
+                test: DepthTest::IfLess,
+                write: true,
+                ..Default::default()
+            },
+            ..DrawParameters::default()
+        };
+
+        let sample_behaviour = self.sampler_behaviour(None, TextureWrap::Clamp);
+
+        for (texture, instances) in batched_billboards {
+            let instance_buffer = self
+                .billboard_instance_buffers
+                .upload(&instances, display)
+                .unwrap();
+
+            // The first two rows of the view matrix are the camera's right and up directions in world space
             let uniforms = uniform! {
-                vp: vp,
-                camera_position: camera_position,
-                // TODO temporary
-                light_color: <[f32; 3]>::from(lights.iter().next().unwrap_or(&Light::default()).color.to_rgb_vector3()),
-                light_position: <[f32; 3]>::from(lights.iter().next().unwrap_or(&Light::default()).position),
-                diffuse_texture: Sampler(material.diffuse.inner_texture.as_ref().unwrap(), sample_behaviour).0,
-                specular_texture: Sampler(material.specular.inner_texture.as_ref().unwrap(), sample_behaviour).0,
+                vp: maths::raw_matrix(projection * view),
+                camera_right: [view.x.x, view.y.x, view.z.x],
+                camera_up: [view.x.y, view.y.y, view.z.y],
+                billboard_texture: TextureSampler(texture.inner_texture.as_ref().unwrap(), sample_behaviour),
+                srgb: self.settings.srgb,
             };
 
+            target
+                .draw(
+                    (
+                        &self.billboard_quad_buffer,
+                        instance_buffer.slice().per_instance().unwrap(),
+                    ),
+                    NoIndices(PrimitiveType::TrianglesList),
+                    &self.billboard_program,
+                    &uniforms,
+                    &draw_parameters,
+                )
+                .unwrap();
+        }
+    }
+
+    /// Render the model instances with transparent materials, furthest from the camera first so that each
+    /// blends over everything behind it. Must be called after all opaque geometry has been drawn
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_transparent_model_instances(
+        &mut self,
+        model_instances: NodeReferences<ModelInstance>,
+        camera_view_projection: &Matrix4<f32>,
+        camera_position: Point3<f32>,
+        lights: &[Light],
+        fog: &Fog,
+        display: &Display<WindowSurface>,
+        target: &mut impl Surface,
+    ) {
+        let frustum = Frustum::from_view_projection(camera_view_projection);
+
+        let transparent_instances = model_instances
+            .map(|(_, model_instance)| model_instance)
+            .filter(|model_instance| !model_instance.hidden && model_instance.is_transparent())
+            .filter(|model_instance| is_in_frustum(model_instance, &frustum))
+            .map(|model_instance| (model_instance, model_instance.distance_to(camera_position)))
+            .filter(|(model_instance, _)| {
+                model_instance
+                    .model_for_camera(camera_position)
+                    .meshes
+                    .lock()
+                    .unwrap()
+                    .is_some()
+            })
+            .sorted_by(|(_, a), (_, b)| b.total_cmp(a))
+            .collect_vec();
+
+        if transparent_instances.is_empty() {
+            return;
+        }
+
+        let vp = maths::raw_matrix(*camera_view_projection);
+
+        let color_pass_start = Instant::now();
+        let query = self.time_elapsed_query(display);
+
+        // Transparent surfaces must not hide what is drawn behind them afterwards
+        let draw_parameters = DrawParameters {
+            depth: Depth {
+                test: DepthTest::IfLess,
+                write: false,
+                ..Default::default()
+            },
+            blend: Blend::alpha_blending(),
+            time_elapsed_query: query.as_ref(),
+            ..DrawParameters::default()
+        };
+
+        let mut draw_calls = 0;
+
+        for (model_instance, _) in transparent_instances {
+            let instance = Instance {
+                transform: maths::raw_matrix(Matrix4::from(model_instance.transform.clone())),
+            };
+            let model = model_instance.model_for_camera(camera_position);
+
+            self.stats.instances += 1;
+            self.stats.triangles += model.triangle_count();
+
+            let instance_buffer = self.instance_buffers.upload(&[instance], display).unwrap();
+
+            draw_calls += self.draw_model(
+                model,
+                model_instance.material.as_ref().unwrap(),
+                &instance_buffer,
+                &vp,
+                camera_position,
+                lights,
+                fog,
+                &draw_parameters,
+                display,
+                target,
+            );
+        }
+
+        // Part of the same pass as the opaque instances
+        self.stats.draw_calls += draw_calls;
+        self.stats.cpu.color_pass += color_pass_start.elapsed();
+        self.stats.gpu.color_pass += gpu_time(query, draw_calls);
+    }
+
+    /// Returns the number of draw calls made
+    #[allow(clippy::too_many_arguments)]
+    fn draw_model(
+        &self,
+        model: &Model,
+        material: &Material,
+        instance_buffer: &PooledVertexBuffer<Instance>,
+        vp: &[[f32; 4]; 4],
+        camera_position: Point3<f32>,
+        lights: &[Light],
+        fog: &Fog,
+        draw_parameters: &DrawParameters,
+        display: &Display<WindowSurface>,
+        target: &mut impl Surface,
+    ) -> usize {
+        let flat_normal = Texture2D::flat_normal(display).unwrap();
+        let emissive_texture = match &material.emissive {
+            Some(emissive) => emissive.clone(),
+            None => Texture2D::black(display).unwrap(),
+        };
+        let sample_behaviour = self.sampler_behaviour(material.filtering, material.wrap);
+        let instances = instance_buffer.slice();
+        let program = match self.settings.shading {
+            Shading::Vertex => &self.vertex_lit_program,
+            Shading::Lit | Shading::Unlit => &self.default_program,
+        };
+        // TODO temporary, only the first light is used
+        let light = lights.first().cloned().unwrap_or_default();
+        let mut draw_calls = 0;
+
+        for mesh in model.meshes.lock().unwrap().iter().flatten() {
+            for primitive in mesh.primitives.iter() {
+                let normal_texture = material
+                    .normal
+                    .as_ref()
+                    .or(primitive.normal_texture.as_ref())
+                    .unwrap_or(&flat_normal);
+
+                let uniforms = uniform! {
+                    vp: *vp,
+                    camera_position: <[f32; 3]>::from(camera_position),
+                    light_color: <[f32; 3]>::from(light.color.to_rgb_vector3()),
+                    light_position: <[f32; 3]>::from(light.position),
+                    light_intensity: light.intensity,
+                    light_range: light.range.unwrap_or(0.0),
+                    diffuse_texture: TextureSampler(material.diffuse.inner_texture.as_ref().unwrap(), sample_behaviour),
+                    specular_texture: TextureSampler(material.specular.inner_texture.as_ref().unwrap(), sample_behaviour),
+                    normal_texture: TextureSampler(normal_texture.inner_texture.as_ref().unwrap(), sample_behaviour),
+                    emissive_texture: TextureSampler(emissive_texture.inner_texture.as_ref().unwrap(), sample_behaviour),
+                    emissive_strength: material.emissive_strength.0,
+                    srgb: self.settings.srgb,
+                    unlit: self.settings.shading == Shading::Unlit,
+                    texture_lod_bias: self.settings.texture_lod_bias,
+                    fog_falloff: fog.falloff.shader_index(),
+                    fog_color: self.shader_color(fog.color),
+                    fog_start: fog.start,
+                    fog_end: fog.end,
+                    fog_density: fog.density,
+                };
+
+                target
+                    .draw(
+                        (&primitive.vertex_buffer, instances.per_instance().unwrap()),
+                        &primitive.index_buffer,
+                        program,
+                        &uniforms,
+                        draw_parameters,
+                    )
+                    .unwrap();
+
+                draw_calls += 1;
+            }
+        }
+
+        draw_calls
+    }
+
+    /// Returns the number of draw calls made
+    fn render_depth_prepass(
+        &self,
+        batched_instances: &[(Arc<Model>, Material, PooledVertexBuffer<Instance>)],
+        vp: &[[f32; 4]; 4],
+        query: Option<&TimeElapsedQuery>,
+        target: &mut impl Surface,
+    ) -> usize {
+        let uniforms = uniform! {
+            vp: *vp,
+        };
+        let mut draw_calls = 0;
+
+        for (model, _, instance_buffer) in batched_instances.iter() {
+            let instances = instance_buffer.slice();
+
             for mesh in model.meshes.lock().unwrap().iter().flatten() {
                 for primitive in mesh.primitives.iter() {
                     target
                         .draw(
-                            (
-                                &primitive.vertex_buffer,
-                                instance_buffer.per_instance().unwrap(),
-                            ),
+                            (&primitive.vertex_buffer, instances.per_instance().unwrap()),
                             &primitive.index_buffer,
-                            &self.default_program,
+                            &self.depth_program,
                             &uniforms,
                             &DrawParameters {
                                 depth: Depth {
@@ -133,13 +707,19 @@ impl Renderer {
                                     write: true,
                                     ..Default::default()
                                 },
+                                color_mask: (false, false, false, false),
+                                time_elapsed_query: query,
                                 ..DrawParameters::default()
                             },
                         )
                         .unwrap();
+
+                    draw_calls += 1;
                 }
             }
         }
+
+        draw_calls
     }
 
     pub fn render_terrain(
@@ -147,11 +727,17 @@ impl Renderer {
         terrain: &Terrain,
         view_projection: &Matrix4<f32>,
         camera_position: Point3<f32>,
-        target: &mut Frame,
+        fog: &Fog,
+        target: &mut impl Surface,
     ) {
         let uniforms = uniform! {
             vp: maths::raw_matrix(*view_projection),
             camera_position: <[f32; 3]>::from(camera_position),
+            fog_falloff: fog.falloff.shader_index(),
+            fog_color: self.shader_color(fog.color),
+            fog_start: fog.start,
+            fog_end: fog.end,
+            fog_density: fog.density,
         };
 
         target
@@ -177,21 +763,19 @@ impl Renderer {
         cubemap: &Cubemap,
         view: &Matrix4<f32>,
         projection: &Matrix4<f32>,
-        target: &mut Frame,
+        sky_distance: f32,
+        target: &mut impl Surface,
     ) {
-        // Strip translation from view matrix = skybox is always in the same place
-        let view = Matrix4::from(Matrix3::from_cols(view.x.xyz(), view.y.xyz(), view.z.xyz()));
-        let view_projection = projection * view;
+        let view_projection = Self::sky_view_projection(view, projection, sky_distance);
 
-        let sample_behaviour = SamplerBehavior {
-            minify_filter: MinifySamplerFilter::Nearest,
-            magnify_filter: MagnifySamplerFilter::Nearest,
-            ..SamplerBehavior::default()
-        };
+        // The cubemap has no mipmaps to blend between
+        let sample_behaviour =
+            self.sampler_behaviour(Some(TextureFiltering::Bilinear), TextureWrap::default());
 
         let uniforms = uniform! {
             vp: maths::raw_matrix(view_projection),
-            skybox: Sampler(cubemap.inner_cubemap.as_ref().unwrap(), sample_behaviour).0
+            skybox: Sampler(cubemap.inner_cubemap.as_ref().unwrap(), sample_behaviour),
+            srgb: self.settings.srgb,
         };
 
         target
@@ -205,47 +789,159 @@ impl Renderer {
             .unwrap();
     }
 
+    /// Fill the sky with a gradient from `horizon` to `zenith`, in place of a skybox
+    pub fn render_sky_gradient(
+        &mut self,
+        horizon: Color,
+        zenith: Color,
+        view: &Matrix4<f32>,
+        projection: &Matrix4<f32>,
+        sky_distance: f32,
+        target: &mut impl Surface,
+    ) {
+        let view_projection = Self::sky_view_projection(view, projection, sky_distance);
+
+        let uniforms = uniform! {
+            vp: maths::raw_matrix(view_projection),
+            horizon_color: self.shader_color(horizon),
+            zenith_color: self.shader_color(zenith),
+        };
+
+        target
+            .draw(
+                &self.cube_vertex_buffer,
+                NoIndices(PrimitiveType::TrianglesList),
+                &self.sky_gradient_program,
+                &uniforms,
+                &DrawParameters::default(),
+            )
+            .unwrap();
+    }
+
+    /// An infinite grid on the ground plane, fading out by `fade_distance` from the camera
+    pub fn render_grid(
+        &mut self,
+        view_projection: &Matrix4<f32>,
+        camera_position: Point3<f32>,
+        fade_distance: f32,
+        target: &mut impl Surface,
+    ) {
+        let Some(inverse_view_projection) = view_projection.invert() else {
+            return;
+        };
+
+        let uniforms = uniform! {
+            inverse_vp: maths::raw_matrix(inverse_view_projection),
+            vp: maths::raw_matrix(*view_projection),
+            camera_position: <[f32; 3]>::from(camera_position),
+            fade_distance: fade_distance,
+        };
+
+        // Drawn over the scene, but hidden behind anything in front of it
+        target
+            .draw(
+                EmptyVertexAttributes { len: 3 },
+                NoIndices(PrimitiveType::TrianglesList),
+                &self.grid_program,
+                &uniforms,
+                &DrawParameters {
+                    depth: Depth {
+                        test: DepthTest::IfLess,
+                        write: false,
+                        ..Default::default()
+                    },
+                    blend: Blend::alpha_blending(),
+                    ..DrawParameters::default()
+                },
+            )
+            .unwrap();
+    }
+
+    fn sky_view_projection(
+        view: &Matrix4<f32>,
+        projection: &Matrix4<f32>,
+        sky_distance: f32,
+    ) -> Matrix4<f32> {
+        // Strip translation from view matrix = skybox is always in the same place
+        let view = Matrix4::from(Matrix3::from_cols(view.x.xyz(), view.y.xyz(), view.z.xyz()));
+
+        projection * view * Matrix4::from_scale(sky_distance)
+    }
+
+    /// Colors are passed to shaders in linear space when lighting is sRGB correct
+    fn shader_color(&self, color: Color) -> [f32; 3] {
+        let rgb = if self.settings.srgb {
+            color.to_linear_rgb_vector4()
+        } else {
+            color.to_rgb_vector4()
+        };
+
+        <[f32; 3]>::from(rgb.truncate())
+    }
+
+    /// Draw lines as quads facing the camera, so that they can be any width and are anti-aliased
     pub fn render_lines(
         &mut self,
         lines: &[Line],
+        polylines: &[Polyline],
         camera_view_projection: &Matrix4<f32>,
         display: &Display<WindowSurface>,
-        target: &mut Frame,
+        target: &mut impl Surface,
     ) {
-        if lines.is_empty() {
+        let line_instances = lines
+            .iter()
+            .cloned()
+            .chain(polylines.iter().flat_map(Polyline::segments))
+            .map(LineInstance::from)
+            .collect_vec();
+
+        if line_instances.is_empty() {
             return;
         }
 
-        let batched_lines = Self::batch_lines(lines);
+        let lines_start = Instant::now();
+        let query = self.time_elapsed_query(display);
 
-        self.write_lines_to_vertex_buffers(display, batched_lines);
+        let instance_buffer = self
+            .line_instance_buffers
+            .upload(&line_instances, display)
+            .unwrap();
+
+        let (width, height) = target.get_dimensions();
 
         let uniforms = uniform! {
             vp: maths::raw_matrix(*camera_view_projection),
+            viewport_size: [width as f32, height as f32],
         };
 
-        for (width, line_points) in self.line_vertex_buffers.iter() {
-            target
-                .draw(
-                    line_points,
-                    NoIndices(PrimitiveType::LinesList),
-                    &self.lines_program,
-                    &uniforms,
-                    &DrawParameters {
-                        line_width: Some(*width as f32),
-                        ..DrawParameters::default()
-                    },
-                )
-                .unwrap();
-        }
+        target
+            .draw(
+                (
+                    &self.line_quad_buffer,
+                    instance_buffer.slice().per_instance().unwrap(),
+                ),
+                NoIndices(PrimitiveType::TrianglesList),
+                &self.lines_program,
+                &uniforms,
+                &DrawParameters {
+                    blend: Blend::alpha_blending(),
+                    time_elapsed_query: query.as_ref(),
+                    ..DrawParameters::default()
+                },
+            )
+            .unwrap();
+
+        self.stats.cpu.lines = lines_start.elapsed();
+        self.stats.gpu.lines = gpu_time(query, 1);
     }
 
+    /// Draw each light as a round icon of constant size on screen, rather than geometry in the scene
     pub fn render_lights(
         &mut self,
         lights: &[Light],
         camera_view_projection: &Matrix4<f32>,
         display: &Display<WindowSurface>,
-        target: &mut Frame,
+        target: &mut impl Surface,
     ) {
         if lights.is_empty() {
             return;
@@ -258,18 +954,22 @@ impl Renderer {
 
         let light_instance_buffer = VertexBuffer::new(display, &shader_lights).unwrap();
 
+        let (width, height) = target.get_dimensions();
+
         let uniforms = uniform! {
             vp: maths::raw_matrix(*camera_view_projection),
+            icon_size: LIGHT_ICON_SIZE,
+            viewport_size: [width as f32, height as f32],
         };
 
         target
             .draw(
                 (
-                    &self.cube_vertex_buffer,
+                    &self.billboard_quad_buffer,
                     light_instance_buffer.per_instance().unwrap(),
                 ),
                 NoIndices(PrimitiveType::TrianglesList),
-                &self.light_program,
+                &self.icon_program,
                 &uniforms,
                 &DrawParameters {
                     depth: Depth {
@@ -283,51 +983,268 @@ impl Renderer {
             .unwrap();
     }
 
-    fn write_lines_to_vertex_buffers(
+    /// An offscreen target which persists between frames and always matches the size of the window
+    pub fn render_target(
         &mut self,
+        name: &'static str,
+        format: RenderTargetFormat,
         display: &Display<WindowSurface>,
-        batched_lines: HashMap<u8, Vec<LinePoint>>,
-    ) {
-        for (width, lines) in batched_lines.iter() {
-            if self.line_vertex_buffers.contains_key(width) {
-                self.line_vertex_buffers.get(width).unwrap().write(lines);
-            } else {
-                self.line_vertex_buffers
-                    .insert(*width, VertexBuffer::dynamic(display, lines).unwrap());
-            }
+    ) -> Result<RenderTarget> {
+        self.render_targets.get(name, format, display)
+    }
+
+    /// Sampler for a material's textures, using the global filtering unless the material overrides it
+    fn sampler_behaviour(
+        &self,
+        filtering: Option<TextureFiltering>,
+        wrap: TextureWrap,
+    ) -> SamplerBehavior {
+        let wrap_function = wrap.wrap_function();
+
+        SamplerBehavior {
+            wrap_function: (wrap_function, wrap_function, wrap_function),
+            ..filtering
+                .unwrap_or(self.settings.filtering)
+                .sampler_behaviour(self.settings.max_anisotropy)
         }
     }
 
-    fn batch_lines(lines: &[Line]) -> HashMap<u8, Vec<LinePoint>> {
-        let mut batched_lines = HashMap::<u8, Vec<LinePoint>>::new();
+    /// The HDR targets which the scene is drawn into before post-processing
+    pub fn scene_target(
+        &mut self,
+        dimensions: (u32, u32),
+        display: &Display<WindowSurface>,
+    ) -> Result<SceneTarget> {
+        // Every offscreen target of the frame matches the size of the scene
+        self.render_targets.resize(dimensions);
 
-        for line in lines.iter() {
-            let line_points = vec![
-                LinePoint {
-                    position: <[f32; 3]>::from(line.p1),
-                    color: *line.color.as_ref(),
+        Ok(SceneTarget {
+            color: self.render_target(
+                "scene",
+                RenderTargetFormat {
+                    color: UncompressedFloatFormat::F16F16F16F16,
+                    depth: Some(DepthFormat::F32),
                 },
-                LinePoint {
-                    position: <[f32; 3]>::from(line.p2),
-                    color: *line.color.as_ref(),
+                display,
+            )?,
+            emissive: self.render_target(
+                "scene_emissive",
+                RenderTargetFormat {
+                    color: UncompressedFloatFormat::F16F16F16F16,
+                    depth: None,
                 },
-            ];
+                display,
+            )?,
+        })
+    }
+
+    /// Tonemap, gamma correct and apply effects to `scene_target`, writing the result to `target`
+    pub fn post_process(
+        &mut self,
+        scene_target: &SceneTarget,
+        settings: &PostProcessSettings,
+        display: &Display<WindowSurface>,
+        target: &mut impl Surface,
+    ) {
+        let post_process_start = Instant::now();
+        let query = self.time_elapsed_query(display);
+
+        // Adding nothing to the scene is the same as adding the scene with no intensity
+        let post_processing = self.settings.post_processing;
+
+        let bloom = if post_processing && settings.bloom_intensity > 0.0 {
+            self.render_bloom(
+                scene_target,
+                settings.bloom_threshold,
+                query.as_ref(),
+                display,
+            )
+            .unwrap()
+        } else {
+            scene_target.color.clone()
+        };
+
+        let uniforms = uniform! {
+            scene: Sampler::new(&*scene_target.color.color)
+                .minify_filter(MinifySamplerFilter::Nearest)
+                .magnify_filter(MagnifySamplerFilter::Nearest),
+            bloom: Sampler::new(&*bloom.color)
+                .minify_filter(MinifySamplerFilter::Nearest)
+                .magnify_filter(MagnifySamplerFilter::Nearest),
+            bloom_intensity: if post_processing { settings.bloom_intensity } else { 0.0 },
+            exposure: settings.exposure,
+            tonemapping: settings.tonemapping.shader_index(),
+            gamma: settings.gamma,
+            vignette: if post_processing { settings.vignette } else { 0.0 },
+            srgb: self.settings.srgb,
+            colorblind_simulation: self.settings.colorblind_filter.simulation_matrix(),
+        };
+
+        let anti_aliasing = if post_processing {
+            self.settings.anti_aliasing
+        } else {
+            AntiAliasing::None
+        };
+
+        match anti_aliasing {
+            AntiAliasing::None => draw_fullscreen(
+                &self.post_process_program,
+                &uniforms,
+                query.as_ref(),
+                target,
+            )
+            .unwrap(),
+            AntiAliasing::Fxaa => {
+                let post_processed = self
+                    .render_target(
+                        "post_processed",
+                        RenderTargetFormat {
+                            color: UncompressedFloatFormat::U8U8U8U8,
+                            depth: None,
+                        },
+                        display,
+                    )
+                    .unwrap();
 
-            batched_lines
-                .entry(line.width)
-                .and_modify(|lines| lines.extend(&line_points))
-                .or_insert(line_points);
+                draw_fullscreen(
+                    &self.post_process_program,
+                    &uniforms,
+                    query.as_ref(),
+                    &mut post_processed.framebuffer(display).unwrap(),
+                )
+                .unwrap();
+
+                let fxaa_uniforms = uniform! {
+                    image: Sampler::new(&*post_processed.color)
+                        .minify_filter(MinifySamplerFilter::Linear)
+                        .magnify_filter(MagnifySamplerFilter::Linear)
+                        .wrap_function(SamplerWrapFunction::Clamp),
+                };
+
+                draw_fullscreen(&self.fxaa_program, &fxaa_uniforms, query.as_ref(), target)
+                    .unwrap();
+            }
         }
-        batched_lines
+
+        // There is always at least the final draw onto `target`
+        self.stats.cpu.post_process = post_process_start.elapsed();
+        self.stats.gpu.post_process = gpu_time(query, 1);
+    }
+
+    /// Extract the parts of the scene brighter than `threshold`, along with anything emissive, and blur them, ping-ponging between two targets
+    fn render_bloom(
+        &mut self,
+        scene_target: &SceneTarget,
+        threshold: f32,
+        query: Option<&TimeElapsedQuery>,
+        display: &Display<WindowSurface>,
+    ) -> Result<RenderTarget> {
+        let format = RenderTargetFormat {
+            color: UncompressedFloatFormat::F16F16F16F16,
+            depth: None,
+        };
+
+        let ping = self.render_target("bloom_ping", format, display)?;
+        let pong = self.render_target("bloom_pong", format, display)?;
+
+        let sampler = |texture| {
+            Sampler::new(texture)
+                .minify_filter(MinifySamplerFilter::Linear)
+                .magnify_filter(MagnifySamplerFilter::Linear)
+                .wrap_function(SamplerWrapFunction::Clamp)
+        };
+
+        draw_fullscreen(
+            &self.bloom_threshold_program,
+            &uniform! {
+                scene: sampler(&*scene_target.color.color),
+                emissive: sampler(&*scene_target.emissive.color),
+                threshold: threshold,
+            },
+            query,
+            &mut ping.framebuffer(display)?,
+        )?;
+
+        for _ in 0..BLOOM_BLUR_PASSES {
+            for (source, destination, direction) in
+                [(&ping, &pong, [1.0_f32, 0.0]), (&pong, &ping, [0.0, 1.0])]
+            {
+                draw_fullscreen(
+                    &self.blur_program,
+                    &uniform! {
+                        image: sampler(&*source.color),
+                        direction: direction,
+                    },
+                    query,
+                    &mut destination.framebuffer(display)?,
+                )?;
+            }
+        }
+
+        Ok(ping)
+    }
+
+    /// A query for timing a pass on the GPU, if GPU timing is enabled and supported
+    fn time_elapsed_query(&self, display: &Display<WindowSurface>) -> Option<TimeElapsedQuery> {
+        if self.settings.gpu_timing {
+            TimeElapsedQuery::new(display).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Draw `text` over the scene, where `position` is the top left of the text in pixels
+    pub fn render_text(
+        &mut self,
+        font: &Font,
+        text: &str,
+        position: [f32; 2],
+        size: f32,
+        color: Srgb,
+        display: &Display<WindowSurface>,
+        target: &mut impl Surface,
+    ) {
+        let vertices = font.layout(text, position, size);
+        if vertices.is_empty() {
+            return;
+        }
+
+        let vertex_buffer = VertexBuffer::new(display, &vertices).unwrap();
+
+        let (width, height) = target.get_dimensions();
+
+        let uniforms = uniform! {
+            projection: maths::raw_matrix(maths::orthographic_projection(width, height)),
+            atlas: Sampler::new(&font.atlas)
+                .minify_filter(MinifySamplerFilter::Linear)
+                .magnify_filter(MagnifySamplerFilter::Linear),
+            color: [color.red, color.green, color.blue],
+        };
+
+        target
+            .draw(
+                &vertex_buffer,
+                NoIndices(PrimitiveType::TrianglesList),
+                &self.text_program,
+                &uniforms,
+                &DrawParameters {
+                    blend: Blend::alpha_blending(),
+                    ..DrawParameters::default()
+                },
+            )
+            .unwrap();
     }
 
     /// Batches instances with the same models and texture
     #[allow(clippy::mutable_key_type)]
-    fn batch_model_instances(
-        model_instances: NodeReferences<ModelInstance>,
+    fn batch_model_instances<'a>(
+        &mut self,
+        model_instances: impl Iterator<Item = &'a ModelInstance>,
+        camera_position: Point3<f32>,
         display: &Display<WindowSurface>,
-    ) -> Vec<(Arc<Model>, Material, VertexBuffer<Instance>)> {
-        let instance_map = Self::group_instances_on_model_and_texture(model_instances, display);
+    ) -> Vec<(Arc<Model>, Material, PooledVertexBuffer<Instance>)> {
+        let instance_map =
+            Self::group_instances_on_model_and_texture(model_instances, camera_position, display);
 
         instance_map
             .into_iter()
@@ -335,22 +1252,25 @@ impl Renderer {
                 (
                     model,
                     texture,
-                    // TODO cache vertex buffers and write over them on next frame
-                    VertexBuffer::new(display, &instances).unwrap(),
+                    self.instance_buffers.upload(&instances, display).unwrap(),
                 )
             })
             .collect_vec()
     }
 
     #[allow(clippy::mutable_key_type)]
-    fn group_instances_on_model_and_texture(
-        model_instances: NodeReferences<ModelInstance>,
+    /// Instances are grouped on the level of detail which is drawn for them, rather than their full model
+    fn group_instances_on_model_and_texture<'a>(
+        model_instances: impl Iterator<Item = &'a ModelInstance>,
+        camera_position: Point3<f32>,
         display: &Display<WindowSurface>,
     ) -> HashMap<(Arc<Model>, Material), Vec<Instance>> {
         let mut instance_map = HashMap::<(Arc<Model>, Material), Vec<Instance>>::new();
 
-        for (_, model_instance) in model_instances {
-            if model_instance.model.meshes.lock().unwrap().is_some() {
+        for model_instance in model_instances {
+            let model = model_instance.model_for_camera(camera_position);
+
+            if model.meshes.lock().unwrap().is_some() {
                 let transform_matrix = Matrix4::from(model_instance.transform.clone());
 
                 let instance = Instance {
@@ -363,8 +1283,8 @@ impl Renderer {
                 };
 
                 instance_map
-                    .entry((model_instance.model.clone(), material))
-                    .or_insert(vec![instance])
+                    .entry((model.clone(), material))
+                    .or_default()
                     .push(instance);
             }
         }
@@ -377,3 +1297,38 @@ struct Instance {
     transform: [[f32; 4]; 4],
 }
 implement_vertex!(Instance, transform);
+
+/// Draw a single triangle covering the whole of `target`, for passes which only sample textures
+fn draw_fullscreen(
+    program: &Program,
+    uniforms: &impl Uniforms,
+    query: Option<&TimeElapsedQuery>,
+    target: &mut impl Surface,
+) -> std::result::Result<(), DrawError> {
+    target.draw(
+        EmptyVertexAttributes { len: 3 },
+        NoIndices(PrimitiveType::TrianglesList),
+        program,
+        uniforms,
+        &DrawParameters {
+            time_elapsed_query: query,
+            ..DrawParameters::default()
+        },
+    )
+}
+
+/// Instances whose model has not loaded yet have no bounds, so are never culled
+fn is_in_frustum(model_instance: &ModelInstance, frustum: &Frustum) -> bool {
+    model_instance
+        .bounding_sphere()
+        .map_or(true, |sphere| frustum.intersects_sphere(&sphere))
+}
+
+/// Wait for the result of `query`. A query which was never used in a draw has no result, so is only read when
+/// `draw_calls` is non zero
+fn gpu_time(query: Option<TimeElapsedQuery>, draw_calls: usize) -> Duration {
+    match query {
+        Some(query) if draw_calls > 0 => Duration::from_nanos(query.get() as u64),
+        _ => Duration::ZERO,
+    }
+}
@@ -1,24 +1,76 @@
-use crate::camera::FpsCamera;
+use crate::billboard::{Billboard, BillboardInstance};
+use crate::camera::{Camera, FpsCamera, OrbitalCamera};
+use crate::colliders::{AABBCollider, BoundingSphere, Ray};
 use crate::colors::{Color, ColorExt};
+use crate::export::glb;
+use crate::fog::Fog;
 use crate::light::Light;
-use crate::line::Line;
-use crate::models::Model;
+use crate::line::{Line, Polyline};
+use crate::models::merge::{merge_primitives, MergePart};
 use crate::models::ModelInstance;
+use crate::models::{DecodedMaterial, DecodedMesh, Material, MetadataValue, Model};
+use crate::post_processing::PostProcessSettings;
 use crate::renderer::Renderer;
 use crate::terrain::Terrain;
 use crate::texture::{Cubemap, Texture2D};
-use cgmath::{Matrix4, Point3};
+use crate::transform::Transform;
+use cgmath::{EuclideanSpace, Matrix4, MetricSpace, Point3, Vector3, Vector4};
+use color_eyre::eyre::{eyre, WrapErr};
 use color_eyre::Result;
 use glium::glutin::surface::WindowSurface;
-use glium::{Display, Frame, Surface};
+use glium::{Display, Surface};
 use itertools::Itertools;
 use petgraph::prelude::StableDiGraph;
+use petgraph::stable_graph::NodeIndex;
 use petgraph::visit::IntoNodeReferences;
+use petgraph::Direction;
 use rfd::FileDialog;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+/// A model file read without the GPU, so that the slow part of importing can be done on another thread
+pub struct DecodedModel {
+    path: PathBuf,
+    /// The node hierarchy and materials, which OBJ files do not have
+    document: Option<gltf::Document>,
+    meshes: Vec<DecodedMesh>,
+    /// By their index in the document
+    materials: Vec<DecodedMaterial>,
+}
+
+impl DecodedModel {
+    pub fn decode(path: &Path) -> Result<Self> {
+        let (document, meshes, images) = Model::decode(path)?;
+
+        // Images are decoded here too, so that only uploading them is left for the main thread
+        let materials = document
+            .iter()
+            .flat_map(|document| document.materials())
+            .map(|material| Material::decode_gltf(&material, path, &images))
+            .collect_vec();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            document,
+            meshes,
+            materials,
+        })
+    }
+}
+
+/// The meshes of a model being imported, which are uploaded as the first node using each is reached
+struct NodeImport<'a> {
+    path: &'a Path,
+    meshes: Vec<Option<DecodedMesh>>,
+    /// By their index in the document, or None for those which use the default material
+    materials: Vec<Option<Material>>,
+    models: HashMap<usize, Arc<Model>>,
+}
+
 #[derive(PartialEq, Serialize, Deserialize)]
 pub enum Background {
     Color(Color),
@@ -31,16 +83,89 @@ impl Default for Background {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CameraSettings {
+    pub near: f32,
+    pub far: f32,
+    /// Distance from the camera at which the skybox is drawn, must lie between the near and far planes
+    pub sky_distance: f32,
+}
+
+impl CameraSettings {
+    pub fn sky_clipped(&self) -> bool {
+        // The corners of the skybox are the furthest points from the camera
+        self.sky_distance < self.near || self.sky_distance * 3.0_f32.sqrt() > self.far
+    }
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            near: 0.01,
+            far: 100.0,
+            sky_distance: 1.0,
+        }
+    }
+}
+
+/// How much of a node lies beyond the far plane
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum FarPlaneClipping {
+    /// Some of the node reaches past the far plane and is cut off
+    Partly,
+    /// The whole node is beyond the far plane and is not drawn
+    Entirely,
+}
+
+/// A named group of nodes which can be selected together
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SelectionSet {
+    pub name: String,
+    pub nodes: Vec<NodeIndex>,
+}
+
+/// Copies of a node and its descendants, which can be added to a scene any number of times
+#[derive(Clone)]
+pub struct Subtree {
+    /// Each node with the position of its parent in the list. The root comes first and is the only one without
+    nodes: Vec<(ModelInstance, Option<usize>)>,
+}
+
+/// State which is only used by the editor, but saved with the scene
+#[derive(Default, Serialize, Deserialize)]
+pub struct EditorMetadata {
+    pub selection_sets: Vec<SelectionSet>,
+    /// The camera of each viewport when the scene was saved, so that reopening it returns to the same views
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cameras: Vec<OrbitalCamera>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Scene {
     pub title: String,
     pub camera: FpsCamera, // the camera state to be used when starting the game
+    #[serde(default)]
+    pub camera_settings: CameraSettings,
+    #[serde(default)]
+    pub post_processing: PostProcessSettings,
+    #[serde(default)]
+    pub fog: Fog,
+    /// Color of the sky straight up, fading into the background color at the horizon. Only used with a color
+    /// background
+    #[serde(default)]
+    pub sky_gradient: Option<Color>,
+    #[serde(default)]
+    pub editor: EditorMetadata,
     pub graph: StableDiGraph<ModelInstance, ()>,
     pub background: Background,
     pub lights: Vec<Light>,
+    #[serde(default)]
+    pub billboards: Vec<Billboard>,
     pub terrain: Option<Terrain>,
     #[serde(skip)]
     pub lines: Vec<Line>,
+    #[serde(skip)]
+    pub polylines: Vec<Polyline>,
 }
 
 impl Scene {
@@ -48,11 +173,18 @@ impl Scene {
         Self {
             graph: StableDiGraph::new(),
             lines: vec![],
+            polylines: vec![],
             title: title.to_owned(),
             camera: FpsCamera::default(),
+            camera_settings: CameraSettings::default(),
+            post_processing: PostProcessSettings::default(),
+            fog: Fog::default(),
+            sky_gradient: None,
+            editor: EditorMetadata::default(),
             background: Background::default(),
             terrain: None,
             lights: vec![],
+            billboards: vec![],
         }
     }
 
@@ -61,25 +193,63 @@ impl Scene {
     }
 
     pub fn from_string(scene_string: &str, display: &Display<WindowSurface>) -> Result<Self> {
-        let mut scene = serde_json::from_str::<Scene>(scene_string)?;
+        let mut scene = Self::parse(scene_string)?;
+
+        scene
+            .camera
+            .set_clipping_planes(scene.camera_settings.near, scene.camera_settings.far);
 
         let node_indices = scene.graph.node_indices().collect_vec();
 
         // Load assets which require Display
         for node_index in node_indices {
-            // Cannot change call to unwrap to "?" because Mutex is not Send, and ErrReport must be Send
-            if scene.graph[node_index]
-                .model
-                .meshes
-                .lock()
-                .unwrap()
-                .is_none()
-            {
-                scene.graph[node_index].model.load_meshes(display).unwrap()
+            let model_instance = &mut scene.graph[node_index];
+
+            let meshes_loaded = model_instance.model.meshes.lock().unwrap().is_some();
+            if !meshes_loaded {
+                model_instance
+                    .model
+                    .load_meshes(display)
+                    .wrap_err_with(|| format!("Failed to load \"{}\"", model_instance.name))?;
+            }
+
+            for level_of_detail in model_instance.levels_of_detail.iter() {
+                let meshes_loaded = level_of_detail.model.meshes.lock().unwrap().is_some();
+                if !meshes_loaded {
+                    level_of_detail
+                        .model
+                        .load_meshes(display)
+                        .wrap_err_with(|| {
+                            format!(
+                                "Failed to load a level of detail of \"{}\"",
+                                model_instance.name
+                            )
+                        })?;
+                }
             }
 
-            if let Some(material) = scene.graph[node_index].material.as_mut() {
-                material.diffuse = Texture2D::load(material.diffuse.path.clone(), display)?;
+            if let Some(material) = model_instance.material.as_mut() {
+                material.diffuse = material.diffuse.load_saved(display).wrap_err_with(|| {
+                    format!("Failed to load the material of \"{}\"", model_instance.name)
+                })?;
+
+                if let Some(emissive) = material.emissive.as_mut() {
+                    *emissive = emissive.load_saved(display).wrap_err_with(|| {
+                        format!(
+                            "Failed to load the emissive texture of \"{}\"",
+                            model_instance.name
+                        )
+                    })?;
+                }
+
+                if let Some(normal) = material.normal.as_mut() {
+                    *normal = normal.load_saved(display).wrap_err_with(|| {
+                        format!(
+                            "Failed to load the normal map of \"{}\"",
+                            model_instance.name
+                        )
+                    })?;
+                }
             }
         }
 
@@ -93,6 +263,11 @@ impl Scene {
         //     }
         // }
 
+        for billboard in scene.billboards.iter_mut() {
+            billboard.texture = Texture2D::load(billboard.texture.path.clone(), display)
+                .wrap_err_with(|| format!("Failed to load the billboard \"{}\"", billboard.name))?;
+        }
+
         if let Background::HDRI(cubemap) = scene.background {
             scene.background = Background::HDRI(Cubemap::load(cubemap.directory.clone(), display)?);
         }
@@ -100,6 +275,20 @@ impl Scene {
         Ok(scene)
     }
 
+    /// Parse a scene without loading any of its assets. Errors give the path of the field which failed to parse
+    /// along with its line and column
+    pub fn parse(scene_string: &str) -> Result<Self> {
+        let mut deserializer = serde_json::Deserializer::from_str(scene_string);
+
+        let scene = serde_path_to_error::deserialize(&mut deserializer)
+            .map_err(|err| eyre!("Invalid scene at \"{}\": {}", err.path(), err.inner()))?;
+
+        // Reject anything after the scene, such as two scenes pasted together
+        deserializer.end()?;
+
+        Ok(scene)
+    }
+
     pub fn save_as(&self) {
         let serialized = serde_json::to_string(self).unwrap();
 
@@ -110,15 +299,699 @@ impl Scene {
         });
     }
 
+    /// Every model and texture file used by the scene
+    pub fn asset_paths(&self) -> Vec<PathBuf> {
+        let models = self.models().map(|model| model.path.clone());
+        let textures = self.textures().map(|texture| texture.path.clone());
+
+        models
+            .chain(textures)
+            // Generated textures have no file
+            .filter(|path| !path.as_os_str().is_empty())
+            .unique()
+            .collect_vec()
+    }
+
+    fn models(&self) -> impl Iterator<Item = &Arc<Model>> {
+        self.graph.node_weights().flat_map(|model_instance| {
+            std::iter::once(&model_instance.model).chain(
+                model_instance
+                    .levels_of_detail
+                    .iter()
+                    .map(|level_of_detail| &level_of_detail.model),
+            )
+        })
+    }
+
+    fn textures(&self) -> impl Iterator<Item = &Arc<Texture2D>> {
+        self.graph
+            .node_weights()
+            .filter_map(|model_instance| model_instance.material.as_ref())
+            .flat_map(Material::textures)
+            .chain(self.billboards.iter().map(|billboard| &billboard.texture))
+    }
+
+    /// Load a model or texture again after it has changed on disk, updating everything which uses it
+    pub fn reload_asset(&mut self, path: &Path, display: &Display<WindowSurface>) -> Result<()> {
+        let models = self
+            .models()
+            .filter(|model| model.path == path)
+            .cloned()
+            .unique()
+            .collect_vec();
+
+        // Instances share their model, so replacing its meshes updates all of them
+        for model in models {
+            model.load_meshes(display)?;
+        }
+
+        // A model's file also holds the images embedded in it
+        let images = self
+            .textures()
+            .filter(|texture| texture.path == path)
+            .map(|texture| texture.image)
+            .unique()
+            .collect_vec();
+
+        for image in images {
+            let reloaded = Texture2D::reload(path.to_path_buf(), image, display)?;
+
+            let material_textures = self
+                .graph
+                .node_weights_mut()
+                .filter_map(|model_instance| model_instance.material.as_mut())
+                .flat_map(Material::textures_mut);
+            let billboard_textures = self
+                .billboards
+                .iter_mut()
+                .map(|billboard| &mut billboard.texture);
+
+            for texture in material_textures
+                .chain(billboard_textures)
+                .filter(|texture| texture.path == path && texture.image == image)
+            {
+                *texture = reloaded.clone();
+            }
+        }
+
+        Ok(())
+    }
+
     /// Load a models and create an instance of it in the scene
     pub fn import_model(&mut self, path: &Path, display: &Display<WindowSurface>) -> Result<()> {
-        let model = Model::load(path.to_path_buf(), display)?;
+        self.add_decoded_model(DecodedModel::decode(path)?, display)
+    }
+
+    /// Upload a model decoded with [`DecodedModel::decode`] and add its nodes to the scene
+    pub fn add_decoded_model(
+        &mut self,
+        decoded: DecodedModel,
+        display: &Display<WindowSurface>,
+    ) -> Result<()> {
+        let DecodedModel {
+            path,
+            document,
+            meshes,
+            materials,
+        } = decoded;
+
+        let Some(gltf_scene) = document.as_ref().and_then(|document| {
+            document
+                .default_scene()
+                .or_else(|| document.scenes().next())
+        }) else {
+            // Without any nodes to place them, such as in OBJ files, import every mesh as one model
+            let model = Model::from_decoded(path, None, meshes, display)?;
+            self.graph.add_node(ModelInstance::from(model));
+
+            return Ok(());
+        };
+
+        let materials = materials
+            .into_iter()
+            .map(|material| Material::from_decoded(material, display))
+            .collect::<Result<Vec<_>>>()
+            .wrap_err_with(|| format!("Failed to load the materials of {:?}", path))?;
+
+        let mut import = NodeImport {
+            path: &path,
+            meshes: meshes.into_iter().map(Some).collect_vec(),
+            materials,
+            models: HashMap::new(),
+        };
+
+        for node in gltf_scene.nodes() {
+            self.import_node(&node, &Transform::default(), None, &mut import, display)?;
+        }
+
+        Ok(())
+    }
+
+    /// Add a glTF node and its children beneath `parent`. Nodes without a mesh cannot be represented in the graph,
+    /// so their children are attached to the nearest ancestor that has one
+    fn import_node(
+        &mut self,
+        node: &gltf::Node,
+        parent_transform: &Transform,
+        parent: Option<NodeIndex>,
+        import: &mut NodeImport,
+        display: &Display<WindowSurface>,
+    ) -> Result<()> {
+        // Model instances are stored in world space
+        let transform = parent_transform.then(&Transform::from_gltf(node.transform()));
+
+        let node_index = match node.mesh() {
+            Some(mesh) => {
+                // Meshes used by several nodes are only uploaded once
+                let model = match import.models.entry(mesh.index()) {
+                    Entry::Occupied(entry) => entry.get().clone(),
+                    Entry::Vacant(entry) => {
+                        let decoded = import.meshes[mesh.index()].take().into_iter().collect_vec();
+
+                        entry
+                            .insert(Model::from_decoded(
+                                import.path.to_path_buf(),
+                                Some(mesh.index()),
+                                decoded,
+                                display,
+                            )?)
+                            .clone()
+                    }
+                };
+
+                let mut model_instance = ModelInstance::from(model);
+                model_instance.transform = transform.clone();
+                if let Some(name) = node.name().or(mesh.name()) {
+                    model_instance.name = name.to_owned();
+                }
+
+                // Materials belong to the instance rather than each primitive, so the first primitive's is used
+                let material = mesh
+                    .primitives()
+                    .next()
+                    .and_then(|primitive| primitive.material().index());
+                if let Some(material) = material {
+                    model_instance.material = import.materials[material].clone();
+                }
+
+                let node_index = self.graph.add_node(model_instance);
+                if let Some(parent) = parent {
+                    self.graph.add_edge(parent, node_index, ());
+                }
+
+                Some(node_index)
+            }
+            None => parent,
+        };
 
-        self.graph.add_node(ModelInstance::from(model));
+        for child in node.children() {
+            self.import_node(&child, &transform, node_index, import, display)?;
+        }
 
         Ok(())
     }
 
+    /// Copy a node `count` times into a square grid centred on it, with `spacing` between neighbours. Used to
+    /// stress test the renderer with large scenes
+    pub fn scatter_instances(&mut self, node_index: NodeIndex, count: usize, spacing: f32) {
+        let mut original = self.graph[node_index].clone();
+        original.selected = false;
+
+        let side = (count as f32).sqrt().ceil() as usize;
+        let half_extent = (side.saturating_sub(1)) as f32 * spacing / 2.0;
+
+        for i in 0..count {
+            let mut copy = original.clone();
+            copy.name = format!("{} {}", original.name, i + 1);
+            copy.transform.translation += Vector3::new(
+                (i % side) as f32 * spacing - half_extent,
+                0.0,
+                (i / side) as f32 * spacing - half_extent,
+            );
+
+            self.graph.add_node(copy);
+        }
+    }
+
+    /// Merge the primitives of `nodes` into one model per material, each written to a glTF file in `directory`, so
+    /// that they are drawn with one call rather than one each. Only nodes which are expected to stay where they are
+    /// can be merged, so those with children, levels of detail, metadata, or billboards or lights following them
+    /// are left as they are, as are hidden ones. Transparent nodes are left too, as they are sorted back to front
+    /// each frame. Returns the nodes of the merged models, which replace the originals
+    pub fn merge_static_geometry(
+        &mut self,
+        nodes: &[NodeIndex],
+        directory: &Path,
+        display: &Display<WindowSurface>,
+    ) -> Result<Vec<NodeIndex>> {
+        let mergeable = nodes
+            .iter()
+            .copied()
+            .filter(|node_index| {
+                let Some(model_instance) = self.graph.node_weight(*node_index) else {
+                    return false;
+                };
+
+                let followed = self
+                    .billboards
+                    .iter()
+                    .map(|billboard| billboard.parent)
+                    .chain(self.lights.iter().map(|light| light.parent))
+                    .any(|parent| parent == Some(*node_index));
+
+                self.graph
+                    .neighbors_directed(*node_index, Direction::Outgoing)
+                    .next()
+                    .is_none()
+                    && model_instance.levels_of_detail.is_empty()
+                    && model_instance.metadata.is_empty()
+                    && !model_instance.hidden
+                    && !model_instance.is_transparent()
+                    && !followed
+            })
+            .collect_vec();
+
+        // Primitives are grouped by everything which would otherwise need a draw call of its own
+        let mut groups = HashMap::<_, Vec<_>>::new();
+
+        for node_index in mergeable.iter() {
+            let model_instance = &self.graph[*node_index];
+            let transform = Matrix4::from(model_instance.transform.clone());
+
+            // The normal map of a primitive is only used when the material does not have one
+            let overrides_normal = model_instance
+                .material
+                .as_ref()
+                .is_some_and(|material| material.normal.is_some());
+
+            for primitive in model_instance.model.read_primitives()? {
+                let normal_texture = primitive.normal_texture.filter(|_| !overrides_normal);
+
+                groups
+                    .entry((model_instance.material.clone(), normal_texture))
+                    .or_default()
+                    .push((transform, primitive.vertices, primitive.indices));
+            }
+        }
+
+        if !groups.is_empty() {
+            std::fs::create_dir_all(directory)?;
+        }
+
+        let mut merged = Vec::new();
+
+        for (i, ((material, normal_texture), primitives)) in groups.into_iter().enumerate() {
+            let parts = primitives
+                .iter()
+                .map(|(transform, vertices, indices)| MergePart {
+                    transform: *transform,
+                    vertices,
+                    indices,
+                })
+                .collect_vec();
+            let (vertices, indices) = merge_primitives(&parts);
+
+            let glb = glb::export_mesh("Merged geometry", &vertices, &indices)?;
+
+            // Named after its contents, so that merging the same geometry again reuses the file rather than leaving
+            // another behind, and a file which is still used by an earlier merge is never written over
+            let mut hasher = DefaultHasher::new();
+            glb.hash(&mut hasher);
+            let path = directory.join(format!("merged_{:016x}.glb", hasher.finish()));
+
+            if !path.exists() {
+                std::fs::write(&path, glb).wrap_err_with(|| {
+                    format!("Could not write the merged geometry to {:?}", path)
+                })?;
+            }
+
+            let mut model_instance = ModelInstance::from(Model::load(path, display)?);
+            model_instance.name = format!("Merged geometry {}", i + 1);
+            // The merged file has no materials, so normal maps which came with the models are moved onto the material
+            model_instance.material = match normal_texture {
+                Some(normal_texture) => {
+                    let mut material = match material {
+                        Some(material) => material,
+                        None => Material::default(display)?,
+                    };
+                    material.normal = Some(normal_texture);
+
+                    Some(material)
+                }
+                None => material,
+            };
+
+            merged.push(self.graph.add_node(model_instance));
+        }
+
+        for node_index in mergeable {
+            self.remove_node(node_index, false);
+        }
+
+        Ok(merged)
+    }
+
+    pub fn parent(&self, node_index: NodeIndex) -> Option<NodeIndex> {
+        self.graph
+            .neighbors_directed(node_index, Direction::Incoming)
+            .next()
+    }
+
+    /// The transform of a node relative to its parent, or to the world if it has none
+    pub fn local_transform(&self, node_index: NodeIndex) -> Transform {
+        let transform = &self.graph[node_index].transform;
+
+        match self.parent(node_index) {
+            Some(parent) => transform.relative_to(&self.graph[parent].transform),
+            None => transform.clone(),
+        }
+    }
+
+    /// Move a node to a new world space transform, taking its descendants with it
+    pub fn set_transform(&mut self, node_index: NodeIndex, transform: Transform) {
+        let children = self
+            .graph
+            .neighbors_directed(node_index, Direction::Outgoing)
+            .map(|child| (child, self.local_transform(child)))
+            .collect_vec();
+
+        for (child, local) in children {
+            self.set_transform(child, transform.then(&local));
+        }
+
+        self.graph[node_index].transform = transform;
+    }
+
+    /// Remove a node. Its children are moved up to its parent if `keep_children` is set, otherwise they are removed
+    /// too. Transforms are stored in world space, so moved children stay where they are
+    pub fn remove_node(&mut self, node_index: NodeIndex, keep_children: bool) {
+        let parent = self.parent(node_index);
+        let children = self
+            .graph
+            .neighbors_directed(node_index, Direction::Outgoing)
+            .collect_vec();
+
+        for child in children {
+            if !keep_children {
+                self.remove_node(child, false);
+            } else if let Some(parent) = parent {
+                self.graph.add_edge(parent, child, ());
+            }
+        }
+
+        self.graph.remove_node(node_index);
+
+        // Removed indices are reused by new nodes, so nothing can be left pointing at this one
+        for selection_set in self.editor.selection_sets.iter_mut() {
+            selection_set.nodes.retain(|node| *node != node_index);
+        }
+
+        self.billboards
+            .retain(|billboard| billboard.parent != Some(node_index));
+        self.lights.retain(|light| light.parent != Some(node_index));
+    }
+
+    pub fn find_by_name<'a>(&'a self, name: &'a str) -> impl Iterator<Item = NodeIndex> + 'a {
+        self.find(move |model_instance| model_instance.name == name)
+    }
+
+    /// Nodes tagged with `tag`, which is a metadata property set to true
+    pub fn find_by_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = NodeIndex> + 'a {
+        self.find(move |model_instance| {
+            model_instance
+                .metadata
+                .get(tag)
+                .and_then(MetadataValue::as_bool)
+                == Some(true)
+        })
+    }
+
+    /// Nodes whose bounds overlap `sphere`
+    pub fn nodes_in_sphere(&self, sphere: BoundingSphere) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.find(move |model_instance| query_bounds(model_instance).intersects(&sphere))
+    }
+
+    /// Nodes whose bounds overlap `aabb`
+    pub fn nodes_in_aabb(&self, aabb: AABBCollider) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.find(move |model_instance| query_bounds(model_instance).intersects_aabb(&aabb))
+    }
+
+    /// The nearest node hit by `ray`, tested against the bounding box of each model. Hidden and locked nodes
+    /// cannot be hit
+    pub fn raycast(&self, ray: &Ray) -> Option<NodeIndex> {
+        self.graph
+            .node_references()
+            .filter(|(_, model_instance)| model_instance.is_pickable())
+            .filter_map(|(node_index, model_instance)| {
+                let bounds = model_instance.model.bounds()?;
+                let transform = &model_instance.transform;
+
+                ray.intersect_sphere(&bounds.sphere.transformed(transform))?;
+
+                // The box is only axis aligned in model space, so the ray is moved there instead
+                let distance = ray
+                    .transformed(&transform.inverse())
+                    .intersect_aabb(&bounds.aabb)?;
+
+                Some((node_index, distance))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(node_index, _)| node_index)
+    }
+
+    /// Nodes whose bounding box overlaps a rectangle on screen, in the coordinates used by `Ray::from_screen`.
+    /// Hidden and locked nodes are left out
+    pub fn nodes_in_screen_rect(
+        &self,
+        view_projection: &Matrix4<f32>,
+        min: (f32, f32),
+        max: (f32, f32),
+    ) -> Vec<NodeIndex> {
+        self.graph
+            .node_references()
+            .filter(|(_, model_instance)| model_instance.is_pickable())
+            .filter(|(_, model_instance)| {
+                let Some(bounds) = model_instance.model.bounds() else {
+                    return false;
+                };
+
+                let model_view_projection =
+                    view_projection * Matrix4::from(model_instance.transform.clone());
+
+                // The rectangle on screen around the box, ignoring corners behind the camera which cannot be projected
+                let projected = bounds
+                    .aabb
+                    .corners()
+                    .into_iter()
+                    .filter_map(|corner| clip_to_screen(model_view_projection * corner.extend(1.0)))
+                    .fold(None, |extent: Option<((f32, f32), (f32, f32))>, (x, y)| {
+                        Some(match extent {
+                            Some(((low_x, low_y), (high_x, high_y))) => {
+                                ((low_x.min(x), low_y.min(y)), (high_x.max(x), high_y.max(y)))
+                            }
+                            None => ((x, y), (x, y)),
+                        })
+                    });
+
+                projected.is_some_and(|(low, high)| {
+                    low.0 <= max.0 && high.0 >= min.0 && low.1 <= max.1 && high.1 >= min.1
+                })
+            })
+            .map(|(node_index, _)| node_index)
+            .collect_vec()
+    }
+
+    /// Where the light is in world space, or None if its parent has been removed
+    pub fn light_position(&self, light: &Light) -> Option<Point3<f32>> {
+        match light.parent {
+            Some(parent) => {
+                Some(light.position + self.graph.node_weight(parent)?.transform.translation)
+            }
+            None => Some(light.position),
+        }
+    }
+
+    /// The lights with their positions in world space, as they are drawn
+    pub fn world_lights(&self) -> Vec<Light> {
+        self.lights
+            .iter()
+            .filter_map(|light| {
+                Some(Light {
+                    position: self.light_position(light)?,
+                    ..light.clone()
+                })
+            })
+            .collect_vec()
+    }
+
+    /// The index of the light whose icon is under `position`, the nearest one if several are. `radius` is the
+    /// icon's radius as a fraction of the screen's width and height, in the coordinates used by `Ray::from_screen`
+    pub fn light_at(
+        &self,
+        view_projection: &Matrix4<f32>,
+        position: (f32, f32),
+        radius: (f32, f32),
+    ) -> Option<usize> {
+        self.lights
+            .iter()
+            .enumerate()
+            .filter_map(|(i, light)| {
+                let clip = view_projection * self.light_position(light)?.to_homogeneous();
+                let (x, y) = clip_to_screen(clip)?;
+
+                let (dx, dy) = ((x - position.0) / radius.0, (y - position.1) / radius.1);
+                (dx * dx + dy * dy <= 1.0).then_some((i, clip.w))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i)
+    }
+
+    /// The indices of the lights inside a rectangle on screen, in the coordinates used by `Ray::from_screen`
+    pub fn lights_in_screen_rect(
+        &self,
+        view_projection: &Matrix4<f32>,
+        min: (f32, f32),
+        max: (f32, f32),
+    ) -> Vec<usize> {
+        self.lights
+            .iter()
+            .enumerate()
+            .filter(|(_, light)| {
+                self.light_position(light)
+                    .and_then(|position| {
+                        clip_to_screen(view_projection * position.to_homogeneous())
+                    })
+                    .is_some_and(|(x, y)| x >= min.0 && x <= max.0 && y >= min.1 && y <= max.1)
+            })
+            .map(|(i, _)| i)
+            .collect_vec()
+    }
+
+    fn find<'a>(
+        &'a self,
+        predicate: impl Fn(&ModelInstance) -> bool + 'a,
+    ) -> impl Iterator<Item = NodeIndex> + 'a {
+        self.graph
+            .node_references()
+            .filter(move |(_, model_instance)| predicate(model_instance))
+            .map(|(node_index, _)| node_index)
+    }
+
+    pub fn selected_nodes(&self) -> Vec<NodeIndex> {
+        self.graph
+            .node_references()
+            .filter(|(_, model_instance)| model_instance.selected)
+            .map(|(node_index, _)| node_index)
+            .collect_vec()
+    }
+
+    /// Selected nodes which are not below another selected node, so that each subtree is only counted once
+    pub fn selected_roots(&self) -> Vec<NodeIndex> {
+        self.selected_nodes()
+            .into_iter()
+            .filter(|node_index| {
+                let mut ancestor = self.parent(*node_index);
+
+                while let Some(node_index) = ancestor {
+                    if self.graph[node_index].selected {
+                        return false;
+                    }
+
+                    ancestor = self.parent(node_index);
+                }
+
+                true
+            })
+            .collect_vec()
+    }
+
+    pub fn copy_subtree(&self, root: NodeIndex) -> Subtree {
+        let mut nodes = Vec::new();
+        let mut stack = vec![(root, None)];
+
+        while let Some((node_index, parent)) = stack.pop() {
+            let mut copy = self.graph[node_index].clone();
+            copy.selected = false;
+            nodes.push((copy, parent));
+
+            let position = nodes.len() - 1;
+            stack.extend(
+                self.graph
+                    .neighbors_directed(node_index, Direction::Outgoing)
+                    .map(|child| (child, Some(position))),
+            );
+        }
+
+        Subtree { nodes }
+    }
+
+    /// Add a copy of `subtree` with its root under `parent`, moved by `offset`. Returns the new root
+    pub fn paste(
+        &mut self,
+        subtree: &Subtree,
+        parent: Option<NodeIndex>,
+        offset: Vector3<f32>,
+    ) -> NodeIndex {
+        let mut added: Vec<NodeIndex> = Vec::with_capacity(subtree.nodes.len());
+
+        for (model_instance, copy_parent) in subtree.nodes.iter() {
+            let mut copy = model_instance.clone();
+            copy.transform.translation += offset;
+
+            let node_index = self.graph.add_node(copy);
+
+            // Only the root has no parent in the copy
+            if let Some(parent) = copy_parent.map(|position| added[position]).or(parent) {
+                self.graph.add_edge(parent, node_index, ());
+            }
+
+            added.push(node_index);
+        }
+
+        added[0]
+    }
+
+    /// Select only the nodes in `nodes`, ignoring any which have since been removed
+    pub fn select_only(&mut self, nodes: &[NodeIndex]) {
+        for model_instance in self.graph.node_weights_mut() {
+            model_instance.selected = false;
+        }
+
+        for node_index in nodes {
+            if let Some(model_instance) = self.graph.node_weight_mut(*node_index) {
+                model_instance.selected = true;
+            }
+        }
+    }
+
+    /// Nodes which reach past the far plane, and whether they are cut off by it or lie wholly beyond it
+    pub fn nodes_beyond_far_plane(
+        &self,
+        camera_position: Point3<f32>,
+    ) -> Vec<(NodeIndex, FarPlaneClipping)> {
+        let far = self.camera_settings.far;
+
+        self.graph
+            .node_references()
+            .filter_map(|(node_index, model_instance)| {
+                // Nodes without any geometry are treated as a point at their origin
+                let sphere = model_instance.bounding_sphere().unwrap_or(BoundingSphere {
+                    center: model_instance.transform.translation,
+                    radius: 0.0,
+                });
+                let distance = Point3::from_vec(sphere.center).distance(camera_position);
+
+                if distance - sphere.radius > far {
+                    Some((node_index, FarPlaneClipping::Entirely))
+                } else if distance + sphere.radius > far {
+                    Some((node_index, FarPlaneClipping::Partly))
+                } else {
+                    None
+                }
+            })
+            .collect_vec()
+    }
+
+    /// Where each billboard is drawn this frame, skipping those whose parent has been removed
+    fn billboard_instances(
+        &self,
+    ) -> impl Iterator<Item = (Arc<Texture2D>, BillboardInstance)> + '_ {
+        self.billboards.iter().filter_map(|billboard| {
+            let origin = match billboard.parent {
+                Some(parent) => self.graph.node_weight(parent)?.transform.translation,
+                None => Vector3::new(0.0, 0.0, 0.0),
+            };
+
+            Some((
+                billboard.texture.clone(),
+                BillboardInstance {
+                    center: <[f32; 3]>::from(origin + billboard.offset),
+                    size: billboard.size,
+                },
+            ))
+        })
+    }
+
     pub fn render(
         &mut self,
         renderer: &mut Renderer,
@@ -126,42 +999,108 @@ impl Scene {
         projection: &Matrix4<f32>,
         camera_position: Point3<f32>,
         display: &Display<WindowSurface>,
-        target: &mut Frame,
+        target: &mut impl Surface,
     ) {
         match &self.background {
             Background::Color(color) => {
-                target.clear_color_and_depth(color.to_rgb_vector4().into(), 1.0)
+                let mut clear_color = if renderer.settings.srgb {
+                    color.to_linear_rgb_vector4()
+                } else {
+                    color.to_rgb_vector4()
+                };
+                // Every attachment is cleared, zero alpha marks the emissive attachment as not written to
+                clear_color.w = 0.0;
+
+                target.clear_color_and_depth(clear_color.into(), 1.0);
+
+                if let Some(zenith) = self.sky_gradient {
+                    renderer.render_sky_gradient(
+                        *color,
+                        zenith,
+                        view,
+                        projection,
+                        self.camera_settings.sky_distance,
+                        target,
+                    );
+                }
             }
             Background::HDRI(cubemap) => {
-                target.clear_color_and_depth(
-                    Color::from_named(palette::named::WHITE)
-                        .to_rgb_vector4()
-                        .into(),
-                    1.0,
+                target.clear_color_and_depth((1.0, 1.0, 1.0, 0.0), 1.0);
+                renderer.render_skybox(
+                    cubemap,
+                    view,
+                    projection,
+                    self.camera_settings.sky_distance,
+                    target,
                 );
-                renderer.render_skybox(cubemap, view, projection, target);
             }
         }
 
         let view_projection = projection * view;
 
+        let lights = self.world_lights();
+
         renderer.render_model_instances(
             self.graph.node_references(),
             &view_projection,
             camera_position,
-            &self.lights,
+            &lights,
+            &self.fog,
             display,
             target,
         );
 
         if let Some(terrain) = &self.terrain {
-            renderer.render_terrain(terrain, &view_projection, camera_position, target);
+            renderer.render_terrain(
+                terrain,
+                &view_projection,
+                camera_position,
+                &self.fog,
+                target,
+            );
         }
 
-        renderer.render_lines(&self.lines, &view_projection, display, target);
+        renderer.render_billboards(
+            self.billboard_instances(),
+            view,
+            projection,
+            display,
+            target,
+        );
+
+        renderer.render_transparent_model_instances(
+            self.graph.node_references(),
+            &view_projection,
+            camera_position,
+            &lights,
+            &self.fog,
+            display,
+            target,
+        );
+
+        renderer.render_lines(
+            &self.lines,
+            &self.polylines,
+            &view_projection,
+            display,
+            target,
+        );
     }
 }
 
+/// A point in clip space as a fraction of the screen's size from the top left, or None if it is behind the camera
+fn clip_to_screen(clip: Vector4<f32>) -> Option<(f32, f32)> {
+    (clip.w > 0.0).then(|| ((clip.x / clip.w + 1.0) / 2.0, (1.0 - clip.y / clip.w) / 2.0))
+}
+
+/// The bounds of a node in world space. Nodes whose model has not loaded are treated as a point at their origin
+fn query_bounds(model_instance: &ModelInstance) -> BoundingSphere {
+    model_instance.bounding_sphere().unwrap_or(BoundingSphere {
+        center: model_instance.transform.translation,
+        radius: 0.0,
+    })
+}
+
 impl Default for Scene {
     fn default() -> Self {
         Self::new("Untitled")
@@ -0,0 +1,43 @@
+use crate::assets::AssetDatabase;
+use log::warn;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serializer};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Saved as the id of the asset in the [`AssetDatabase`]. Generated assets have no path, so stay empty, and assets
+/// which were never registered are saved by their path
+pub fn serialize<S>(path: &Path, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if path.as_os_str().is_empty() {
+        return serializer.serialize_str("");
+    }
+
+    match AssetDatabase::guid(path) {
+        Some(guid) => serializer.serialize_str(&guid.to_string()),
+        None => {
+            warn!("{:?} has no asset id, so is saved by its path", path);
+            serializer.serialize_str(&path.to_string_lossy())
+        }
+    }
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<PathBuf, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let string = String::deserialize(deserializer)?;
+
+    if string.is_empty() {
+        return Ok(PathBuf::new());
+    }
+
+    match Uuid::parse_str(&string) {
+        Ok(guid) => AssetDatabase::path(guid)
+            .ok_or_else(|| D::Error::custom(format!("There is no asset with the id {}", guid))),
+        // Scenes saved before the asset database refer to assets by their path
+        Err(_) => Ok(AssetDatabase::locate(Path::new(&string))),
+    }
+}
@@ -1,3 +1,4 @@
+use crate::assets::AssetDatabase;
 use crate::import;
 use cgmath::Vector3;
 use color_eyre::eyre::Result;
@@ -9,6 +10,7 @@ use std::path::{Path, PathBuf};
 
 #[derive(Serialize, Deserialize)]
 pub struct Terrain {
+    #[serde(with = "crate::serde::asset_path")]
     pub path: PathBuf,
     // pub heightmap: Vec<Vec<u16>>,
     #[serde(skip)]
@@ -24,6 +26,8 @@ implement_vertex!(TerrainVertex, position, normal);
 
 impl Terrain {
     pub fn load(path: &Path, display: &Display<WindowSurface>) -> Result<Self> {
+        AssetDatabase::register(path);
+
         let image_1d = import::image::load_dynamic_image(path)?.into_luma16();
 
         let dimensions = image_1d.dimensions();
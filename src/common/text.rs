@@ -0,0 +1,186 @@
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use glium::glutin::surface::WindowSurface;
+use glium::texture::{ClientFormat, MipmapsOption, RawImage2d, UncompressedFloatFormat};
+use glium::{implement_vertex, Display, Texture2d};
+use log::info;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+
+const ATLAS_WIDTH: usize = 512;
+// Space between glyphs so that linear filtering does not bleed neighbours into each other
+const ATLAS_PADDING: usize = 1;
+
+#[derive(Copy, Clone, Debug)]
+struct Glyph {
+    uv_min: [f32; 2],
+    uv_max: [f32; 2],
+    size: [f32; 2],
+    /// Offset of the bottom left of the bitmap from the pen position on the baseline
+    offset: [f32; 2],
+    advance: f32,
+}
+
+/// A font rasterised at a single size into an atlas texture
+pub struct Font {
+    pub atlas: Texture2d,
+    glyphs: HashMap<char, Glyph>,
+    pixel_size: f32,
+    ascent: f32,
+    line_height: f32,
+}
+
+#[derive(Copy, Clone)]
+pub struct TextVertex {
+    pub position: [f32; 2],
+    pub tex_coord: [f32; 2],
+}
+implement_vertex!(TextVertex, position, tex_coord);
+
+impl Font {
+    /// Rasterise the printable ASCII characters of a TTF/OTF font at `pixel_size`
+    pub fn load(path: &Path, pixel_size: f32, display: &Display<WindowSurface>) -> Result<Self> {
+        info!("Loading font {:?}...", path);
+
+        let font =
+            fontdue::Font::from_bytes(std::fs::read(path)?, fontdue::FontSettings::default())
+                .map_err(|err| eyre!("Failed to parse font {:?}: {}", path, err))?;
+
+        let rasterised = (' '..='~')
+            .map(|character| (character, font.rasterize(character, pixel_size)))
+            .collect::<Vec<_>>();
+
+        // Shelf pack the glyphs into rows
+        let mut placements = Vec::with_capacity(rasterised.len());
+        let (mut x, mut y, mut row_height) = (0, 0, 0);
+
+        for (_, (metrics, _)) in rasterised.iter() {
+            if x + metrics.width + ATLAS_PADDING > ATLAS_WIDTH {
+                x = 0;
+                y += row_height + ATLAS_PADDING;
+                row_height = 0;
+            }
+
+            placements.push((x, y));
+
+            x += metrics.width + ATLAS_PADDING;
+            row_height = row_height.max(metrics.height);
+        }
+
+        let atlas_height = (y + row_height).next_power_of_two();
+        let mut pixels = vec![0_u8; ATLAS_WIDTH * atlas_height];
+        let mut glyphs = HashMap::with_capacity(rasterised.len());
+
+        for ((character, (metrics, bitmap)), (x, y)) in rasterised.iter().zip(placements) {
+            for row in 0..metrics.height {
+                let destination = (y + row) * ATLAS_WIDTH + x;
+                pixels[destination..destination + metrics.width]
+                    .copy_from_slice(&bitmap[row * metrics.width..(row + 1) * metrics.width]);
+            }
+
+            // Rows are stored top down, so the top of the glyph has the smallest v
+            glyphs.insert(
+                *character,
+                Glyph {
+                    uv_min: [
+                        x as f32 / ATLAS_WIDTH as f32,
+                        y as f32 / atlas_height as f32,
+                    ],
+                    uv_max: [
+                        (x + metrics.width) as f32 / ATLAS_WIDTH as f32,
+                        (y + metrics.height) as f32 / atlas_height as f32,
+                    ],
+                    size: [metrics.width as f32, metrics.height as f32],
+                    offset: [metrics.xmin as f32, metrics.ymin as f32],
+                    advance: metrics.advance_width,
+                },
+            );
+        }
+
+        let raw_image = RawImage2d {
+            data: Cow::Owned(pixels),
+            width: ATLAS_WIDTH as u32,
+            height: atlas_height as u32,
+            format: ClientFormat::U8,
+        };
+
+        let atlas = Texture2d::with_format(
+            display,
+            raw_image,
+            UncompressedFloatFormat::U8,
+            MipmapsOption::NoMipmap,
+        )?;
+
+        let line_metrics = font
+            .horizontal_line_metrics(pixel_size)
+            .ok_or_else(|| eyre!("Font {:?} has no horizontal line metrics", path))?;
+
+        Ok(Self {
+            atlas,
+            glyphs,
+            pixel_size,
+            ascent: line_metrics.ascent,
+            line_height: line_metrics.new_line_size,
+        })
+    }
+
+    /// Lay out `text` as a list of triangles in screen space, where `position` is the top left of the text in pixels
+    pub fn layout(&self, text: &str, position: [f32; 2], size: f32) -> Vec<TextVertex> {
+        let scale = size / self.pixel_size;
+
+        let mut vertices = Vec::with_capacity(text.len() * 6);
+        let mut pen = [position[0], position[1] + self.ascent * scale];
+
+        for character in text.chars() {
+            if character == '\n' {
+                pen = [position[0], pen[1] + self.line_height * scale];
+                continue;
+            }
+
+            let Some(glyph) = self
+                .glyphs
+                .get(&character)
+                .or_else(|| self.glyphs.get(&'?'))
+            else {
+                continue;
+            };
+
+            // Screen space y points down, whereas glyph offsets point up
+            let left = pen[0] + glyph.offset[0] * scale;
+            let right = left + glyph.size[0] * scale;
+            let bottom = pen[1] - glyph.offset[1] * scale;
+            let top = bottom - glyph.size[1] * scale;
+
+            let top_left = TextVertex {
+                position: [left, top],
+                tex_coord: glyph.uv_min,
+            };
+            let top_right = TextVertex {
+                position: [right, top],
+                tex_coord: [glyph.uv_max[0], glyph.uv_min[1]],
+            };
+            let bottom_left = TextVertex {
+                position: [left, bottom],
+                tex_coord: [glyph.uv_min[0], glyph.uv_max[1]],
+            };
+            let bottom_right = TextVertex {
+                position: [right, bottom],
+                tex_coord: glyph.uv_max,
+            };
+
+            vertices.extend([
+                top_left,
+                bottom_left,
+                top_right,
+                top_right,
+                bottom_left,
+                bottom_right,
+            ]);
+
+            pen[0] += glyph.advance * scale;
+        }
+
+        vertices
+    }
+}
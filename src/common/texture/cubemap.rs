@@ -1,14 +1,18 @@
+use crate::assets::AssetDatabase;
+use crate::import;
 use crate::texture::texture;
 use crate::texture::texture::TextureLoadError;
 use glium::framebuffer::SimpleFrameBuffer;
 use glium::glutin::surface::WindowSurface;
-use glium::texture::CubeLayer;
+use glium::texture::{CubeLayer, RawImage2d};
 use glium::uniforms::MagnifySamplerFilter;
 use glium::{BlitTarget, Display, Surface, Texture2d};
-use memoize::memoize;
+use image::RgbaImage;
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -16,18 +20,131 @@ use uuid::Uuid;
 pub struct Cubemap {
     #[serde(with = "crate::serde::uuid")]
     pub uuid: Uuid,
+    /// A directory with an image for each side, or a single image with every side laid out in a cross or strip
+    #[serde(with = "crate::serde::asset_path")]
     pub directory: PathBuf,
 
     #[serde(skip)]
     pub inner_cubemap: Option<glium::texture::Cubemap>,
 }
 
+/// The sides of a cubemap read on any thread, which only need uploading on the main thread
+pub struct DecodedCubemap {
+    directory: PathBuf,
+    sides: Vec<RawImage2d<'static, u8>>,
+}
+
 impl Cubemap {
     pub fn load(
         directory: PathBuf,
         display: &Display<WindowSurface>,
     ) -> color_eyre::Result<Arc<Self>> {
-        Ok(load(directory, display)?)
+        if let Some(cubemap) = Self::cached(&directory) {
+            return Ok(cubemap);
+        }
+
+        Self::from_decoded(Self::decode(directory)?, display)
+    }
+
+    /// The cubemap in `directory`, if it has already been loaded
+    pub fn cached(directory: &Path) -> Option<Arc<Self>> {
+        CUBEMAPS.with_borrow(|cubemaps| cubemaps.get(directory).cloned())
+    }
+
+    /// Read the sides of the cubemap in `directory` without touching the GPU. The sides are read in parallel, as
+    /// HDRIs are large. `directory` can also be a single image, see `decode_single_image`
+    pub fn decode(directory: PathBuf) -> Result<DecodedCubemap, TextureLoadError> {
+        AssetDatabase::register(&directory);
+
+        if directory.is_file() {
+            return Self::decode_single_image(directory);
+        }
+
+        let sides = std::thread::scope(|scope| {
+            let handles = SIDE_NAMES
+                .iter()
+                .map(|side| {
+                    let path = directory.join(side).with_extension("jpg");
+                    scope.spawn(move || texture::load_raw_image(&path))
+                })
+                .collect_vec();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("Reading a cubemap side panicked"))
+                .collect::<Result<Vec<_>, TextureLoadError>>()
+        })?;
+
+        // Check each side is of the same dimension
+        let unique_cubemap_dimensions =
+            HashSet::from_iter(sides.iter().map(|side| (side.width, side.height)));
+
+        if unique_cubemap_dimensions.len() > 1 {
+            return Err(TextureLoadError::CubemapDimensionError(
+                unique_cubemap_dimensions,
+            ));
+        }
+
+        Ok(DecodedCubemap { directory, sides })
+    }
+
+    /// Read a single image holding every side of a cubemap, see `slice_sides`
+    fn decode_single_image(path: PathBuf) -> Result<DecodedCubemap, TextureLoadError> {
+        let image = import::image::load_dynamic_image(&path)
+            .map_err(TextureLoadError::ImageLoadError)?
+            .into_rgba8();
+
+        let sides = Self::slice_sides(&image)?
+            .into_iter()
+            .map(|side| {
+                let dimensions = side.dimensions();
+                RawImage2d::from_raw_rgba(side.into_raw(), dimensions)
+            })
+            .collect_vec();
+
+        Ok(DecodedCubemap {
+            directory: path,
+            sides,
+        })
+    }
+
+    /// Slice an image into the sides of a cubemap, in the order of `SIDE_NAMES`. The image is either a horizontal
+    /// cross, with +Y above and -Y below the row -X, +Z, +X, -Z, or a vertical strip of +X, -X, +Y, -Y, +Z, -Z from
+    /// the top
+    pub fn slice_sides(image: &RgbaImage) -> Result<Vec<RgbaImage>, TextureLoadError> {
+        let (width, height) = image.dimensions();
+
+        // Positions of the sides in units of the side length, in the order of `SIDE_NAMES`
+        let (size, cells) = if width * 3 == height * 4 {
+            (width / 4, [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)])
+        } else if width * 6 == height {
+            (width, [(0, 0), (0, 1), (0, 2), (0, 3), (0, 4), (0, 5)])
+        } else {
+            return Err(TextureLoadError::CubemapLayoutError((width, height)));
+        };
+
+        if !size.is_power_of_two() {
+            return Err(TextureLoadError::CubemapFaceSizeError(size));
+        }
+
+        Ok(cells
+            .into_iter()
+            .map(|(x, y)| {
+                image::imageops::crop_imm(image, x * size, y * size, size, size).to_image()
+            })
+            .collect_vec())
+    }
+
+    pub fn from_decoded(
+        decoded: DecodedCubemap,
+        display: &Display<WindowSurface>,
+    ) -> color_eyre::Result<Arc<Self>> {
+        let directory = decoded.directory.clone();
+        let cubemap = upload(decoded, display)?;
+
+        CUBEMAPS.with_borrow_mut(|cubemaps| cubemaps.insert(directory, cubemap.clone()));
+
+        Ok(cubemap)
     }
 }
 
@@ -37,12 +154,17 @@ impl PartialEq<Self> for Cubemap {
     }
 }
 
-#[memoize(Ignore: display)]
-fn load(
-    directory: PathBuf,
+const SIDE_NAMES: [&str; 6] = ["posx", "negx", "posy", "negy", "posz", "negz"];
+
+thread_local! {
+    /// Cubemaps by directory, as HDRIs are slow to read
+    static CUBEMAPS: RefCell<HashMap<PathBuf, Arc<Cubemap>>> = RefCell::new(HashMap::new());
+}
+
+fn upload(
+    decoded: DecodedCubemap,
     display: &Display<WindowSurface>,
 ) -> Result<Arc<Cubemap>, TextureLoadError> {
-    let side_names = vec!["posx", "negx", "posy", "negy", "posz", "negz"];
     let cube_layers = vec![
         CubeLayer::PositiveX,
         CubeLayer::NegativeX,
@@ -52,34 +174,15 @@ fn load(
         CubeLayer::NegativeZ,
     ];
 
-    // Load each side of cubemap
-    let mut textures = side_names
-        .into_iter()
-        .map(|side| {
-            let mut path = directory.clone();
-            path.push(side);
+    let DecodedCubemap { directory, sides } = decoded;
 
-            let raw_image = texture::load_raw_image(&path.with_extension("jpg"))?;
+    let dimension = sides[0].width;
 
-            Texture2d::new(display, raw_image).map_err(TextureLoadError::CreateTextureError)
-        })
+    let mut textures = sides
+        .into_iter()
+        .map(|side| Texture2d::new(display, side).map_err(TextureLoadError::CreateTextureError))
         .collect::<Result<Vec<Texture2d>, TextureLoadError>>()?;
 
-    // Check each side is of the same dimension
-    let unique_cubemap_dimensions = HashSet::from_iter(
-        textures
-            .iter()
-            .map(|texture| (texture.width(), texture.height())),
-    );
-
-    if unique_cubemap_dimensions.len() > 1 {
-        return Err(TextureLoadError::CubemapDimensionError(
-            unique_cubemap_dimensions,
-        ));
-    }
-
-    let dimension = textures[0].width();
-
     // Create cubemap texture and framebuffers
     let inner_cubemap = glium::texture::Cubemap::empty(display, dimension)
         .map_err(TextureLoadError::CreateTextureError)?;
@@ -113,7 +216,7 @@ fn load(
 
     Ok(Arc::new(Cubemap {
         inner_cubemap: Some(inner_cubemap),
-        directory: directory.clone(),
+        directory,
         uuid: Uuid::new_v4(),
     }))
 }
@@ -0,0 +1,86 @@
+use glium::uniforms::{
+    MagnifySamplerFilter, MinifySamplerFilter, SamplerBehavior, SamplerWrapFunction,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub enum TextureFiltering {
+    /// Blocky, for intentionally pixelated art
+    Nearest,
+    Bilinear,
+    /// Also blends between mipmap levels
+    #[default]
+    Trilinear,
+}
+
+impl TextureFiltering {
+    pub const ALL: [TextureFiltering; 3] = [Self::Nearest, Self::Bilinear, Self::Trilinear];
+
+    /// Every texture sampler should be created here, so that filtering can be changed in one place
+    pub fn sampler_behaviour(self, max_anisotropy: u16) -> SamplerBehavior {
+        let (minify_filter, magnify_filter) = match self {
+            Self::Nearest => (MinifySamplerFilter::Nearest, MagnifySamplerFilter::Nearest),
+            Self::Bilinear => (MinifySamplerFilter::Linear, MagnifySamplerFilter::Linear),
+            Self::Trilinear => (
+                MinifySamplerFilter::LinearMipmapLinear,
+                MagnifySamplerFilter::Linear,
+            ),
+        };
+
+        SamplerBehavior {
+            minify_filter,
+            magnify_filter,
+            // Anisotropy would blur pixel art
+            max_anisotropy: if self == Self::Nearest {
+                1
+            } else {
+                max_anisotropy.max(1)
+            },
+            ..SamplerBehavior::default()
+        }
+    }
+}
+
+impl Display for TextureFiltering {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Nearest => write!(f, "Nearest"),
+            Self::Bilinear => write!(f, "Bilinear"),
+            Self::Trilinear => write!(f, "Trilinear"),
+        }
+    }
+}
+
+/// What is sampled outside of the 0 to 1 texture coordinate range
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub enum TextureWrap {
+    Repeat,
+    /// Repeat, flipping every other tile
+    #[default]
+    Mirror,
+    /// Stretch the edge pixels
+    Clamp,
+}
+
+impl TextureWrap {
+    pub const ALL: [TextureWrap; 3] = [Self::Repeat, Self::Mirror, Self::Clamp];
+
+    pub fn wrap_function(self) -> SamplerWrapFunction {
+        match self {
+            Self::Repeat => SamplerWrapFunction::Repeat,
+            Self::Mirror => SamplerWrapFunction::Mirror,
+            Self::Clamp => SamplerWrapFunction::Clamp,
+        }
+    }
+}
+
+impl Display for TextureWrap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Repeat => write!(f, "Repeat"),
+            Self::Mirror => write!(f, "Mirror"),
+            Self::Clamp => write!(f, "Clamp"),
+        }
+    }
+}
@@ -1,7 +1,10 @@
 mod texture;
 
 pub mod cubemap;
+pub mod filtering;
 pub mod texture2d;
 
-pub use cubemap::Cubemap;
-pub use texture2d::Texture2D;
+pub use cubemap::{Cubemap, DecodedCubemap};
+pub use filtering::{TextureFiltering, TextureWrap};
+pub use texture::TextureLoadError;
+pub use texture2d::{DecodedTexture2D, GpuTexture, Texture2D, TextureSampler};
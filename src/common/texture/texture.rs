@@ -1,7 +1,10 @@
 use crate::import;
+use crate::import::dds::DdsLoadError;
 use crate::import::image::ImageLoadError;
 use color_eyre::eyre::Result;
 use glium::texture::RawImage2d;
+use gltf::image::Format;
+use image::{DynamicImage, GrayAlphaImage, GrayImage, RgbImage, RgbaImage};
 use std::collections::HashSet;
 use std::fmt;
 use std::fmt::Formatter;
@@ -12,7 +15,16 @@ pub enum TextureLoadError {
     ImageLoadError(ImageLoadError),
     CreateTextureError(glium::texture::TextureCreationError),
     CubemapDimensionError(HashSet<(u32, u32)>),
+    /// The dimensions of a single image which is neither a horizontal cross nor a vertical strip
+    CubemapLayoutError((u32, u32)),
+    CubemapFaceSizeError(u32),
     CubemapFramebufferError,
+    /// An image embedded in a model which could not be read, by the model's path and the index of the image
+    EmbeddedImageNotFound(PathBuf, usize),
+    UnsupportedGltfImageFormat(gltf::image::Format),
+    DdsLoadError(DdsLoadError),
+    /// A mipmap of a compressed texture which could not be uploaded, by its level
+    CompressedMipmapError(u32),
 }
 
 impl fmt::Display for TextureLoadError {
@@ -25,9 +37,29 @@ impl fmt::Display for TextureLoadError {
                 "Cubemap sides must be the same size, found sizes: {:?}",
                 dimensions
             ),
+            Self::CubemapLayoutError((width, height)) => write!(
+                f,
+                "A cubemap image must be a horizontal cross (4:3) or a vertical strip (1:6), found {}x{}",
+                width, height
+            ),
+            Self::CubemapFaceSizeError(size) => write!(
+                f,
+                "Cubemap sides must be a power of two in size, found {}x{}",
+                size, size
+            ),
             Self::CubemapFramebufferError => {
                 write!(f, "Could not create framebuffer(s) when creating cubemap")
             }
+            Self::EmbeddedImageNotFound(path, image) => {
+                write!(f, "Could not read image {} embedded in {:?}", image, path)
+            }
+            Self::UnsupportedGltfImageFormat(format) => {
+                write!(f, "glTF images of the format {:?} are not supported", format)
+            }
+            Self::DdsLoadError(err) => write!(f, "{}", err),
+            Self::CompressedMipmapError(level) => {
+                write!(f, "Could not upload mipmap {} of a compressed texture", level)
+            }
         }
     }
 }
@@ -43,3 +75,30 @@ pub fn load_raw_image<'a>(path: &PathBuf) -> Result<RawImage2d<'a, u8>, TextureL
 
     Ok(RawImage2d::from_raw_rgba(rgba8.into_raw(), dimensions))
 }
+
+/// An image which `gltf::import` has already decoded, such as one embedded in a .glb file
+pub fn gltf_raw_image<'a>(
+    data: &gltf::image::Data,
+) -> Result<RawImage2d<'a, u8>, TextureLoadError> {
+    let pixels = data.pixels.clone();
+    let (width, height) = (data.width, data.height);
+
+    // Only 8 bit images can be stored in an RGBA8 texture without converting each channel
+    let image = match data.format {
+        Format::R8 => GrayImage::from_raw(width, height, pixels).map(DynamicImage::ImageLuma8),
+        Format::R8G8 => {
+            GrayAlphaImage::from_raw(width, height, pixels).map(DynamicImage::ImageLumaA8)
+        }
+        Format::R8G8B8 => RgbImage::from_raw(width, height, pixels).map(DynamicImage::ImageRgb8),
+        Format::R8G8B8A8 => {
+            RgbaImage::from_raw(width, height, pixels).map(DynamicImage::ImageRgba8)
+        }
+        format => return Err(TextureLoadError::UnsupportedGltfImageFormat(format)),
+    }
+    // Only if there are fewer pixels than the dimensions need, which the glTF crate does not allow
+    .ok_or(TextureLoadError::UnsupportedGltfImageFormat(data.format))?;
+
+    let rgba8 = image.into_rgba8();
+
+    Ok(RawImage2d::from_raw_rgba(rgba8.into_raw(), (width, height)))
+}
@@ -1,13 +1,20 @@
+use crate::assets::AssetDatabase;
+use crate::import::dds;
+use crate::import::dds::Dds;
 use crate::texture::texture;
 use crate::texture::texture::TextureLoadError;
 use color_eyre::Result;
 use glium::glutin::surface::WindowSurface;
-use glium::texture::CompressedTexture2d;
-use glium::Display;
+use glium::texture::{CompressedMipmapsOption, CompressedTexture2d, MipmapsOption, RawImage2d};
+use glium::uniforms::{AsUniformValue, SamplerBehavior, UniformValue};
+use glium::{Display, Rect, Texture2d};
+use log::warn;
 use memoize::memoize;
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -15,14 +22,190 @@ use uuid::Uuid;
 pub struct Texture2D {
     #[serde(with = "crate::serde::uuid")]
     pub uuid: Uuid,
+    #[serde(with = "crate::serde::asset_path")]
     pub path: PathBuf,
+    /// The index of the image within the model at `path`, for images embedded in the model rather than saved
+    /// alongside it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<usize>,
     #[serde(skip)]
-    pub inner_texture: Option<CompressedTexture2d>,
+    pub inner_texture: Option<GpuTexture>,
+}
+
+/// The copy of a texture on the GPU
+#[derive(Debug)]
+pub enum GpuTexture {
+    /// Decoded from an image, with mipmaps generated on upload
+    Uncompressed(Texture2d),
+    /// Block compressed, such as from a DDS file, which must come with its own mipmaps
+    Compressed(CompressedTexture2d),
+}
+
+impl GpuTexture {
+    pub fn dimensions(&self) -> (u32, u32) {
+        match self {
+            Self::Uncompressed(texture) => texture.dimensions(),
+            Self::Compressed(texture) => texture.dimensions(),
+        }
+    }
+}
+
+/// A texture bound to a shader with the given sampling, whichever kind of texture it is
+pub struct TextureSampler<'a>(pub &'a GpuTexture, pub SamplerBehavior);
+
+impl AsUniformValue for TextureSampler<'_> {
+    fn as_uniform_value(&self) -> UniformValue {
+        match self.0 {
+            GpuTexture::Uncompressed(texture) => UniformValue::Texture2d(texture, Some(self.1)),
+            GpuTexture::Compressed(texture) => {
+                UniformValue::CompressedTexture2d(texture, Some(self.1))
+            }
+        }
+    }
+}
+
+/// A texture read from its file without the GPU, so that it can be done off the main thread
+pub struct DecodedTexture2D {
+    path: PathBuf,
+    image: Option<usize>,
+    pixels: DecodedPixels,
+}
+
+enum DecodedPixels {
+    Raw(RawImage2d<'static, u8>),
+    Compressed(Dds),
 }
 
 impl Texture2D {
     pub fn load(path: PathBuf, display: &Display<WindowSurface>) -> Result<Arc<Self>> {
-        Ok(load(path, display)?)
+        Self::load_image(path, None, display)
+    }
+
+    /// Load an image embedded in the model at `path`. The whole model is read again, so this is only for textures
+    /// which were not loaded with their model, such as those of a saved scene
+    pub fn load_embedded(
+        path: PathBuf,
+        image: usize,
+        display: &Display<WindowSurface>,
+    ) -> Result<Arc<Self>> {
+        Self::load_image(path, Some(image), display)
+    }
+
+    /// Load the same image as `self`, which only knows where its image is after being deserialized
+    pub fn load_saved(&self, display: &Display<WindowSurface>) -> Result<Arc<Self>> {
+        Self::load_image(self.path.clone(), self.image, display)
+    }
+
+    fn load_image(
+        path: PathBuf,
+        image: Option<usize>,
+        display: &Display<WindowSurface>,
+    ) -> Result<Arc<Self>> {
+        let key = (path, image);
+        if let Some(texture) = TEXTURES.with_borrow(|textures| textures.get(&key).cloned()) {
+            return Ok(texture);
+        }
+
+        let decoded = match image {
+            Some(image) => decode_embedded(&key.0, image)?,
+            None => Self::decode(key.0.clone())?,
+        };
+
+        Ok(Self::from_decoded(decoded, display)?)
+    }
+
+    /// Read the image at `path` without touching the GPU. DDS files are kept compressed
+    pub fn decode(path: PathBuf) -> Result<DecodedTexture2D, TextureLoadError> {
+        AssetDatabase::register(&path);
+
+        let pixels = if dds::is_dds(&path) {
+            DecodedPixels::Compressed(dds::load_dds(&path).map_err(TextureLoadError::DdsLoadError)?)
+        } else {
+            DecodedPixels::Raw(texture::load_raw_image(&path)?)
+        };
+
+        Ok(DecodedTexture2D {
+            path,
+            image: None,
+            pixels,
+        })
+    }
+
+    /// Read the image of a glTF texture without touching the GPU. Images in files next to the model at
+    /// `model_path` are read from them, while `images` are those already decoded by `gltf::import`, which includes
+    /// those embedded in the model or in data URIs
+    pub fn decode_gltf(
+        texture: &gltf::Texture,
+        model_path: &Path,
+        images: &[gltf::image::Data],
+    ) -> Result<DecodedTexture2D, TextureLoadError> {
+        let source = texture.source();
+
+        match source.source() {
+            gltf::image::Source::Uri { uri, .. } if !uri.starts_with("data:") => {
+                // Textures referenced by the model are relative to it
+                let directory = model_path.parent().unwrap_or(Path::new(""));
+
+                Self::decode(directory.join(uri))
+            }
+            _ => {
+                let index = source.index();
+                let data = images.get(index).ok_or_else(|| {
+                    TextureLoadError::EmbeddedImageNotFound(model_path.to_path_buf(), index)
+                })?;
+
+                Ok(DecodedTexture2D {
+                    path: model_path.to_path_buf(),
+                    image: Some(index),
+                    pixels: DecodedPixels::Raw(texture::gltf_raw_image(data)?),
+                })
+            }
+        }
+    }
+
+    /// Upload a texture read with [`Texture2D::decode`] or [`Texture2D::decode_gltf`], unless the same image is
+    /// already loaded
+    pub fn from_decoded(
+        decoded: DecodedTexture2D,
+        display: &Display<WindowSurface>,
+    ) -> Result<Arc<Self>, TextureLoadError> {
+        let DecodedTexture2D {
+            path,
+            image,
+            pixels,
+        } = decoded;
+
+        let key = (path, image);
+        if let Some(texture) = TEXTURES.with_borrow(|textures| textures.get(&key).cloned()) {
+            return Ok(texture);
+        }
+
+        let texture = upload(key.0.clone(), image, pixels, display)?;
+        TEXTURES.with_borrow_mut(|textures| textures.insert(key, texture.clone()));
+
+        Ok(texture)
+    }
+
+    /// Free the textures which are only held by the cache, returning how many there were
+    pub fn collect_garbage() -> usize {
+        TEXTURES.with_borrow_mut(|textures| {
+            let count = textures.len();
+            textures.retain(|_, texture| Arc::strong_count(texture) > 1);
+
+            count - textures.len()
+        })
+    }
+
+    /// Load `path` again after it has changed on disk. Anything holding the old texture must be given the new one
+    pub fn reload(
+        path: PathBuf,
+        image: Option<usize>,
+        display: &Display<WindowSurface>,
+    ) -> Result<Arc<Self>> {
+        let key = (path, image);
+        TEXTURES.with_borrow_mut(|textures| textures.remove(&key));
+
+        Self::load_image(key.0, key.1, display)
     }
 
     pub fn default_diffuse(display: &Display<WindowSurface>) -> Result<Arc<Self>> {
@@ -32,6 +215,17 @@ impl Texture2D {
     pub fn solid(width: u32, height: u32, display: &Display<WindowSurface>) -> Result<Arc<Self>> {
         Ok(solid_grey_texture(255 / 2, width, height, display)?)
     }
+
+    /// A normal map which leaves the surface normal unchanged
+    pub fn flat_normal(display: &Display<WindowSurface>) -> Result<Arc<Self>> {
+        // Tangent space +Z
+        Ok(solid_color_texture((128, 128, 255), display)?)
+    }
+
+    /// Used where a texture adds nothing, such as the emission of a material which does not glow
+    pub fn black(display: &Display<WindowSurface>) -> Result<Arc<Self>> {
+        Ok(solid_color_texture((0, 0, 0), display)?)
+    }
 }
 
 #[memoize(Ignore: display)]
@@ -42,35 +236,127 @@ fn solid_grey_texture(
     height: u32,
     display: &Display<WindowSurface>,
 ) -> Result<Arc<Texture2D>, TextureLoadError> {
-    let opengl_texture = CompressedTexture2d::new(
+    let opengl_texture = Texture2d::new(
         display,
         vec![vec![(value / 255, value / 255, value / 255); height as usize]; width as usize],
     )
     .map_err(TextureLoadError::CreateTextureError)?;
 
     Ok(Arc::new(Texture2D {
-        inner_texture: Some(opengl_texture),
+        inner_texture: Some(GpuTexture::Uncompressed(opengl_texture)),
         path: PathBuf::new(),
+        image: None,
         uuid: Uuid::new_v4(),
     }))
 }
 
 #[memoize(Ignore: display)]
-fn load(
+fn solid_color_texture(
+    color: (u8, u8, u8),
+    display: &Display<WindowSurface>,
+) -> Result<Arc<Texture2D>, TextureLoadError> {
+    let opengl_texture =
+        Texture2d::new(display, vec![vec![color]]).map_err(TextureLoadError::CreateTextureError)?;
+
+    Ok(Arc::new(Texture2D {
+        inner_texture: Some(GpuTexture::Uncompressed(opengl_texture)),
+        path: PathBuf::new(),
+        image: None,
+        uuid: Uuid::new_v4(),
+    }))
+}
+
+thread_local! {
+    /// Textures by path and embedded image, so that everything using the same image shares it. These are kept until
+    /// [`Texture2D::collect_garbage`] finds that nothing else uses them
+    static TEXTURES: RefCell<HashMap<(PathBuf, Option<usize>), Arc<Texture2D>>> = RefCell::new(HashMap::new());
+}
+
+fn decode_embedded(path: &Path, image: usize) -> Result<DecodedTexture2D, TextureLoadError> {
+    let (_, _, images) = gltf::import(path)
+        .map_err(|_| TextureLoadError::EmbeddedImageNotFound(path.to_path_buf(), image))?;
+
+    let data = images
+        .get(image)
+        .ok_or_else(|| TextureLoadError::EmbeddedImageNotFound(path.to_path_buf(), image))?;
+
+    Ok(DecodedTexture2D {
+        path: path.to_path_buf(),
+        image: Some(image),
+        pixels: DecodedPixels::Raw(texture::gltf_raw_image(data)?),
+    })
+}
+
+fn upload(
     path: PathBuf,
+    image: Option<usize>,
+    pixels: DecodedPixels,
     display: &Display<WindowSurface>,
 ) -> Result<Arc<Texture2D>, TextureLoadError> {
-    let raw_image = texture::load_raw_image(&path)?;
-    let opengl_texture = CompressedTexture2d::new(display, raw_image)
-        .map_err(TextureLoadError::CreateTextureError)?;
+    let opengl_texture = match pixels {
+        // Without mipmaps, distant surfaces shimmer as each pixel skips over many texels
+        DecodedPixels::Raw(raw_image) => GpuTexture::Uncompressed(
+            Texture2d::with_mipmaps(display, raw_image, MipmapsOption::AutoGeneratedMipmaps)
+                .map_err(TextureLoadError::CreateTextureError)?,
+        ),
+        DecodedPixels::Compressed(dds) => {
+            GpuTexture::Compressed(upload_compressed(&path, dds, display)?)
+        }
+    };
 
     Ok(Arc::new(Texture2D {
         inner_texture: Some(opengl_texture),
-        path: path.clone(),
+        path,
+        image,
         uuid: Uuid::new_v4(),
     }))
 }
 
+/// Compressed textures cannot have their mipmaps generated, so each level is uploaded from the file
+fn upload_compressed(
+    path: &Path,
+    dds: Dds,
+    display: &Display<WindowSurface>,
+) -> Result<CompressedTexture2d, TextureLoadError> {
+    if dds.levels.len() == 1 {
+        warn!("{:?} has no mipmaps, so will shimmer in the distance", path);
+    }
+
+    let texture = CompressedTexture2d::with_compressed_data(
+        display,
+        &dds.levels[0],
+        dds.width,
+        dds.height,
+        dds.format,
+        CompressedMipmapsOption::EmptyMipmapsMax(dds.levels.len() as u32 - 1),
+    )
+    .map_err(TextureLoadError::CreateTextureError)?;
+
+    for (level, data) in dds.levels.iter().enumerate().skip(1) {
+        let width = (dds.width >> level).max(1);
+        let height = (dds.height >> level).max(1);
+
+        texture
+            .mipmap(level as u32)
+            .ok_or(TextureLoadError::CompressedMipmapError(level as u32))?
+            .write_compressed_data(
+                Rect {
+                    left: 0,
+                    bottom: 0,
+                    width,
+                    height,
+                },
+                data,
+                width,
+                height,
+                dds.format,
+            )
+            .map_err(|_| TextureLoadError::CompressedMipmapError(level as u32))?;
+    }
+
+    Ok(texture)
+}
+
 impl PartialEq<Self> for Texture2D {
     fn eq(&self, other: &Self) -> bool {
         self.uuid == other.uuid
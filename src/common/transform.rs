@@ -1,4 +1,5 @@
 use cgmath::{Matrix4, Quaternion, Vector3, Zero};
+use log::warn;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -8,6 +9,49 @@ pub struct Transform {
     pub scale: f32,
 }
 
+impl Transform {
+    /// A glTF node's local transform. Only uniform scale is supported, so non-uniform scales are averaged
+    pub fn from_gltf(transform: gltf::scene::Transform) -> Self {
+        let (translation, [x, y, z, w], scale) = transform.decomposed();
+
+        if scale[0] != scale[1] || scale[1] != scale[2] {
+            warn!("Non-uniform scale {scale:?} is not supported, averaging");
+        }
+
+        Self {
+            translation: Vector3::from(translation),
+            rotation: Quaternion::new(w, x, y, z),
+            scale: (scale[0] + scale[1] + scale[2]) / 3.0,
+        }
+    }
+
+    /// `local` relative to this transform, e.g. a child relative to its parent
+    pub fn then(&self, local: &Transform) -> Self {
+        Self {
+            translation: self.translation + self.rotation * (local.translation * self.scale),
+            rotation: self.rotation * local.rotation,
+            scale: self.scale * local.scale,
+        }
+    }
+
+    /// The transform which undoes this one, such that `self.inverse().then(self)` is the identity
+    pub fn inverse(&self) -> Self {
+        let rotation = self.rotation.conjugate();
+        let scale = 1.0 / self.scale;
+
+        Self {
+            translation: rotation * (-self.translation * scale),
+            rotation,
+            scale,
+        }
+    }
+
+    /// This transform relative to `parent`, the inverse of `parent.then(local)`
+    pub fn relative_to(&self, parent: &Transform) -> Self {
+        parent.inverse().then(self)
+    }
+}
+
 impl From<Transform> for Matrix4<f32> {
     fn from(value: Transform) -> Self {
         Matrix4::from_translation(value.translation)
@@ -0,0 +1,119 @@
+use common::billboard::Billboard;
+use common::light::Light;
+use common::models::ModelInstance;
+use common::scene::{Scene, SelectionSet};
+use common::transform::Transform;
+use petgraph::prelude::StableDiGraph;
+use petgraph::stable_graph::NodeIndex;
+
+/// An edit to the scene which can be undone
+pub trait Command {
+    fn apply(&mut self, scene: &mut Scene);
+    fn revert(&mut self, scene: &mut Scene);
+}
+
+/// How many commands can be undone. Edits to the nodes each hold two copies of the graph, which also keep the models
+/// and textures they use from being freed, so older commands are forgotten
+const MAX_UNDO: usize = 50;
+
+/// Commands which have been applied, and those which have been undone and can be redone
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Box<dyn Command>>,
+    redo: Vec<Box<dyn Command>>,
+}
+
+impl History {
+    /// Record a command which has already been applied
+    pub fn record(&mut self, command: impl Command + 'static) {
+        self.undo.push(Box::new(command));
+        if self.undo.len() > MAX_UNDO {
+            self.undo.remove(0);
+        }
+
+        // Redoing on top of a different scene than the one the commands were made for would go wrong
+        self.redo.clear();
+    }
+
+    pub fn undo(&mut self, scene: &mut Scene) {
+        if let Some(mut command) = self.undo.pop() {
+            command.revert(scene);
+            self.redo.push(command);
+        }
+    }
+
+    pub fn redo(&mut self, scene: &mut Scene) {
+        if let Some(mut command) = self.redo.pop() {
+            command.apply(scene);
+            self.undo.push(command);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+/// Moving a node, and its descendants with it
+pub struct SetTransform {
+    pub node_index: NodeIndex,
+    pub before: Transform,
+    pub after: Transform,
+}
+
+impl Command for SetTransform {
+    fn apply(&mut self, scene: &mut Scene) {
+        scene.set_transform(self.node_index, self.after.clone());
+    }
+
+    fn revert(&mut self, scene: &mut Scene) {
+        scene.set_transform(self.node_index, self.before.clone());
+    }
+}
+
+/// The parts of the scene which refer to nodes by index
+#[derive(Clone)]
+pub struct Nodes {
+    graph: StableDiGraph<ModelInstance, ()>,
+    billboards: Vec<Billboard>,
+    lights: Vec<Light>,
+    selection_sets: Vec<SelectionSet>,
+}
+
+impl Nodes {
+    pub fn of(scene: &Scene) -> Self {
+        Self {
+            graph: scene.graph.clone(),
+            billboards: scene.billboards.clone(),
+            lights: scene.lights.clone(),
+            selection_sets: scene.editor.selection_sets.clone(),
+        }
+    }
+
+    fn restore(&self, scene: &mut Scene) {
+        scene.graph = self.graph.clone();
+        scene.billboards = self.billboards.clone();
+        scene.lights = self.lights.clone();
+        scene.editor.selection_sets = self.selection_sets.clone();
+    }
+}
+
+/// Any edit to the nodes of the scene, such as adding, removing or importing them, or changing their materials,
+/// or adding and removing lights.
+/// The nodes are swapped back wholesale rather than re-added, as a re-added node could be given a different index
+/// and break the commands around it. Models and textures are shared, so the copies are cheap
+pub struct EditNodes {
+    pub before: Nodes,
+    pub after: Nodes,
+}
+
+impl Command for EditNodes {
+    fn apply(&mut self, scene: &mut Scene) {
+        self.after.restore(scene);
+    }
+
+    fn revert(&mut self, scene: &mut Scene) {
+        self.before.restore(scene);
+    }
+}
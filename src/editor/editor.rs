@@ -1,19 +1,25 @@
-use cgmath::Point3;
-use std::path::PathBuf;
+use cgmath::{Deg, EuclideanSpace, Euler, Point3, Quaternion, Vector3};
+use std::f32::consts::TAU;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc;
 use std::sync::mpsc::{Receiver, Sender};
-use std::time::Instant;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use egui_glium::egui_winit::egui;
-use egui_glium::egui_winit::egui::{Align, Button, Ui, ViewportId};
+use egui_glium::egui_winit::egui::{
+    pos2, Align, Align2, Button, Color32, ComboBox, CursorIcon, DragValue, FontId, Grid, Id, Image,
+    LayerId, Modifiers, Order, PointerButton, Pos2, Rect, Sense, Slider, Stroke, TextEdit, Ui,
+    Vec2, ViewportId,
+};
 use egui_glium::egui_winit::winit::event_loop::EventLoop;
 use egui_glium::EguiGlium;
 use itertools::Itertools;
-use log::info;
-use palette::Srgb;
+use log::{error, info};
+use palette::{FromColor, IntoColor, Srgb};
 use petgraph::prelude::StableDiGraph;
 use petgraph::stable_graph::NodeIndex;
-use petgraph::visit::{Bfs, IntoNodeReferences};
+use petgraph::visit::IntoNodeReferences;
 use petgraph::Direction;
 use rfd::FileDialog;
 use winit::event::{Event, MouseButton, WindowEvent};
@@ -21,33 +27,246 @@ use winit::event_loop::ControlFlow;
 use winit::keyboard::KeyCode;
 
 use app::Application;
-use common::camera::Camera;
-use common::camera::OrbitalCamera;
+use common::billboard::Billboard;
+use common::camera::{Camera, Projection, ViewAxis};
+use common::colliders::Ray;
 use common::colors::{Color, ColorExt};
+use common::fog::FogFalloff;
+use common::hot_reload::AssetWatcher;
 use common::light::Light;
-use common::line::Line;
 use common::models::ModelInstance;
-use common::models::{Material, Model};
-use common::renderer::Renderer;
-use common::scene::Background;
+use common::models::{EmissiveStrength, LevelOfDetail, Material, MetadataValue, Model};
+use common::post_processing::{ColorblindFilter, Tonemapping};
+use common::profiler::{Profiler, Scope};
+use common::renderer::{
+    AntiAliasing, GraphicsPreset, RenderSettings, Renderer, Shading, LIGHT_ICON_SIZE,
+};
+use common::scene::{Background, DecodedModel, FarPlaneClipping, SelectionSet, Subtree};
 use common::terrain::Terrain;
-use common::texture::{Cubemap, Texture2D};
+use common::texture::{Cubemap, DecodedCubemap, Texture2D, TextureFiltering, TextureWrap};
+use common::transform::Transform;
 use common::*;
 use context::OpenGLContext;
 use input::Input;
 use scene::Scene;
 
+use crate::command::{EditNodes, History, Nodes, SetTransform};
+use crate::viewport::{Comparison, Viewport};
+
+/// Where the models baked by "Merge static geometry" are written
+const MERGED_GEOMETRY_DIRECTORY: &str = "assets/models/merged";
+
 struct FrameState {
     pub last_frame_end: Instant,
     pub frame_count: u128,
     pub deltatime: f64,
     pub fps: f32,
     pub is_moving_camera: bool,
+    /// Size in pixels of the space left for the scene by the GUI
+    pub viewport_dimensions: (u32, u32),
     pub gui: GuiState,
 }
 
 struct GuiState {
-    pub render_lights: bool,
+    pub viewport_count: usize,
+    pub new_property_key: String,
+    pub stress_test_count: usize,
+    pub stress_test_spacing: f32,
+    pub new_selection_set_name: String,
+    /// The color which will be set by the next click on a viewport
+    pub eyedropper: Option<ColorTarget>,
+    pub show_grid: bool,
+    pub transform_space: TransformSpace,
+    /// The viewport a selection rectangle is being dragged out in, and where the drag started
+    pub marquee: Option<(usize, Pos2)>,
+    pub outliner: OutlinerOptions,
+    /// Where the radial menu was opened, while its key is held
+    pub radial_menu: Option<Pos2>,
+}
+
+/// A tool in the radial menu
+#[derive(Copy, Clone, PartialEq)]
+enum RadialAction {
+    View(ViewAxis),
+    ToggleProjection,
+    ToggleGrid,
+    ToggleShading,
+    Add(PrimitiveShape),
+}
+
+impl RadialAction {
+    fn label(self) -> &'static str {
+        match self {
+            RadialAction::View(ViewAxis::Top) => "Top view",
+            RadialAction::View(ViewAxis::Front) => "Front view",
+            RadialAction::View(ViewAxis::Right) => "Right view",
+            RadialAction::ToggleProjection => "Perspective/Orthographic",
+            RadialAction::ToggleGrid => "Grid",
+            RadialAction::ToggleShading => "Shading",
+            RadialAction::Add(PrimitiveShape::Cube) => "Add cube",
+            RadialAction::Add(PrimitiveShape::Teapot) => "Add teapot",
+        }
+    }
+}
+
+/// How the scene tree is laid out
+#[derive(Copy, Clone, Default)]
+struct OutlinerOptions {
+    /// Otherwise nodes are in the order they were added
+    sort_by_name: bool,
+    /// Show lights and billboards in the tree, each in their own group after the nodes
+    group_by_kind: bool,
+}
+
+/// How clicking or dragging out a rectangle changes the selection, in both the viewports and the scene tree
+#[derive(Copy, Clone)]
+enum SelectionMode {
+    Replace,
+    Add,
+    Remove,
+}
+
+impl SelectionMode {
+    /// Shift adds to the selection and Ctrl removes from it
+    fn from_modifiers(modifiers: Modifiers) -> Self {
+        if modifiers.shift {
+            Self::Add
+        } else if modifiers.ctrl {
+            Self::Remove
+        } else {
+            Self::Replace
+        }
+    }
+
+    fn apply(self, graph: &mut StableDiGraph<ModelInstance, ()>, nodes: &[NodeIndex]) {
+        if let SelectionMode::Replace = self {
+            for model_instance in graph.node_weights_mut() {
+                model_instance.selected = false;
+            }
+        }
+
+        for node_index in nodes {
+            if let Some(model_instance) = graph.node_weight_mut(*node_index) {
+                model_instance.selected = !matches!(self, SelectionMode::Remove);
+            }
+        }
+    }
+
+    fn apply_to_lights(self, lights: &mut [Light], indices: &[usize]) {
+        if let SelectionMode::Replace = self {
+            for light in lights.iter_mut() {
+                light.selected = false;
+            }
+        }
+
+        for i in indices {
+            if let Some(light) = lights.get_mut(*i) {
+                light.selected = !matches!(self, SelectionMode::Remove);
+            }
+        }
+    }
+}
+
+/// Whether transforms are edited relative to the parent node or to the world
+#[derive(Copy, Clone, PartialEq)]
+enum TransformSpace {
+    Local,
+    World,
+}
+
+impl TransformSpace {
+    const ALL: [TransformSpace; 2] = [Self::Local, Self::World];
+
+    fn label(self) -> &'static str {
+        match self {
+            TransformSpace::Local => "Local",
+            TransformSpace::World => "World",
+        }
+    }
+}
+
+/// A color in the scene which can be picked with the eyedropper
+#[derive(Copy, Clone, PartialEq)]
+enum ColorTarget {
+    Background,
+    Zenith,
+    Fog,
+    Light(usize),
+}
+
+impl ColorTarget {
+    fn color(self, scene: &mut Scene) -> Option<&mut Color> {
+        match self {
+            ColorTarget::Background => match &mut scene.background {
+                Background::Color(color) => Some(color),
+                Background::HDRI(_) => None,
+            },
+            ColorTarget::Zenith => scene.sky_gradient.as_mut(),
+            ColorTarget::Fog => Some(&mut scene.fog.color),
+            ColorTarget::Light(i) => scene.lights.get_mut(i).map(|light| &mut light.color),
+        }
+    }
+}
+
+/// The textures of a material which can be changed in the editor
+#[derive(Copy, Clone)]
+enum MaterialTexture {
+    Diffuse,
+    Normal,
+    Emissive,
+}
+
+impl MaterialTexture {
+    const ALL: [MaterialTexture; 3] = [Self::Diffuse, Self::Normal, Self::Emissive];
+
+    fn label(self) -> &'static str {
+        match self {
+            MaterialTexture::Diffuse => "Diffuse",
+            MaterialTexture::Normal => "Normal",
+            MaterialTexture::Emissive => "Emissive",
+        }
+    }
+
+    fn texture(self, material: &Material) -> Option<&Arc<Texture2D>> {
+        match self {
+            MaterialTexture::Diffuse => Some(&material.diffuse),
+            MaterialTexture::Normal => material.normal.as_ref(),
+            MaterialTexture::Emissive => material.emissive.as_ref(),
+        }
+    }
+
+    fn set(self, material: &mut Material, texture: Arc<Texture2D>) {
+        match self {
+            MaterialTexture::Diffuse => material.diffuse = texture,
+            MaterialTexture::Normal => material.normal = Some(texture),
+            MaterialTexture::Emissive => material.emissive = Some(texture),
+        }
+    }
+}
+
+/// Built in models which can be added from the scene tree
+#[derive(Copy, Clone, PartialEq)]
+enum PrimitiveShape {
+    Cube,
+    Teapot,
+}
+
+impl PrimitiveShape {
+    const ALL: [PrimitiveShape; 2] = [Self::Cube, Self::Teapot];
+
+    fn label(self) -> &'static str {
+        match self {
+            PrimitiveShape::Cube => "Cube",
+            PrimitiveShape::Teapot => "Teapot",
+        }
+    }
+
+    fn path(self) -> PathBuf {
+        match self {
+            PrimitiveShape::Cube => PathBuf::from("assets/models/cube.glb"),
+            PrimitiveShape::Teapot => PathBuf::from("assets/models/teapot.glb"),
+        }
+    }
 }
 
 impl FrameState {
@@ -61,19 +280,112 @@ impl FrameState {
     }
 }
 
+const MAX_VIEWPORTS: usize = 4;
+
+/// How often asset files are checked for changes to hot reload
+const ASSET_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How far duplicated and pasted nodes are moved from the originals, so that they can be seen
+const PASTE_OFFSET: Vector3<f32> = Vector3::new(1.0, 0.0, 1.0);
+
+/// Held over a viewport to open the radial menu, which runs the tool pointed at when it is let go
+const RADIAL_MENU_KEY: egui::Key = egui::Key::Q;
+/// The tools in the radial menu, clockwise from the top
+const RADIAL_MENU: [RadialAction; 8] = [
+    RadialAction::View(ViewAxis::Top),
+    RadialAction::View(ViewAxis::Right),
+    RadialAction::ToggleProjection,
+    RadialAction::Add(PrimitiveShape::Cube),
+    RadialAction::View(ViewAxis::Front),
+    RadialAction::Add(PrimitiveShape::Teapot),
+    RadialAction::ToggleShading,
+    RadialAction::ToggleGrid,
+];
+const RADIAL_MENU_RADIUS: f32 = 100.0;
+/// How far the pointer has to move from the centre of the radial menu to choose a tool
+const RADIAL_MENU_DEAD_ZONE: f32 = 20.0;
+
+// Icons in the scene tree, all of which are in egui's default fonts
+const MODEL_ICON: &str = "📦";
+const GROUP_ICON: &str = "📁";
+const LIGHT_ICON: &str = "💡";
+const BILLBOARD_ICON: &str = "🖼";
+const VISIBLE_ICON: &str = "👁";
+const LOCKED_ICON: &str = "🔒";
+
+/// Keys which select the first selection sets, in order
+const SELECTION_SET_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
 enum EngineEvent {
     ImportHDRIBackground(PathBuf),
+    /// An HDRI imported with `ImportHDRIBackground` has been read on another thread and is ready to upload
+    HDRIDecoded(PathBuf, color_eyre::Result<DecodedCubemap>),
     LoadScene(String),
     ImportModel(PathBuf),
+    /// A model imported with `ImportModel` has been read on another thread and is ready to upload
+    ModelDecoded(PathBuf, color_eyre::Result<DecodedModel>),
+    AddLevelOfDetail(NodeIndex, PathBuf),
+    LoadMaterial(NodeIndex, PathBuf),
+    SetMaterialTexture(NodeIndex, MaterialTexture, PathBuf),
+    /// Capture the active viewport for comparison
+    CaptureComparison,
+    AddBillboard(PathBuf),
+    /// Add a primitive as a child of the node, or at the top level
+    AddPrimitive(Option<NodeIndex>, PrimitiveShape),
+    /// Add a light which follows the node, or one where the camera is looking
+    AddLight(Option<NodeIndex>),
+    RemoveLight(usize),
+    /// Scatter copies of the selected nodes, as many and as far apart as set in the stress test panel
+    ScatterSelected,
+    /// Remove a node, and its children unless the flag is set
+    RemoveNode(NodeIndex, bool),
+    Duplicate(Vec<NodeIndex>),
+    Copy(Vec<NodeIndex>),
+    /// Paste the copied nodes under the node, or at the top level
+    Paste(Option<NodeIndex>),
+    /// Bake the nodes into as few models as possible, see `Scene::merge_static_geometry`
+    MergeStaticGeometry(Vec<NodeIndex>),
+}
+
+/// How far an asset being read on another thread has got
+enum ImportState {
+    Loading,
+    /// The error, shown until it is dismissed
+    Failed(String),
 }
 
 pub struct Editor {
     input: Input,
     scene: Scene,
-    camera: OrbitalCamera,
     renderer: Renderer,
     opengl_context: OpenGLContext,
     gui: EguiGlium,
+    /// Only the first `GuiState::viewport_count` are shown
+    viewports: Vec<Viewport>,
+    /// The viewport which the mouse was last over, whose camera is moved
+    active_viewport: usize,
+    comparison: Option<Comparison>,
+    /// Models and HDRIs being read on other threads, and those which could not be. Imports are removed once they
+    /// have been added to the scene
+    imports: Vec<(PathBuf, ImportState)>,
+    asset_watcher: AssetWatcher,
+    last_asset_poll: Instant,
+    history: History,
+    profiler: Profiler,
+    /// Nodes copied with Ctrl+C
+    clipboard: Vec<Subtree>,
+    /// A node being moved in the properties panel, and its transform before the edit began
+    editing_transform: Option<(NodeIndex, Transform)>,
     state: FrameState,
     sender: Sender<EngineEvent>,
     receiver: Receiver<EngineEvent>,
@@ -88,26 +400,6 @@ impl Editor {
         let opengl_context = OpenGLContext::new("We glium teapot now", false, event_loop);
 
         let mut scene = Scene {
-            lines: vec![
-                Line::new(
-                    Point3::new(-1000.0, 0.0, 0.0),
-                    Point3::new(1000.0, 0.0, 0.0),
-                    Srgb::from(palette::named::RED),
-                    2,
-                ),
-                Line::new(
-                    Point3::new(0.0, -1000.0, 0.0),
-                    Point3::new(0.0, 1000.0, 0.0),
-                    Srgb::from(palette::named::GREEN),
-                    2,
-                ),
-                Line::new(
-                    Point3::new(0.0, 0.0, -1000.0),
-                    Point3::new(0.0, 0.0, 1000.0),
-                    Srgb::from(palette::named::BLUE),
-                    2,
-                ),
-            ],
             terrain: Some(
                 Terrain::load(
                     &PathBuf::from("assets/game_scenes/terrain_heightmap.png"),
@@ -118,8 +410,6 @@ impl Editor {
             ..Default::default()
         };
 
-        let camera = OrbitalCamera::default();
-
         let mut model_instance = ModelInstance::from(
             Model::load(
                 PathBuf::from("assets/models/cube.glb"),
@@ -138,6 +428,12 @@ impl Editor {
                 &opengl_context.display,
             )
             .unwrap(),
+            normal: None,
+            filtering: None,
+            wrap: TextureWrap::default(),
+            emissive: None,
+            emissive_strength: EmissiveStrength::default(),
+            transparent: false,
         });
 
         scene.graph.add_node(model_instance.clone());
@@ -154,6 +450,7 @@ impl Editor {
         scene.lights.push(Light {
             position: Point3::new(3.0, 2.0, 1.0),
             color: Color::from_named(palette::named::WHITE),
+            ..Light::default()
         });
 
         // let size = 10;
@@ -176,21 +473,37 @@ impl Editor {
 
         let input = Input::new();
 
-        let gui = EguiGlium::new(
+        let mut gui = EguiGlium::new(
             ViewportId::ROOT,
             &opengl_context.display,
             &opengl_context.window,
             event_loop,
         );
 
+        // Resized to fit the central panel once the GUI has been laid out
+        let viewports = (0..MAX_VIEWPORTS)
+            .map(|_| Viewport::new((1, 1), &opengl_context.display, &mut gui.painter).unwrap())
+            .collect_vec();
+
         let state = FrameState {
             last_frame_end: Instant::now(),
             frame_count: 0,
             deltatime: 0.0,
             fps: 0.0,
             is_moving_camera: false,
+            viewport_dimensions: (1, 1),
             gui: GuiState {
-                render_lights: true,
+                viewport_count: 1,
+                new_property_key: String::new(),
+                stress_test_count: 1000,
+                stress_test_spacing: 2.0,
+                new_selection_set_name: String::new(),
+                eyedropper: None,
+                show_grid: true,
+                transform_space: TransformSpace::Local,
+                marquee: None,
+                outliner: OutlinerOptions::default(),
+                radial_menu: None,
             },
         };
 
@@ -202,10 +515,110 @@ impl Editor {
             renderer,
             input,
             gui,
+            viewports,
             state,
             sender,
             receiver,
-            camera,
+            active_viewport: 0,
+            comparison: None,
+            imports: Vec::new(),
+            asset_watcher: AssetWatcher::default(),
+            last_asset_poll: Instant::now(),
+            history: History::default(),
+            profiler: Profiler::default(),
+            clipboard: Vec::new(),
+            editing_transform: None,
+        }
+    }
+
+    /// Record an edit to the scene's nodes made since `before`, so that it can be undone
+    fn record_node_edit(&mut self, before: Nodes) {
+        self.finish_transform_edit();
+
+        self.history.record(EditNodes {
+            before,
+            after: Nodes::of(&self.scene),
+        });
+    }
+
+    /// Stop showing `path` as loading, and show the error if it could not be imported
+    fn finish_import(&mut self, path: &Path, err: Option<color_eyre::Report>) {
+        let loading = self
+            .imports
+            .iter()
+            .position(|(import, state)| import == path && matches!(state, ImportState::Loading));
+        if let Some(i) = loading {
+            self.imports.remove(i);
+        }
+
+        if let Some(err) = err {
+            self.imports.push((
+                path.to_path_buf(),
+                ImportState::Failed(format!("{:#}", err)),
+            ));
+        }
+    }
+
+    /// Copy each node with its descendants, next to the original, and select the copies
+    fn duplicate(&mut self, nodes: &[NodeIndex]) {
+        let before = Nodes::of(&self.scene);
+
+        let copies = nodes
+            .iter()
+            .filter(|node_index| self.scene.graph.contains_node(**node_index))
+            .map(|node_index| {
+                let subtree = self.scene.copy_subtree(*node_index);
+                let parent = self.scene.parent(*node_index);
+
+                self.scene.paste(&subtree, parent, PASTE_OFFSET)
+            })
+            .collect_vec();
+
+        if !copies.is_empty() {
+            self.scene.select_only(&copies);
+            self.record_node_edit(before);
+        }
+    }
+
+    fn copy(&mut self, nodes: &[NodeIndex]) {
+        self.clipboard = nodes
+            .iter()
+            .filter(|node_index| self.scene.graph.contains_node(**node_index))
+            .map(|node_index| self.scene.copy_subtree(*node_index))
+            .collect_vec();
+    }
+
+    /// Add the copied nodes under `parent`, or at the top level, and select them
+    fn paste(&mut self, parent: Option<NodeIndex>) {
+        if self.clipboard.is_empty() {
+            return;
+        }
+
+        let parent = parent.filter(|parent| self.scene.graph.contains_node(*parent));
+        let before = Nodes::of(&self.scene);
+
+        let copies = self
+            .clipboard
+            .iter()
+            .map(|subtree| self.scene.paste(subtree, parent, PASTE_OFFSET))
+            .collect_vec();
+
+        self.scene.select_only(&copies);
+        self.record_node_edit(before);
+    }
+
+    /// Record the transform edit in progress, so that it is undone in one step rather than frame by frame
+    fn finish_transform_edit(&mut self) {
+        let Some((node_index, before)) = self.editing_transform.take() else {
+            return;
+        };
+
+        if let Some(model_instance) = self.scene.graph.node_weight(node_index) {
+            self.history.record(SetTransform {
+                node_index,
+                before,
+                after: model_instance.transform.clone(),
+            });
         }
     }
 }
@@ -229,10 +642,6 @@ impl Application for Editor {
                                 self.opengl_context
                                     .display
                                     .resize((new_size.width, new_size.height));
-
-                                self.camera.set_aspect_ratio(
-                                    new_size.width as f32 / new_size.height as f32,
-                                );
                             }
                             WindowEvent::RedrawRequested => {
                                 if self.input.key_pressed(KeyCode::Escape) {
@@ -242,6 +651,7 @@ impl Application for Editor {
                                 self.update();
                                 self.render();
 
+                                self.profiler.finish_frame();
                                 self.state.update_statistics();
                             }
                             _ => (),
@@ -263,31 +673,334 @@ impl Application for Editor {
     }
 
     fn update(&mut self) {
+        self.profiler.begin("Update");
+        self.profiler.begin("Engine events");
+
         for engine_event in self.receiver.try_iter() {
             match engine_event {
                 EngineEvent::LoadScene(scene_string) => {
-                    self.scene =
-                        Scene::from_string(&scene_string, &self.opengl_context.display).unwrap()
+                    match Scene::from_string(&scene_string, &self.opengl_context.display) {
+                        Ok(scene) => {
+                            self.scene = scene;
+
+                            let cameras = std::mem::take(&mut self.scene.editor.cameras);
+                            for (viewport, camera) in self.viewports.iter_mut().zip(cameras) {
+                                viewport.camera = camera;
+                            }
+
+                            self.editing_transform = None;
+                            self.history.clear();
+                            collect_garbage();
+                        }
+                        Err(err) => error!("Could not open scene: {:?}", err),
+                    }
+                }
+                EngineEvent::ImportModel(model_path) => {
+                    self.imports
+                        .push((model_path.clone(), ImportState::Loading));
+
+                    // Only the upload needs the display, so reading the file does not freeze the editor
+                    let sender = self.sender.clone();
+                    std::thread::spawn(move || {
+                        let decoded = DecodedModel::decode(&model_path);
+                        sender
+                            .send(EngineEvent::ModelDecoded(model_path, decoded))
+                            .unwrap();
+                    });
+                }
+                EngineEvent::ModelDecoded(model_path, decoded) => {
+                    let before = Nodes::of(&self.scene);
+                    let imported = decoded.and_then(|decoded| {
+                        self.scene
+                            .add_decoded_model(decoded, &self.opengl_context.display)
+                    });
+
+                    match imported {
+                        Ok(()) => {
+                            self.finish_import(&model_path, None);
+                            self.record_node_edit(before);
+                        }
+                        Err(err) => {
+                            error!("Could not import {:?}: {:?}", model_path, err);
+                            self.finish_import(&model_path, Some(err));
+                        }
+                    }
+                }
+                EngineEvent::AddLevelOfDetail(node_index, model_path) => {
+                    match Model::load(model_path, &self.opengl_context.display) {
+                        Ok(model) => {
+                            if let Some(model_instance) =
+                                self.scene.graph.node_weight_mut(node_index)
+                            {
+                                let distance = model_instance
+                                    .levels_of_detail
+                                    .iter()
+                                    .map(|level_of_detail| level_of_detail.distance)
+                                    .fold(0.0, f32::max)
+                                    + 10.0;
+
+                                let before = Nodes::of(&self.scene);
+
+                                self.scene.graph[node_index]
+                                    .levels_of_detail
+                                    .push(LevelOfDetail { model, distance });
+
+                                self.record_node_edit(before);
+                            }
+                        }
+                        Err(err) => error!("Could not load level of detail: {:?}", err),
+                    }
+                }
+                EngineEvent::AddPrimitive(parent, shape) => {
+                    match Model::load(shape.path(), &self.opengl_context.display) {
+                        Ok(model) => {
+                            let mut model_instance = ModelInstance::from(model);
+                            model_instance.name = shape.label().to_owned();
+
+                            match parent.and_then(|parent| self.scene.graph.node_weight(parent)) {
+                                Some(parent) => model_instance.transform = parent.transform.clone(),
+                                None => {
+                                    model_instance.transform.translation =
+                                        self.viewports[self.active_viewport].camera.target.to_vec()
+                                }
+                            }
+
+                            let before = Nodes::of(&self.scene);
+                            let node_index = self.scene.graph.add_node(model_instance);
+
+                            if let Some(parent) = parent {
+                                self.scene.graph.add_edge(parent, node_index, ());
+                            }
+
+                            self.scene.select_only(&[node_index]);
+                            self.record_node_edit(before);
+                        }
+                        Err(err) => error!("Could not add {}: {:?}", shape.label(), err),
+                    }
+                }
+                EngineEvent::AddLight(parent) => {
+                    let before = Nodes::of(&self.scene);
+
+                    // Where the camera is looking, so that it can be seen straight away
+                    let position = match parent {
+                        Some(_) => Point3::new(0.0, 0.0, 0.0),
+                        None => self.viewports[self.active_viewport].camera.target,
+                    };
+
+                    for light in self.scene.lights.iter_mut() {
+                        light.selected = false;
+                    }
+
+                    self.scene.lights.push(Light {
+                        parent,
+                        position,
+                        selected: true,
+                        ..Light::default()
+                    });
+                    self.record_node_edit(before);
+                }
+                EngineEvent::RemoveLight(i) => {
+                    let before = Nodes::of(&self.scene);
+                    self.scene.lights.remove(i);
+                    self.record_node_edit(before);
+                }
+                EngineEvent::ScatterSelected => {
+                    let before = Nodes::of(&self.scene);
+
+                    for node_index in self.scene.selected_nodes() {
+                        self.scene.scatter_instances(
+                            node_index,
+                            self.state.gui.stress_test_count,
+                            self.state.gui.stress_test_spacing,
+                        );
+                    }
+
+                    self.record_node_edit(before);
+                }
+                EngineEvent::MergeStaticGeometry(nodes) => {
+                    let before = Nodes::of(&self.scene);
+
+                    match self.scene.merge_static_geometry(
+                        &nodes,
+                        Path::new(MERGED_GEOMETRY_DIRECTORY),
+                        &self.opengl_context.display,
+                    ) {
+                        Ok(merged) if merged.is_empty() => {
+                            info!("None of the selected nodes could be merged")
+                        }
+                        Ok(merged) => {
+                            info!("Merged the selected nodes into {} models", merged.len());
+                            self.scene.select_only(&merged);
+                            self.record_node_edit(before);
+                        }
+                        Err(err) => error!("Could not merge the static geometry: {:?}", err),
+                    }
+                }
+                EngineEvent::RemoveNode(node_index, keep_children) => {
+                    let before = Nodes::of(&self.scene);
+                    self.scene.remove_node(node_index, keep_children);
+                    self.record_node_edit(before);
+
+                    // Models kept alive by the undo history are not freed
+                    collect_garbage();
+                }
+                EngineEvent::Duplicate(nodes) => self.duplicate(&nodes),
+                EngineEvent::Copy(nodes) => self.copy(&nodes),
+                EngineEvent::Paste(parent) => self.paste(parent),
+                EngineEvent::AddBillboard(texture_path) => {
+                    match Texture2D::load(texture_path, &self.opengl_context.display) {
+                        Ok(texture) => {
+                            // Above the first selected node, or where the camera is looking
+                            let parent = self.scene.selected_nodes().first().copied();
+                            let offset = match parent {
+                                Some(_) => Vector3::new(0.0, 1.0, 0.0),
+                                None => self.viewports[self.active_viewport].camera.target.to_vec(),
+                            };
+
+                            let before = Nodes::of(&self.scene);
+                            self.scene
+                                .billboards
+                                .push(Billboard::new(texture, parent, offset));
+                            self.record_node_edit(before);
+                        }
+                        Err(err) => error!("Could not load billboard: {:?}", err),
+                    }
+                }
+                EngineEvent::LoadMaterial(node_index, path) => {
+                    match Material::load_asset(&path, &self.opengl_context.display) {
+                        Ok(material) => {
+                            let before = Nodes::of(&self.scene);
+
+                            if let Some(model_instance) =
+                                self.scene.graph.node_weight_mut(node_index)
+                            {
+                                model_instance.material = Some(material);
+                                self.record_node_edit(before);
+                            }
+                        }
+                        Err(err) => error!("Could not load material: {:?}", err),
+                    }
+                }
+                EngineEvent::SetMaterialTexture(node_index, slot, path) => {
+                    let display = &self.opengl_context.display;
+                    let before = Nodes::of(&self.scene);
+
+                    let result = Texture2D::load(path, display).and_then(|texture| {
+                        let Some(model_instance) = self.scene.graph.node_weight_mut(node_index)
+                        else {
+                            return Ok(());
+                        };
+
+                        // Instances without a material are drawn with the default, so start from that
+                        if model_instance.material.is_none() {
+                            model_instance.material = Some(Material::default(display)?);
+                        }
+
+                        slot.set(model_instance.material.as_mut().unwrap(), texture);
+
+                        Ok(())
+                    });
+
+                    match result {
+                        Ok(()) => self.record_node_edit(before),
+                        Err(err) => error!("Could not load texture: {:?}", err),
+                    }
+                }
+                EngineEvent::CaptureComparison => {
+                    let display = &self.opengl_context.display;
+                    let viewport = &self.viewports[self.active_viewport];
+
+                    let result = match self.comparison.as_mut() {
+                        Some(comparison) => comparison.recapture(
+                            self.active_viewport,
+                            viewport,
+                            display,
+                            &mut self.gui.painter,
+                        ),
+                        None => Comparison::capture(
+                            self.active_viewport,
+                            viewport,
+                            display,
+                            &mut self.gui.painter,
+                        )
+                        .map(|comparison| self.comparison = Some(comparison)),
+                    };
+
+                    if let Err(err) = result {
+                        error!("Could not capture the viewport: {:?}", err);
+                    }
                 }
-                EngineEvent::ImportModel(model_path) => self
-                    .scene
-                    .import_model(model_path.as_path(), &self.opengl_context.display)
-                    .unwrap(),
                 EngineEvent::ImportHDRIBackground(hdri_directory_path) => {
-                    self.scene.background = Background::HDRI(
-                        Cubemap::load(hdri_directory_path, &self.opengl_context.display).unwrap(),
-                    )
+                    match Cubemap::cached(&hdri_directory_path) {
+                        Some(cubemap) => self.scene.background = Background::HDRI(cubemap),
+                        None => {
+                            self.imports
+                                .push((hdri_directory_path.clone(), ImportState::Loading));
+
+                            let sender = self.sender.clone();
+                            std::thread::spawn(move || {
+                                let decoded = Cubemap::decode(hdri_directory_path.clone())
+                                    .map_err(color_eyre::Report::from);
+                                sender
+                                    .send(EngineEvent::HDRIDecoded(hdri_directory_path, decoded))
+                                    .unwrap();
+                            });
+                        }
+                    }
+                }
+                EngineEvent::HDRIDecoded(hdri_directory_path, decoded) => {
+                    let cubemap = decoded.and_then(|decoded| {
+                        Cubemap::from_decoded(decoded, &self.opengl_context.display)
+                    });
+
+                    match cubemap {
+                        Ok(cubemap) => {
+                            self.finish_import(&hdri_directory_path, None);
+                            self.scene.background = Background::HDRI(cubemap);
+                        }
+                        Err(err) => {
+                            error!("Could not import HDRI {:?}: {:?}", hdri_directory_path, err);
+                            self.finish_import(&hdri_directory_path, Some(err));
+                        }
+                    }
+                }
+            }
+        }
+
+        self.profiler.end();
+        self.profiler.begin("Hot reload");
+
+        if self.last_asset_poll.elapsed() >= ASSET_POLL_INTERVAL {
+            self.last_asset_poll = Instant::now();
+
+            for path in self.asset_watcher.changed(self.scene.asset_paths()) {
+                info!("{:?} changed on disk, reloading...", path);
+
+                if let Err(err) = self.scene.reload_asset(&path, &self.opengl_context.display) {
+                    error!("Could not reload {:?}: {:?}", path, err);
                 }
             }
         }
 
-        self.camera.update_zoom(&self.input);
+        self.profiler.end();
+        self.profiler.begin("Camera");
+
+        for viewport in self.viewports.iter_mut() {
+            viewport.camera.set_clipping_planes(
+                self.scene.camera_settings.near,
+                self.scene.camera_settings.far,
+            );
+            viewport.camera.smooth(self.state.deltatime as f32);
+        }
+
+        let camera = &mut self.viewports[self.active_viewport].camera;
+        camera.update_zoom(&self.input);
 
         self.state.is_moving_camera = self.input.mouse_button_down(MouseButton::Middle)
             || self.input.key_down(KeyCode::Space);
 
         if self.state.is_moving_camera {
-            self.camera.update(&self.input, self.state.deltatime as f32);
+            camera.update(&self.input, self.state.deltatime as f32);
             self.opengl_context.capture_cursor();
             self.opengl_context.window.set_cursor_visible(false);
             self.opengl_context.center_cursor();
@@ -296,6 +1009,92 @@ impl Application for Editor {
             self.opengl_context.window.set_cursor_visible(true);
         }
 
+        self.profiler.end();
+        self.profiler.begin("Shortcuts");
+
+        // Keys typed into a text field are not shortcuts
+        let typing = self.gui.egui_ctx.wants_keyboard_input();
+
+        // The same keys as Blender
+        let camera = &mut self.viewports[self.active_viewport].camera;
+        if self.input.key_pressed(KeyCode::Numpad5) && !typing {
+            camera.set_projection_mode(match camera.projection_mode() {
+                Projection::Perspective => Projection::Orthographic,
+                Projection::Orthographic => Projection::Perspective,
+            });
+        }
+
+        for (key_code, axis) in [
+            (KeyCode::Numpad7, ViewAxis::Top),
+            (KeyCode::Numpad1, ViewAxis::Front),
+            (KeyCode::Numpad3, ViewAxis::Right),
+        ] {
+            if self.input.key_pressed(key_code) && !typing {
+                camera.look_along(axis);
+            }
+        }
+
+        // Frame the selection, as with view selected in Blender
+        if self.input.key_pressed(KeyCode::NumpadDecimal) && !typing {
+            let selection = self
+                .scene
+                .selected_nodes()
+                .iter()
+                .filter_map(|node_index| self.scene.graph[*node_index].bounding_sphere())
+                .reduce(|a, b| a.union(&b));
+
+            if let Some(sphere) = selection {
+                camera.target = Point3::from_vec(sphere.center);
+                // Far enough away that all of the sphere fits in the field of view
+                camera.radius = (sphere.radius / (camera.fov() / 2.0).sin()).max(camera.near());
+            }
+        }
+
+        let control =
+            self.input.key_down(KeyCode::ControlLeft) || self.input.key_down(KeyCode::ControlRight);
+        let shift =
+            self.input.key_down(KeyCode::ShiftLeft) || self.input.key_down(KeyCode::ShiftRight);
+
+        if self.input.key_pressed(KeyCode::KeyZ) && !typing {
+            if control {
+                self.finish_transform_edit();
+
+                if shift {
+                    self.history.redo(&mut self.scene);
+                } else {
+                    self.history.undo(&mut self.scene);
+                }
+            } else {
+                self.renderer.settings.shading = self.renderer.settings.shading.toggled();
+            }
+        }
+
+        // Text fields have their own copy and paste
+        if control && !self.gui.egui_ctx.wants_keyboard_input() {
+            if self.input.key_pressed(KeyCode::KeyD) {
+                self.duplicate(&self.scene.selected_roots());
+            }
+
+            if self.input.key_pressed(KeyCode::KeyC) {
+                self.copy(&self.scene.selected_roots());
+            }
+
+            if self.input.key_pressed(KeyCode::KeyV) {
+                self.paste(None);
+            }
+        }
+
+        for (key_code, selection_set) in SELECTION_SET_KEYS
+            .iter()
+            .zip(self.scene.editor.selection_sets.clone())
+        {
+            if self.input.key_pressed(*key_code) && !typing {
+                self.scene.select_only(&selection_set.nodes);
+            }
+        }
+
+        self.profiler.end();
+
         self.input.reset_internal_state();
 
         if self.state.frame_count % 5 == 0 {
@@ -316,30 +1115,88 @@ impl Application for Editor {
         // self.scene.graph[node_indices[0]].transform.rotation =
         //     Quaternion::from_angle_y(Deg((self.state.frame_count % 360) as f32));
 
+        self.profiler.begin("Render");
+        self.renderer.begin_frame();
+
+        // The GUI decides how much space is left for the scene
+        self.profiler.begin("GUI");
+        self.render_gui();
+        self.profiler.end();
+
+        self.profiler.begin("Viewports");
+        let (width, height) = self.state.viewport_dimensions;
+        if width > 0 && height > 0 {
+            for i in 0..self.state.gui.viewport_count {
+                self.render_viewport(i);
+            }
+        }
+        self.profiler.end();
+
+        self.profiler.begin("Present");
         let mut target = self.opengl_context.display.draw();
+        target.clear_color(0.0, 0.0, 0.0, 1.0);
+        self.gui.paint(&self.opengl_context.display, &mut target);
+        target.finish().unwrap();
+        self.profiler.end();
+
+        self.profiler.end();
+    }
+
+    /// Draw the scene into the texture of a viewport shown by the central panel
+    fn render_viewport(&mut self, i: usize) {
+        let display = &self.opengl_context.display;
+        let dimensions = self.state.viewport_dimensions;
+        let viewport = &mut self.viewports[i];
+
+        viewport
+            .resize(dimensions, display, &mut self.gui.painter)
+            .unwrap();
+        viewport
+            .camera
+            .set_aspect_ratio(dimensions.0 as f32 / dimensions.1 as f32);
+        let camera = &viewport.camera;
+
+        let scene_target = self.renderer.scene_target(dimensions, display).unwrap();
+
         {
+            let mut scene_framebuffer = scene_target
+                .framebuffer(&self.opengl_context.display)
+                .unwrap();
+
             self.scene.render(
                 &mut self.renderer,
-                &self.camera.view(),
-                &self.camera.projection(),
-                self.camera.position(),
+                &camera.view(),
+                &camera.projection(),
+                camera.position(),
                 &self.opengl_context.display,
-                &mut target,
+                &mut scene_framebuffer,
             );
 
-            if self.state.gui.render_lights {
+            if self.state.gui.show_grid {
+                self.renderer.render_grid(
+                    &(camera.projection() * camera.view()),
+                    camera.position(),
+                    self.scene.camera_settings.far,
+                    &mut scene_framebuffer,
+                );
+            }
+
+            if viewport.render_lights {
                 self.renderer.render_lights(
-                    &self.scene.lights,
-                    &(self.camera.projection() * self.camera.view()),
+                    &self.scene.world_lights(),
+                    &(camera.projection() * camera.view()),
                     &self.opengl_context.display,
-                    &mut target,
+                    &mut scene_framebuffer,
                 );
             }
-
-            self.render_gui();
-            self.gui.paint(&self.opengl_context.display, &mut target);
         }
-        target.finish().unwrap();
+
+        self.renderer.post_process(
+            &scene_target,
+            &self.scene.post_processing,
+            display,
+            &mut viewport.framebuffer(display).unwrap(),
+        );
     }
 
     fn render_gui(&mut self) {
@@ -350,6 +1207,9 @@ impl Application for Editor {
                         ui.menu_button("File", |ui| {
                             if ui.add(Button::new("New")).clicked() {
                                 self.scene = Scene::default();
+                                self.editing_transform = None;
+                                self.history.clear();
+                                collect_garbage();
 
                                 ui.close_menu();
                             }
@@ -364,9 +1224,14 @@ impl Application for Editor {
                                         .set_directory("/")
                                         .pick_file()
                                     {
-                                        let scene_string = std::fs::read_to_string(file).unwrap();
-
-                                        sender.send(EngineEvent::LoadScene(scene_string)).unwrap();
+                                        match std::fs::read_to_string(&file) {
+                                            Ok(scene_string) => sender
+                                                .send(EngineEvent::LoadScene(scene_string))
+                                                .unwrap(),
+                                            Err(err) => {
+                                                error!("Could not read scene {:?}: {}", file, err)
+                                            }
+                                        }
                                     }
                                 });
 
@@ -375,7 +1240,33 @@ impl Application for Editor {
 
                             if ui.add(Button::new("Save as")).clicked() {
                                 info!("Saving scene...");
+                                self.scene.editor.cameras = self
+                                    .viewports
+                                    .iter()
+                                    .map(|viewport| viewport.camera.clone())
+                                    .collect_vec();
                                 self.scene.save_as();
+                                self.scene.editor.cameras.clear();
+                                ui.close_menu();
+                            }
+
+                            if ui.add(Button::new("Export glTF")).clicked() {
+                                match export::glb::export_scene(&self.scene) {
+                                    Ok(glb) => {
+                                        std::thread::spawn(move || {
+                                            if let Some(path) = FileDialog::new()
+                                                .add_filter("glb", &["glb"])
+                                                .save_file()
+                                            {
+                                                if let Err(err) = std::fs::write(&path, glb) {
+                                                    error!("Could not write {:?}: {}", path, err);
+                                                }
+                                            }
+                                        });
+                                    }
+                                    Err(err) => error!("Could not export scene: {:?}", err),
+                                }
+
                                 ui.close_menu();
                             }
                         });
@@ -386,7 +1277,7 @@ impl Application for Editor {
 
                                 std::thread::spawn(move || {
                                     if let Some(paths) = FileDialog::new()
-                                        .add_filter("gltf", &["gltf", "glb"])
+                                        .add_filter("model", &["gltf", "glb", "obj"])
                                         .set_can_create_directories(true)
                                         .set_directory("/")
                                         .pick_files()
@@ -401,6 +1292,52 @@ impl Application for Editor {
                             }
                         });
 
+                        ui.menu_button("Add", |ui| {
+                            if ui.add(Button::new("Light")).clicked() {
+                                self.sender.send(EngineEvent::AddLight(None)).unwrap();
+                                ui.close_menu();
+                            }
+
+                            if ui.add(Button::new("Billboard")).clicked() {
+                                let sender = self.sender.clone();
+
+                                std::thread::spawn(move || {
+                                    if let Some(path) = FileDialog::new()
+                                        .add_filter("image", &["png", "jpg", "jpeg", "dds"])
+                                        .set_directory("/")
+                                        .pick_file()
+                                    {
+                                        sender.send(EngineEvent::AddBillboard(path)).unwrap();
+                                    }
+                                });
+
+                                ui.close_menu();
+                            }
+                        });
+
+                        ui.menu_button("View", |ui| {
+                            for (viewport_count, label) in [
+                                (1, "Single viewport"),
+                                (2, "Two viewports"),
+                                (MAX_VIEWPORTS, "Four viewports"),
+                            ] {
+                                if ui
+                                    .radio_value(
+                                        &mut self.state.gui.viewport_count,
+                                        viewport_count,
+                                        label,
+                                    )
+                                    .clicked()
+                                {
+                                    self.active_viewport = 0;
+                                    ui.close_menu();
+                                }
+                            }
+
+                            ui.separator();
+                            ui.checkbox(&mut self.state.gui.show_grid, "Grid");
+                        });
+
                         ui.menu_button("Run", |ui| {
                             if ui.add(Button::new("Run game")).clicked() {
                                 std::process::Command::new("cargo")
@@ -417,12 +1354,63 @@ impl Application for Editor {
                                 ui.close_menu();
                             }
                         });
+
+                        let file_name = |path: &PathBuf| {
+                            path.file_name()
+                                .map(|name| name.to_string_lossy().into_owned())
+                                .unwrap_or_default()
+                        };
+
+                        let loading = self
+                            .imports
+                            .iter()
+                            .filter(|(_, state)| matches!(state, ImportState::Loading))
+                            .map(|(path, _)| file_name(path))
+                            .collect_vec();
+
+                        if !loading.is_empty() {
+                            ui.separator();
+                            ui.spinner();
+                            ui.label(format!("Importing {}...", loading.join(", ")));
+                        }
+
+                        let mut dismissed = None;
+
+                        for (i, (path, state)) in self.imports.iter().enumerate() {
+                            if let ImportState::Failed(err) = state {
+                                ui.separator();
+                                ui.colored_label(
+                                    Color32::LIGHT_RED,
+                                    format!("Could not import {}", file_name(path)),
+                                )
+                                .on_hover_text(err.as_str());
+
+                                if ui.small_button("Dismiss").clicked() {
+                                    dismissed = Some(i);
+                                }
+                            }
+                        }
+
+                        if let Some(i) = dismissed {
+                            self.imports.remove(i);
+                        }
                     });
                 });
             });
 
             egui::SidePanel::left("left_panel").show(ctx, |ui| {
-                let top_level_nodes = self
+                let options = &mut self.state.gui.outliner;
+
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut options.sort_by_name, "Sort by name");
+                    ui.checkbox(&mut options.group_by_kind, "Group by kind");
+                });
+
+                let options = *options;
+
+                ui.separator();
+
+                let mut top_level_nodes = self
                     .scene
                     .graph
                     .node_references()
@@ -436,25 +1424,393 @@ impl Application for Editor {
                     .map(|(node_index, _)| node_index)
                     .collect_vec();
 
-                for (i, node) in top_level_nodes.iter().enumerate() {
-                    let mut bfs = Bfs::new(&self.scene.graph, *node);
+                if options.sort_by_name {
+                    sort_by_name(&self.scene.graph, &mut top_level_nodes);
+                }
+
+                let graph = &mut self.scene.graph;
+                let sender = &self.sender;
+                let show_nodes = |ui: &mut Ui| {
+                    for node_index in top_level_nodes {
+                        make_collapsing_header(ui, graph, node_index, options, sender);
+                    }
+                };
 
-                    ui.push_id(i, |ui| {
-                        if let Some(next) = bfs.next(&self.scene.graph) {
-                            make_collapsing_header(ui, &mut self.scene.graph, next);
-                        }
-                    });
+                if options.group_by_kind {
+                    egui::CollapsingHeader::new("Models")
+                        .default_open(true)
+                        .show(ui, show_nodes);
+
+                    egui::CollapsingHeader::new("Lights")
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            for (i, light) in self.scene.lights.iter_mut().enumerate() {
+                                if ui
+                                    .selectable_label(
+                                        light.selected,
+                                        format!("{LIGHT_ICON} Light {}", i + 1),
+                                    )
+                                    .clicked()
+                                {
+                                    light.selected = !light.selected;
+                                }
+                            }
+                        });
+
+                    egui::CollapsingHeader::new("Billboards")
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            for billboard in self.scene.billboards.iter() {
+                                ui.label(format!("{BILLBOARD_ICON} {}", billboard.name));
+                            }
+                        });
+                } else {
+                    show_nodes(ui);
                 }
+
+                ui.separator();
+
+                ui.menu_button("Add Node", |ui| {
+                    add_node_menu(ui, None, &self.sender);
+                });
             });
 
             egui::SidePanel::right("right_panel").show(ctx, |ui| {
+                ui.collapsing("Properties", |ui| {
+                    let new_property_key = &mut self.state.gui.new_property_key;
+                    let transform_space = &mut self.state.gui.transform_space;
+                    let sender = &self.sender;
+
+                    for node_index in self.scene.graph.node_indices().collect_vec() {
+                        if !self.scene.graph[node_index].selected {
+                            continue;
+                        }
+
+                        let parent_transform = self
+                            .scene
+                            .parent(node_index)
+                            .map(|parent| self.scene.graph[parent].transform.clone());
+                        let mut moved = None;
+
+                        let model_instance = &mut self.scene.graph[node_index];
+
+                        ui.push_id(node_index, |ui| {
+                            ui.strong(&model_instance.name);
+
+                            ui.horizontal(|ui| {
+                                ui.label("Transform");
+
+                                for space in TransformSpace::ALL {
+                                    ui.selectable_value(transform_space, space, space.label());
+                                }
+                            });
+
+                            let parent_transform = parent_transform
+                                .as_ref()
+                                .filter(|_| *transform_space == TransformSpace::Local);
+
+                            let mut transform = match parent_transform {
+                                Some(parent) => model_instance.transform.relative_to(parent),
+                                None => model_instance.transform.clone(),
+                            };
+
+                            let edited = ui
+                                .add_enabled_ui(!model_instance.locked, |ui| {
+                                    edit_transform(ui, &mut transform)
+                                })
+                                .inner;
+
+                            if edited {
+                                moved = Some(match parent_transform {
+                                    Some(parent) => parent.then(&transform),
+                                    None => transform,
+                                });
+                            }
+
+                            ui.separator();
+
+                            let mut removed_key = None;
+
+                            for (key, value) in model_instance.metadata.iter_mut() {
+                                ui.horizontal(|ui| {
+                                    ui.label(key);
+
+                                    match value {
+                                        MetadataValue::Bool(value) => {
+                                            ui.checkbox(value, "");
+                                        }
+                                        MetadataValue::Number(value) => {
+                                            ui.add(DragValue::new(value).speed(0.1));
+                                        }
+                                        MetadataValue::String(value) => {
+                                            ui.text_edit_singleline(value);
+                                        }
+                                    }
+
+                                    if ui.small_button("Remove").clicked() {
+                                        removed_key = Some(key.clone());
+                                    }
+                                });
+                            }
+
+                            if let Some(key) = removed_key {
+                                model_instance.metadata.remove(&key);
+                            }
+
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    TextEdit::singleline(new_property_key)
+                                        .hint_text("New property"),
+                                );
+
+                                for (label, value) in [
+                                    ("Bool", MetadataValue::Bool(false)),
+                                    ("Number", MetadataValue::Number(0.0)),
+                                    ("String", MetadataValue::String(String::new())),
+                                ] {
+                                    if ui.button(label).clicked() && !new_property_key.is_empty() {
+                                        model_instance
+                                            .metadata
+                                            .insert(std::mem::take(new_property_key), value);
+                                    }
+                                }
+                            });
+
+                            ui.separator();
+
+                            ui.horizontal(|ui| {
+                                ui.label("Material");
+
+                                if ui.small_button("Load").clicked() {
+                                    let sender = sender.clone();
+
+                                    std::thread::spawn(move || {
+                                        if let Some(path) = FileDialog::new()
+                                            .add_filter("material", &["material"])
+                                            .pick_file()
+                                        {
+                                            sender
+                                                .send(EngineEvent::LoadMaterial(node_index, path))
+                                                .unwrap();
+                                        }
+                                    });
+                                }
+
+                                if let Some(material) = model_instance.material.as_ref() {
+                                    if ui.small_button("Save").clicked() {
+                                        // Textures cannot leave the main thread, so only the JSON is sent
+                                        match material.to_asset() {
+                                            Ok(json) => {
+                                                std::thread::spawn(move || {
+                                                    if let Some(path) = FileDialog::new()
+                                                        .add_filter("material", &["material"])
+                                                        .save_file()
+                                                    {
+                                                        if let Err(err) =
+                                                            std::fs::write(&path, json)
+                                                        {
+                                                            error!(
+                                                                "Could not write {:?}: {}",
+                                                                path, err
+                                                            );
+                                                        }
+                                                    }
+                                                });
+                                            }
+                                            Err(err) => {
+                                                error!("Could not save material: {:?}", err)
+                                            }
+                                        }
+                                    }
+                                }
+                            });
+
+                            for slot in MaterialTexture::ALL {
+                                let texture = model_instance
+                                    .material
+                                    .as_ref()
+                                    .and_then(|material| slot.texture(material));
+
+                                ui.horizontal(|ui| {
+                                    ui.label(slot.label());
+                                    ui.weak(texture.map_or("Default".to_owned(), |texture| {
+                                        texture.path.to_string_lossy().into_owned()
+                                    }));
+
+                                    if ui.small_button("Change").clicked() {
+                                        let sender = sender.clone();
+
+                                        std::thread::spawn(move || {
+                                            if let Some(path) = FileDialog::new()
+                                                .add_filter("image", &["png", "jpg", "jpeg", "dds"])
+                                                .pick_file()
+                                            {
+                                                sender
+                                                    .send(EngineEvent::SetMaterialTexture(
+                                                        node_index, slot, path,
+                                                    ))
+                                                    .unwrap();
+                                            }
+                                        });
+                                    }
+                                });
+                            }
+
+                            if let Some(material) = model_instance.material.as_mut() {
+                                ui.checkbox(&mut material.transparent, "Transparent");
+
+                                if material.emissive.is_some() {
+                                    ui.horizontal(|ui| {
+                                        ui.label("Emissive strength");
+                                        ui.add(
+                                            DragValue::new(&mut material.emissive_strength.0)
+                                                .speed(0.1)
+                                                .clamp_range(0.0..=f32::MAX),
+                                        );
+                                    });
+                                }
+
+                                ComboBox::from_label("Filtering")
+                                    .selected_text(
+                                        material
+                                            .filtering
+                                            .map_or("Global".to_owned(), |filtering| {
+                                                filtering.to_string()
+                                            }),
+                                    )
+                                    .show_ui(ui, |ui| {
+                                        ui.selectable_value(
+                                            &mut material.filtering,
+                                            None,
+                                            "Global",
+                                        );
+
+                                        for filtering in TextureFiltering::ALL {
+                                            ui.selectable_value(
+                                                &mut material.filtering,
+                                                Some(filtering),
+                                                filtering.to_string(),
+                                            );
+                                        }
+                                    });
+
+                                ComboBox::from_label("Wrap")
+                                    .selected_text(material.wrap.to_string())
+                                    .show_ui(ui, |ui| {
+                                        for wrap in TextureWrap::ALL {
+                                            ui.selectable_value(
+                                                &mut material.wrap,
+                                                wrap,
+                                                wrap.to_string(),
+                                            );
+                                        }
+                                    });
+                            }
+
+                            ui.label("Levels of detail");
+
+                            let mut removed_level_of_detail = None;
+
+                            for (i, level_of_detail) in
+                                model_instance.levels_of_detail.iter_mut().enumerate()
+                            {
+                                ui.horizontal(|ui| {
+                                    ui.label(level_of_detail.model.path.to_string_lossy());
+                                    ui.add(
+                                        DragValue::new(&mut level_of_detail.distance)
+                                            .speed(0.1)
+                                            .clamp_range(0.0..=f32::MAX)
+                                            .prefix("From "),
+                                    );
+
+                                    if ui.small_button("Remove").clicked() {
+                                        removed_level_of_detail = Some(i);
+                                    }
+                                });
+                            }
+
+                            if let Some(i) = removed_level_of_detail {
+                                model_instance.levels_of_detail.remove(i);
+                            }
+
+                            if ui.button("Add level of detail").clicked() {
+                                let sender = sender.clone();
+
+                                std::thread::spawn(move || {
+                                    if let Some(path) = FileDialog::new()
+                                        .add_filter("model", &["gltf", "glb", "obj"])
+                                        .set_directory("/")
+                                        .pick_file()
+                                    {
+                                        sender
+                                            .send(EngineEvent::AddLevelOfDetail(node_index, path))
+                                            .unwrap();
+                                    }
+                                });
+                            }
+                        });
+
+                        if let Some(transform) = moved {
+                            if self.editing_transform.is_none() {
+                                self.editing_transform = Some((
+                                    node_index,
+                                    self.scene.graph[node_index].transform.clone(),
+                                ));
+                            }
+
+                            self.scene.set_transform(node_index, transform);
+                        }
+                    }
+                });
+
+                ui.collapsing("Selection sets", |ui| {
+                    let mut selected_set = None;
+                    let mut removed_set = None;
+
+                    for (i, selection_set) in self.scene.editor.selection_sets.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui.button(&selection_set.name).clicked() {
+                                selected_set = Some(i);
+                            }
+
+                            if i < SELECTION_SET_KEYS.len() {
+                                ui.weak(format!("{}", i + 1));
+                            }
+
+                            if ui.small_button("Remove").clicked() {
+                                removed_set = Some(i);
+                            }
+                        });
+                    }
+
+                    if let Some(i) = selected_set {
+                        let nodes = self.scene.editor.selection_sets[i].nodes.clone();
+                        self.scene.select_only(&nodes);
+                    }
+
+                    if let Some(i) = removed_set {
+                        self.scene.editor.selection_sets.remove(i);
+                    }
+
+                    ui.horizontal(|ui| {
+                        let name = &mut self.state.gui.new_selection_set_name;
+                        ui.add(TextEdit::singleline(name).hint_text("Name"));
+
+                        if ui.button("Save selection").clicked() && !name.is_empty() {
+                            self.scene.editor.selection_sets.push(SelectionSet {
+                                name: std::mem::take(name),
+                                nodes: self.scene.selected_nodes(),
+                            });
+                        }
+                    });
+                });
+
                 ui.collapsing("Background", |ui| {
                     ui.horizontal(|ui| {
-                        ui.selectable_value(
-                            &mut self.scene.background,
-                            Background::default(),
-                            "Color",
-                        );
+                        let is_color = matches!(self.scene.background, Background::Color(_));
+                        if ui.selectable_label(is_color, "Color").clicked() && !is_color {
+                            self.scene.background = Background::default();
+                        }
 
                         if ui.selectable_label(false, "HDRI").clicked() {
                             let sender = self.sender.clone();
@@ -471,44 +1827,1246 @@ impl Application for Editor {
                                 }
                             });
                         }
+
+                        if ui
+                            .selectable_label(false, "HDRI image")
+                            .on_hover_text("A horizontal cross or vertical strip of the six sides")
+                            .clicked()
+                        {
+                            let sender = self.sender.clone();
+
+                            std::thread::spawn(move || {
+                                if let Some(path) = FileDialog::new()
+                                    .add_filter("image", &["png", "jpg", "jpeg"])
+                                    .set_directory("/")
+                                    .pick_file()
+                                {
+                                    sender
+                                        .send(EngineEvent::ImportHDRIBackground(path))
+                                        .unwrap();
+                                }
+                            });
+                        }
                     });
+
+                    if let Background::Color(color) = &mut self.scene.background {
+                        ui.horizontal(|ui| {
+                            ui.label("Color");
+                            color_picker(
+                                ui,
+                                color,
+                                ColorTarget::Background,
+                                &mut self.state.gui.eyedropper,
+                            );
+                        });
+
+                        let mut sky_gradient = self.scene.sky_gradient.is_some();
+                        if ui.checkbox(&mut sky_gradient, "Sky gradient").changed() {
+                            self.scene.sky_gradient =
+                                sky_gradient.then(|| Color::from_named(palette::named::SKYBLUE));
+                        }
+
+                        if let Some(zenith) = self.scene.sky_gradient.as_mut() {
+                            ui.horizontal(|ui| {
+                                ui.label("Zenith");
+                                color_picker(
+                                    ui,
+                                    zenith,
+                                    ColorTarget::Zenith,
+                                    &mut self.state.gui.eyedropper,
+                                );
+                            });
+                        }
+                    }
                 });
 
-                ui.collapsing("Lighting", |ui| {
-                    ui.checkbox(&mut self.state.gui.render_lights, "Render lights");
+                ui.collapsing("Lights", |ui| {
+                    let mut removed_light = None;
+
+                    for (i, light) in self.scene.lights.iter_mut().enumerate() {
+                        ui.push_id(i, |ui| {
+                            ui.horizontal(|ui| {
+                                if ui
+                                    .selectable_label(light.selected, format!("Light {}", i + 1))
+                                    .clicked()
+                                {
+                                    light.selected = !light.selected;
+                                }
+
+                                if ui.small_button("Remove").clicked() {
+                                    removed_light = Some(i);
+                                }
+                            });
+
+                            if !light.selected {
+                                return;
+                            }
+
+                            let parent = light
+                                .parent
+                                .and_then(|parent| self.scene.graph.node_weight(parent));
+                            if let Some(parent) = parent {
+                                ui.label(format!("Follows {}", parent.name));
+                            }
+
+                            ui.horizontal(|ui| {
+                                ui.label(if parent.is_some() {
+                                    "Offset"
+                                } else {
+                                    "Position"
+                                });
+                                ui.add(DragValue::new(&mut light.position.x).speed(0.1));
+                                ui.add(DragValue::new(&mut light.position.y).speed(0.1));
+                                ui.add(DragValue::new(&mut light.position.z).speed(0.1));
+                            });
+
+                            ui.horizontal(|ui| {
+                                ui.label("Color");
+                                color_picker(
+                                    ui,
+                                    &mut light.color,
+                                    ColorTarget::Light(i),
+                                    &mut self.state.gui.eyedropper,
+                                );
+                            });
+
+                            ui.horizontal(|ui| {
+                                ui.label("Intensity");
+                                ui.add(
+                                    DragValue::new(&mut light.intensity)
+                                        .speed(0.05)
+                                        .clamp_range(0.0..=f32::MAX),
+                                );
+                            });
+
+                            ui.horizontal(|ui| {
+                                let mut limited = light.range.is_some();
+                                if ui.checkbox(&mut limited, "Range").changed() {
+                                    light.range = limited.then_some(10.0);
+                                }
+
+                                if let Some(range) = light.range.as_mut() {
+                                    ui.add(
+                                        DragValue::new(range)
+                                            .speed(0.1)
+                                            .clamp_range(0.01..=f32::MAX),
+                                    );
+                                }
+                            });
+                        });
+                    }
+
+                    if let Some(i) = removed_light {
+                        self.sender.send(EngineEvent::RemoveLight(i)).unwrap();
+                    }
                 });
-            });
-        });
-    }
-}
 
-fn make_collapsing_header(
-    ui: &mut Ui,
-    graph: &mut StableDiGraph<ModelInstance, ()>,
-    node_index: NodeIndex,
-) {
-    let model_name = graph[node_index].name.clone();
-    let children = graph
-        .neighbors_directed(node_index, Direction::Outgoing)
-        .collect_vec();
-    let id = ui.make_persistent_id(node_index);
+                ui.collapsing("Billboards", |ui| {
+                    let mut removed_billboard = None;
 
-    if children.is_empty() {
-        ui.indent(id, |ui| {
-            if ui.selectable_label(false, model_name).clicked() {
-                graph[node_index].selected = !graph[node_index].selected;
-            }
-        });
-    } else {
-        egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, false)
-            .show_header(ui, |ui| {
-                if ui.selectable_label(false, model_name).clicked() {
-                    graph[node_index].selected = !graph[node_index].selected;
-                }
-            })
+                    for (i, billboard) in self.scene.billboards.iter_mut().enumerate() {
+                        ui.push_id(i, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.strong(&billboard.name);
+
+                                if ui.small_button("Remove").clicked() {
+                                    removed_billboard = Some(i);
+                                }
+                            });
+
+                            ui.horizontal(|ui| {
+                                ui.label("Offset");
+                                ui.add(DragValue::new(&mut billboard.offset.x).speed(0.1));
+                                ui.add(DragValue::new(&mut billboard.offset.y).speed(0.1));
+                                ui.add(DragValue::new(&mut billboard.offset.z).speed(0.1));
+                            });
+
+                            ui.horizontal(|ui| {
+                                ui.label("Size");
+                                for size in billboard.size.iter_mut() {
+                                    ui.add(
+                                        DragValue::new(size)
+                                            .speed(0.05)
+                                            .clamp_range(0.0..=f32::MAX),
+                                    );
+                                }
+                            });
+                        });
+                    }
+
+                    if let Some(i) = removed_billboard {
+                        self.scene.billboards.remove(i);
+                        collect_garbage();
+                    }
+                });
+
+                ui.collapsing("Fog", |ui| {
+                    let fog = &mut self.scene.fog;
+
+                    ComboBox::from_label("Falloff")
+                        .selected_text(fog.falloff.to_string())
+                        .show_ui(ui, |ui| {
+                            for falloff in FogFalloff::ALL {
+                                ui.selectable_value(&mut fog.falloff, falloff, falloff.to_string());
+                            }
+                        });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Color");
+                        color_picker(
+                            ui,
+                            &mut fog.color,
+                            ColorTarget::Fog,
+                            &mut self.state.gui.eyedropper,
+                        );
+                    });
+
+                    match fog.falloff {
+                        FogFalloff::None => (),
+                        FogFalloff::Linear => {
+                            ui.horizontal(|ui| {
+                                ui.label("Start");
+                                ui.add(
+                                    DragValue::new(&mut fog.start)
+                                        .speed(0.1)
+                                        .clamp_range(0.0..=fog.end),
+                                );
+                            });
+
+                            ui.horizontal(|ui| {
+                                ui.label("End");
+                                ui.add(
+                                    DragValue::new(&mut fog.end)
+                                        .speed(0.1)
+                                        .clamp_range(fog.start..=f32::MAX),
+                                );
+                            });
+                        }
+                        FogFalloff::Exponential => {
+                            ui.horizontal(|ui| {
+                                ui.label("Density");
+                                ui.add(
+                                    DragValue::new(&mut fog.density)
+                                        .speed(0.001)
+                                        .clamp_range(0.0..=1.0),
+                                );
+                            });
+                        }
+                    }
+                });
+
+                ui.collapsing("Camera", |ui| {
+                    let camera = &mut self.viewports[self.active_viewport].camera;
+
+                    ui.horizontal(|ui| {
+                        ui.label("Projection (Numpad 5)");
+
+                        for (projection_mode, label) in [
+                            (Projection::Perspective, "Perspective"),
+                            (Projection::Orthographic, "Orthographic"),
+                        ] {
+                            if ui
+                                .selectable_label(
+                                    camera.projection_mode() == projection_mode,
+                                    label,
+                                )
+                                .clicked()
+                            {
+                                camera.set_projection_mode(projection_mode);
+                            }
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        for (axis, label) in [
+                            (ViewAxis::Top, "Top (Numpad 7)"),
+                            (ViewAxis::Front, "Front (Numpad 1)"),
+                            (ViewAxis::Right, "Right (Numpad 3)"),
+                        ] {
+                            if ui.button(label).clicked() {
+                                camera.look_along(axis);
+                            }
+                        }
+                    });
+
+                    let mut fov = camera.fov().to_degrees();
+                    ui.horizontal(|ui| {
+                        ui.label("Field of view");
+                        if ui
+                            .add(
+                                DragValue::new(&mut fov)
+                                    .speed(0.5)
+                                    .clamp_range(30.0..=120.0),
+                            )
+                            .changed()
+                        {
+                            camera.set_fov(fov.to_radians());
+                        }
+                    });
+
+                    let camera_settings = &mut self.scene.camera_settings;
+
+                    ui.horizontal(|ui| {
+                        ui.label("Near plane");
+                        ui.add(
+                            DragValue::new(&mut camera_settings.near)
+                                .speed(0.01)
+                                .clamp_range(0.001..=camera_settings.far),
+                        );
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Far plane");
+                        ui.add(
+                            DragValue::new(&mut camera_settings.far)
+                                .speed(1.0)
+                                .clamp_range(camera_settings.near..=f32::MAX),
+                        );
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Sky distance");
+                        ui.add(
+                            DragValue::new(&mut camera_settings.sky_distance)
+                                .speed(0.1)
+                                .clamp_range(0.001..=f32::MAX),
+                        );
+                    });
+
+                    if camera_settings.sky_clipped() {
+                        ui.colored_label(
+                            Color32::YELLOW,
+                            "The skybox lies outside of the near and far planes",
+                        );
+                    }
+
+                    for (node_index, clipping) in
+                        self.scene.nodes_beyond_far_plane(camera.position())
+                    {
+                        let name = &self.scene.graph[node_index].name;
+
+                        ui.colored_label(
+                            Color32::YELLOW,
+                            match clipping {
+                                FarPlaneClipping::Partly => {
+                                    format!("\"{}\" is partly cut off by the far plane", name)
+                                }
+                                FarPlaneClipping::Entirely => {
+                                    format!(
+                                        "\"{}\" is beyond the far plane and will not be drawn",
+                                        name
+                                    )
+                                }
+                            },
+                        );
+                    }
+                });
+
+                ui.collapsing("Post processing", |ui| {
+                    let post_processing = &mut self.scene.post_processing;
+
+                    ui.horizontal(|ui| {
+                        ui.label("Bloom threshold");
+                        ui.add(
+                            DragValue::new(&mut post_processing.bloom_threshold)
+                                .speed(0.01)
+                                .clamp_range(0.0..=f32::MAX),
+                        );
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Bloom intensity");
+                        ui.add(
+                            DragValue::new(&mut post_processing.bloom_intensity)
+                                .speed(0.01)
+                                .clamp_range(0.0..=f32::MAX),
+                        );
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Exposure");
+                        ui.add(
+                            DragValue::new(&mut post_processing.exposure)
+                                .speed(0.01)
+                                .clamp_range(0.0..=f32::MAX),
+                        );
+                    });
+
+                    ComboBox::from_label("Tonemapping")
+                        .selected_text(post_processing.tonemapping.to_string())
+                        .show_ui(ui, |ui| {
+                            for tonemapping in Tonemapping::ALL {
+                                ui.selectable_value(
+                                    &mut post_processing.tonemapping,
+                                    tonemapping,
+                                    tonemapping.to_string(),
+                                );
+                            }
+                        });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Gamma");
+                        ui.add(
+                            DragValue::new(&mut post_processing.gamma)
+                                .speed(0.01)
+                                .clamp_range(0.1..=5.0),
+                        );
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Vignette");
+                        ui.add(
+                            DragValue::new(&mut post_processing.vignette)
+                                .speed(0.01)
+                                .clamp_range(0.0..=1.0),
+                        );
+                    });
+                });
+
+                ui.collapsing("Lighting", |ui| {
+                    ui.checkbox(
+                        &mut self.viewports[self.active_viewport].render_lights,
+                        "Render lights",
+                    );
+                });
+
+                ui.collapsing("Rendering", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Preset");
+
+                        for preset in GraphicsPreset::ALL {
+                            if ui.button(preset.to_string()).clicked() {
+                                // Presets are about performance, so accessibility settings are kept
+                                self.renderer.settings = RenderSettings {
+                                    colorblind_filter: self.renderer.settings.colorblind_filter,
+                                    ..RenderSettings::preset(preset)
+                                };
+                            }
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Shading (Z)");
+                        ui.selectable_value(
+                            &mut self.renderer.settings.shading,
+                            Shading::Lit,
+                            "Lit",
+                        );
+                        ui.selectable_value(
+                            &mut self.renderer.settings.shading,
+                            Shading::Unlit,
+                            "Unlit",
+                        );
+                        ui.selectable_value(
+                            &mut self.renderer.settings.shading,
+                            Shading::Vertex,
+                            "Per vertex",
+                        );
+                    });
+
+                    ui.checkbox(&mut self.renderer.settings.depth_prepass, "Depth pre-pass");
+                    ui.checkbox(&mut self.renderer.settings.srgb, "sRGB correct lighting");
+                    ui.checkbox(
+                        &mut self.renderer.settings.post_processing,
+                        "Post-processing",
+                    );
+
+                    ComboBox::from_label("Colorblind filter")
+                        .selected_text(self.renderer.settings.colorblind_filter.to_string())
+                        .show_ui(ui, |ui| {
+                            for colorblind_filter in ColorblindFilter::ALL {
+                                ui.selectable_value(
+                                    &mut self.renderer.settings.colorblind_filter,
+                                    colorblind_filter,
+                                    colorblind_filter.to_string(),
+                                );
+                            }
+                        });
+
+                    ComboBox::from_label("Anti-aliasing")
+                        .selected_text(self.renderer.settings.anti_aliasing.to_string())
+                        .show_ui(ui, |ui| {
+                            for anti_aliasing in AntiAliasing::ALL {
+                                ui.selectable_value(
+                                    &mut self.renderer.settings.anti_aliasing,
+                                    anti_aliasing,
+                                    anti_aliasing.to_string(),
+                                );
+                            }
+                        });
+
+                    ComboBox::from_label("Texture filtering")
+                        .selected_text(self.renderer.settings.filtering.to_string())
+                        .show_ui(ui, |ui| {
+                            for filtering in TextureFiltering::ALL {
+                                ui.selectable_value(
+                                    &mut self.renderer.settings.filtering,
+                                    filtering,
+                                    filtering.to_string(),
+                                );
+                            }
+                        });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Anisotropy");
+                        ui.add(
+                            DragValue::new(&mut self.renderer.settings.max_anisotropy)
+                                .clamp_range(1..=16),
+                        );
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Texture LOD bias");
+                        ui.add(
+                            DragValue::new(&mut self.renderer.settings.texture_lod_bias)
+                                .speed(0.1)
+                                .clamp_range(0.0..=4.0),
+                        );
+                    });
+                });
+
+                ui.collapsing("Statistics", |ui| {
+                    ui.checkbox(&mut self.renderer.settings.gpu_timing, "GPU timing");
+
+                    let stats = &self.renderer.stats;
+
+                    Grid::new("pass_timings").show(ui, |ui| {
+                        ui.label("Pass");
+                        ui.label("CPU");
+                        ui.label("GPU");
+                        ui.end_row();
+
+                        for (pass, cpu, gpu) in [
+                            (
+                                "Depth pre-pass",
+                                stats.cpu.depth_prepass,
+                                stats.gpu.depth_prepass,
+                            ),
+                            ("Color pass", stats.cpu.color_pass, stats.gpu.color_pass),
+                            ("Lines", stats.cpu.lines, stats.gpu.lines),
+                            (
+                                "Post-processing",
+                                stats.cpu.post_process,
+                                stats.gpu.post_process,
+                            ),
+                        ] {
+                            ui.label(pass);
+                            ui.label(format!("{:.2} ms", cpu.as_secs_f64() * 1000.0));
+                            if self.renderer.settings.gpu_timing {
+                                ui.label(format!("{:.2} ms", gpu.as_secs_f64() * 1000.0));
+                            } else {
+                                ui.label("-");
+                            }
+                            ui.end_row();
+                        }
+                    });
+
+                    ui.label(format!("Draw calls: {}", stats.draw_calls));
+                    ui.label(format!("Instances: {}", stats.instances));
+                    ui.label(format!("Triangles: {}", stats.triangles));
+
+                    ui.label("Frame profile");
+                    show_profile(ui, self.profiler.last_frame());
+                });
+
+                ui.collapsing("Comparison", |ui| {
+                    if ui.button("Capture viewport").clicked() {
+                        self.sender.send(EngineEvent::CaptureComparison).unwrap();
+                    }
+
+                    if let Some(comparison) = self.comparison.as_mut() {
+                        ui.checkbox(&mut comparison.visible, "Show");
+                        ui.add(Slider::new(&mut comparison.split, 0.0..=1.0).text("Split"));
+                    }
+                });
+
+                ui.collapsing("Stress test", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Copies");
+                        ui.add(
+                            DragValue::new(&mut self.state.gui.stress_test_count)
+                                .speed(100)
+                                .clamp_range(1..=100_000),
+                        );
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Spacing");
+                        ui.add(
+                            DragValue::new(&mut self.state.gui.stress_test_spacing)
+                                .speed(0.1)
+                                .clamp_range(0.0..=f32::MAX),
+                        );
+                    });
+
+                    if ui.button("Scatter selected").clicked() {
+                        self.sender.send(EngineEvent::ScatterSelected).unwrap();
+                    }
+
+                    ui.label(format!("Nodes: {}", self.scene.graph.node_count()));
+                    ui.label(format!(
+                        "Frame time: {:.2} ms ({:.0} FPS)",
+                        self.state.deltatime * 1000.0,
+                        self.state.fps
+                    ));
+                });
+            });
+
+            let mut viewport_hovered = false;
+
+            // Added last so that it fills the space left between the other panels
+            egui::CentralPanel::default()
+                .frame(egui::Frame::none())
+                .show(ctx, |ui| {
+                    let viewport_count = self.state.gui.viewport_count;
+                    let columns = if viewport_count == 1 { 1 } else { 2 };
+                    let rows = viewport_count.div_ceil(columns);
+
+                    ui.spacing_mut().item_spacing = Vec2::ZERO;
+
+                    // Every viewport is the same size so that they can share offscreen targets
+                    let size = ui.available_size() / Vec2::new(columns as f32, rows as f32);
+                    let pixels = size * ctx.pixels_per_point();
+                    self.state.viewport_dimensions = (pixels.x as u32, pixels.y as u32);
+
+                    for row in 0..rows {
+                        ui.horizontal(|ui| {
+                            for i in
+                                (row * columns..(row + 1) * columns).filter(|i| *i < viewport_count)
+                            {
+                                // Textures are stored bottom row first, whereas egui expects the top row first
+                                let mut response = ui.add(
+                                    Image::new((self.viewports[i].texture_id, size))
+                                        .uv(Rect::from_min_max(pos2(0.0, 1.0), pos2(1.0, 0.0)))
+                                        .sense(Sense::click_and_drag()),
+                                );
+
+                                if let Some(target) = self.state.gui.eyedropper {
+                                    response = response.on_hover_cursor(CursorIcon::Crosshair);
+
+                                    if let Some(position) = response
+                                        .interact_pointer_pos()
+                                        .filter(|_| response.clicked())
+                                    {
+                                        let uv =
+                                            (position - response.rect.min) / response.rect.size();
+                                        let picked = self.viewports[i].read_pixel((uv.x, uv.y));
+
+                                        if let Some(color) = target.color(&mut self.scene) {
+                                            *color = Color::from_color(picked.into_format::<f32>());
+                                        }
+
+                                        self.state.gui.eyedropper = None;
+                                    }
+                                } else {
+                                    let selection_mode = SelectionMode::from_modifiers(
+                                        ui.input(|input| input.modifiers),
+                                    );
+                                    let camera = &self.viewports[i].camera;
+                                    let view_projection = camera.projection() * camera.view();
+
+                                    // As a fraction of the image's size, from the top left
+                                    let to_uv = |position: Pos2| {
+                                        let uv =
+                                            (position - response.rect.min) / response.rect.size();
+                                        (uv.x, uv.y)
+                                    };
+
+                                    if response.drag_started_by(PointerButton::Primary) {
+                                        self.state.gui.marquee = response
+                                            .interact_pointer_pos()
+                                            .map(|position| (i, position));
+                                    }
+
+                                    if let Some(((_, start), end)) = self
+                                        .state
+                                        .gui
+                                        .marquee
+                                        .filter(|(viewport, _)| *viewport == i)
+                                        .zip(response.interact_pointer_pos())
+                                    {
+                                        let marquee = Rect::from_two_pos(start, end);
+
+                                        ui.painter().rect(
+                                            marquee,
+                                            0.0,
+                                            Color32::from_rgba_unmultiplied(100, 150, 255, 40),
+                                            Stroke::new(1.0, Color32::LIGHT_BLUE),
+                                        );
+
+                                        if response.drag_released() {
+                                            let nodes = self.scene.nodes_in_screen_rect(
+                                                &view_projection,
+                                                to_uv(marquee.min),
+                                                to_uv(marquee.max),
+                                            );
+                                            let lights = self.scene.lights_in_screen_rect(
+                                                &view_projection,
+                                                to_uv(marquee.min),
+                                                to_uv(marquee.max),
+                                            );
+
+                                            selection_mode.apply(&mut self.scene.graph, &nodes);
+                                            selection_mode
+                                                .apply_to_lights(&mut self.scene.lights, &lights);
+                                            self.state.gui.marquee = None;
+                                        }
+                                    }
+
+                                    if let Some(position) = response
+                                        .interact_pointer_pos()
+                                        .filter(|_| response.clicked())
+                                    {
+                                        if let Some(ray) =
+                                            Ray::from_screen(to_uv(position), &view_projection)
+                                        {
+                                            // Lights are often inside the bounds of a model, so are picked first
+                                            let (width, height) = self.viewports[i].dimensions();
+                                            let icon_radius = LIGHT_ICON_SIZE / 2.0;
+                                            let light = self.scene.light_at(
+                                                &view_projection,
+                                                to_uv(position),
+                                                (
+                                                    icon_radius / width as f32,
+                                                    icon_radius / height as f32,
+                                                ),
+                                            );
+                                            let hit = match light {
+                                                Some(_) => None,
+                                                None => self.scene.raycast(&ray),
+                                            };
+
+                                            // Clicking empty space clears the selection, unless adding or removing
+                                            selection_mode
+                                                .apply(&mut self.scene.graph, hit.as_slice());
+                                            selection_mode.apply_to_lights(
+                                                &mut self.scene.lights,
+                                                light.as_slice(),
+                                            );
+                                        }
+                                    }
+                                }
+
+                                response = response.context_menu(|ui| {
+                                    let selected = self.scene.selected_roots();
+
+                                    if ui
+                                        .add_enabled(!selected.is_empty(), Button::new("Duplicate"))
+                                        .clicked()
+                                    {
+                                        self.sender
+                                            .send(EngineEvent::Duplicate(selected.clone()))
+                                            .unwrap();
+                                        ui.close_menu();
+                                    }
+
+                                    if ui
+                                        .add_enabled(!selected.is_empty(), Button::new("Copy"))
+                                        .clicked()
+                                    {
+                                        self.sender.send(EngineEvent::Copy(selected)).unwrap();
+                                        ui.close_menu();
+                                    }
+
+                                    if ui
+                                        .add_enabled(
+                                            !self.clipboard.is_empty(),
+                                            Button::new("Paste"),
+                                        )
+                                        .clicked()
+                                    {
+                                        self.sender.send(EngineEvent::Paste(None)).unwrap();
+                                        ui.close_menu();
+                                    }
+
+                                    ui.separator();
+
+                                    let selected = self.scene.selected_nodes();
+                                    if ui
+                                        .add_enabled(
+                                            !selected.is_empty(),
+                                            Button::new("Merge static geometry"),
+                                        )
+                                        .on_hover_text(
+                                            "Bake the selected nodes into one model per material, \
+                                             drawn with a single call each",
+                                        )
+                                        .clicked()
+                                    {
+                                        self.sender
+                                            .send(EngineEvent::MergeStaticGeometry(selected))
+                                            .unwrap();
+                                        ui.close_menu();
+                                    }
+                                });
+
+                                if response.hovered() && !self.state.is_moving_camera {
+                                    self.active_viewport = i;
+                                    viewport_hovered = true;
+                                }
+
+                                if let Some(comparison) =
+                                    self.comparison.as_mut().filter(|comparison| {
+                                        comparison.visible && comparison.viewport == i
+                                    })
+                                {
+                                    show_comparison(ui, comparison, response.rect);
+                                }
+
+                                if viewport_count > 1 && i == self.active_viewport {
+                                    ui.painter().rect_stroke(
+                                        response.rect,
+                                        0.0,
+                                        Stroke::new(1.0, Color32::LIGHT_BLUE),
+                                    );
+                                }
+                            }
+                        });
+                    }
+                });
+
+            let held =
+                ctx.input(|input| input.key_down(RADIAL_MENU_KEY)) && !ctx.wants_keyboard_input();
+            let pointer = ctx.pointer_latest_pos();
+
+            match (self.state.gui.radial_menu, held) {
+                (None, true) if viewport_hovered => self.state.gui.radial_menu = pointer,
+                (Some(center), true) => {
+                    let choice = pointer.and_then(|pointer| radial_menu_choice(center, pointer));
+                    show_radial_menu(ctx, center, choice);
+                }
+                (Some(center), false) => {
+                    self.state.gui.radial_menu = None;
+
+                    match pointer.and_then(|pointer| radial_menu_choice(center, pointer)) {
+                        Some(RadialAction::View(axis)) => {
+                            self.viewports[self.active_viewport].camera.look_along(axis)
+                        }
+                        Some(RadialAction::ToggleProjection) => {
+                            let camera = &mut self.viewports[self.active_viewport].camera;
+                            camera.set_projection_mode(match camera.projection_mode() {
+                                Projection::Perspective => Projection::Orthographic,
+                                Projection::Orthographic => Projection::Perspective,
+                            });
+                        }
+                        Some(RadialAction::ToggleGrid) => {
+                            self.state.gui.show_grid = !self.state.gui.show_grid
+                        }
+                        Some(RadialAction::ToggleShading) => {
+                            self.renderer.settings.shading =
+                                self.renderer.settings.shading.toggled()
+                        }
+                        Some(RadialAction::Add(shape)) => {
+                            self.sender
+                                .send(EngineEvent::AddPrimitive(None, shape))
+                                .unwrap();
+                        }
+                        None => (),
+                    }
+                }
+                _ => (),
+            }
+        });
+
+        // A drag or a typed value is finished once the field is let go of
+        let ctx = &self.gui.egui_ctx;
+        if !ctx.is_using_pointer() && !ctx.wants_keyboard_input() {
+            self.finish_transform_edit();
+        }
+    }
+}
+
+/// The tool in the radial menu pointed at from `center`, once the pointer has moved far enough to choose one
+fn radial_menu_choice(center: Pos2, pointer: Pos2) -> Option<RadialAction> {
+    let offset = pointer - center;
+    if offset.length() < RADIAL_MENU_DEAD_ZONE {
+        return None;
+    }
+
+    // Clockwise from the top, as y points down the screen
+    let angle = offset.x.atan2(-offset.y).rem_euclid(TAU);
+    let step = TAU / RADIAL_MENU.len() as f32;
+
+    Some(RADIAL_MENU[(angle / step).round() as usize % RADIAL_MENU.len()])
+}
+
+fn show_radial_menu(ctx: &egui::Context, center: Pos2, choice: Option<RadialAction>) {
+    let painter = ctx.layer_painter(LayerId::new(Order::Foreground, Id::new("radial_menu")));
+    let step = TAU / RADIAL_MENU.len() as f32;
+
+    painter.circle_stroke(
+        center,
+        RADIAL_MENU_DEAD_ZONE,
+        Stroke::new(2.0, Color32::LIGHT_BLUE),
+    );
+
+    for (i, action) in RADIAL_MENU.iter().enumerate() {
+        let angle = i as f32 * step;
+        let position = center + RADIAL_MENU_RADIUS * Vec2::new(angle.sin(), -angle.cos());
+        let chosen = choice == Some(*action);
+
+        let galley = painter.layout_no_wrap(
+            action.label().to_owned(),
+            FontId::proportional(14.0),
+            Color32::WHITE,
+        );
+        let rect = Rect::from_center_size(position, galley.size()).expand(6.0);
+
+        painter.rect_filled(
+            rect,
+            4.0,
+            if chosen {
+                Color32::from_rgb(60, 110, 180)
+            } else {
+                Color32::from_black_alpha(200)
+            },
+        );
+        painter.galley(rect.min + Vec2::splat(6.0), galley, Color32::WHITE);
+    }
+}
+
+/// Draw the captured image over the left of `rect`, split from the live viewport by a draggable line
+fn show_comparison(ui: &mut Ui, comparison: &mut Comparison, rect: Rect) {
+    let handle = Rect::from_center_size(
+        pos2(
+            rect.left() + rect.width() * comparison.split,
+            rect.center().y,
+        ),
+        Vec2::new(8.0, rect.height()),
+    );
+    let response = ui
+        .interact(handle, ui.id().with("comparison_split"), Sense::drag())
+        .on_hover_cursor(CursorIcon::ResizeHorizontal);
+
+    comparison.split = (comparison.split + response.drag_delta().x / rect.width()).clamp(0.0, 1.0);
+
+    let split_x = rect.left() + rect.width() * comparison.split;
+    let captured = Rect::from_min_max(rect.min, pos2(split_x, rect.max.y));
+
+    // Textures are stored bottom row first, whereas egui expects the top row first
+    ui.painter().image(
+        comparison.texture_id,
+        captured,
+        Rect::from_min_max(pos2(0.0, 1.0), pos2(comparison.split, 0.0)),
+        Color32::WHITE,
+    );
+    ui.painter()
+        .vline(split_x, rect.y_range(), Stroke::new(2.0, Color32::WHITE));
+}
+
+/// Edit a color as sRGB, with a hex code and an eyedropper which picks `target` from the next viewport clicked
+fn color_picker(
+    ui: &mut Ui,
+    color: &mut Color,
+    target: ColorTarget,
+    eyedropper: &mut Option<ColorTarget>,
+) {
+    let srgb: Srgb = (*color).into_color();
+    let mut rgb = [srgb.red, srgb.green, srgb.blue];
+
+    if ui.color_edit_button_rgb(&mut rgb).changed() {
+        *color = Color::from_color(Srgb::new(rgb[0], rgb[1], rgb[2]));
+    }
+
+    // Keep what is being typed until it is a valid color, rather than resetting it every frame
+    let hex_id = ui.next_auto_id();
+    let mut hex = ui
+        .data_mut(|data| data.get_temp::<String>(hex_id))
+        .unwrap_or_else(|| {
+            let srgb = srgb.into_format::<u8>();
+            format!("#{:02x}{:02x}{:02x}", srgb.red, srgb.green, srgb.blue)
+        });
+
+    let response = ui.add(TextEdit::singleline(&mut hex).desired_width(64.0));
+
+    if response.has_focus() {
+        if let Ok(parsed) = hex.trim().parse::<Srgb<u8>>() {
+            *color = Color::from_color(parsed.into_format::<f32>());
+        }
+
+        ui.data_mut(|data| data.insert_temp(hex_id, hex));
+    } else {
+        ui.data_mut(|data| data.remove::<String>(hex_id));
+    }
+
+    let picking = *eyedropper == Some(target);
+    if ui
+        .selectable_label(picking, "Pick")
+        .on_hover_text("Pick the color from a viewport")
+        .clicked()
+    {
+        *eyedropper = if picking { None } else { Some(target) };
+    }
+}
+
+/// Draw the scopes of a frame as a flame graph, with time along x and nested scopes below the scope containing them
+fn show_profile(ui: &mut Ui, scopes: &[Scope]) {
+    const ROW_HEIGHT: f32 = 18.0;
+
+    let total = scopes
+        .iter()
+        .filter(|scope| scope.parent.is_none())
+        .map(|scope| scope.duration)
+        .sum::<Duration>()
+        .as_secs_f32();
+
+    if total == 0.0 {
+        return;
+    }
+
+    let mut depths = Vec::with_capacity(scopes.len());
+    for scope in scopes {
+        let depth = scope.parent.map_or(0, |parent| depths[parent] + 1);
+        depths.push(depth);
+    }
+
+    let rows = depths.iter().max().map_or(0, |depth| depth + 1);
+    let (response, painter) = ui.allocate_painter(
+        Vec2::new(ui.available_width(), rows as f32 * ROW_HEIGHT),
+        Sense::hover(),
+    );
+
+    // Where the next child of each scope starts, in seconds from the start of the frame
+    let mut next_child = vec![0.0; scopes.len()];
+    let mut next_root = 0.0;
+    let mut hovered = None;
+
+    for (i, scope) in scopes.iter().enumerate() {
+        let duration = scope.duration.as_secs_f32();
+        let cursor = match scope.parent {
+            Some(parent) => &mut next_child[parent],
+            None => &mut next_root,
+        };
+        let start = *cursor;
+        *cursor += duration;
+        next_child[i] = start;
+
+        let block = Rect::from_min_size(
+            pos2(
+                response.rect.left() + start / total * response.rect.width(),
+                response.rect.top() + depths[i] as f32 * ROW_HEIGHT,
+            ),
+            Vec2::new(duration / total * response.rect.width(), ROW_HEIGHT),
+        );
+
+        painter.rect(
+            block,
+            0.0,
+            Color32::from_rgb(230, 90 + (depths[i] * 40 % 140) as u8, 50),
+            Stroke::new(1.0, Color32::BLACK),
+        );
+        painter.with_clip_rect(block.shrink(2.0)).text(
+            block.left_center() + Vec2::new(3.0, 0.0),
+            Align2::LEFT_CENTER,
+            scope.name,
+            FontId::proportional(12.0),
+            Color32::BLACK,
+        );
+
+        if response.hover_pos().is_some_and(|pos| block.contains(pos)) {
+            hovered = Some(scope);
+        }
+    }
+
+    if let Some(scope) = hovered {
+        response.on_hover_text(format!(
+            "{}: {:.2} ms over {} calls",
+            scope.name,
+            scope.duration.as_secs_f64() * 1000.0,
+            scope.calls
+        ));
+    }
+}
+
+/// Edit a transform, with the rotation shown as Euler angles in degrees. Returns whether it was changed
+fn edit_transform(ui: &mut Ui, transform: &mut Transform) -> bool {
+    let euler = Euler::from(transform.rotation);
+    let mut angles = [Deg::from(euler.x), Deg::from(euler.y), Deg::from(euler.z)];
+
+    let mut changed = false;
+    let mut rotated = false;
+
+    ui.horizontal(|ui| {
+        ui.label("Position");
+
+        for axis in 0..3 {
+            changed |= ui
+                .add(DragValue::new(&mut transform.translation[axis]).speed(0.1))
+                .changed();
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Rotation");
+
+        for angle in angles.iter_mut() {
+            rotated |= ui
+                .add(DragValue::new(&mut angle.0).speed(1.0).suffix("°"))
+                .changed();
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Scale");
+
+        changed |= ui
+            .add(
+                DragValue::new(&mut transform.scale)
+                    .speed(0.01)
+                    .clamp_range(0.001..=f32::MAX),
+            )
+            .changed();
+    });
+
+    // Only rebuild the rotation when it is edited, as the round trip through Euler angles is lossy
+    if rotated {
+        transform.rotation = Quaternion::from(Euler::new(angles[0], angles[1], angles[2]));
+    }
+
+    changed || rotated
+}
+
+/// Free the models and textures which the scene no longer uses
+fn collect_garbage() {
+    // Models hold on to their normal maps, so must be freed first
+    let models = Model::collect_garbage();
+    let textures = Texture2D::collect_garbage();
+
+    if models + textures > 0 {
+        info!("Freed {} models and {} textures", models, textures);
+    }
+}
+
+/// Buttons which add a node as a child of `parent`, or at the top level
+fn add_node_menu(ui: &mut Ui, parent: Option<NodeIndex>, sender: &Sender<EngineEvent>) {
+    for shape in PrimitiveShape::ALL {
+        if ui.button(shape.label()).clicked() {
+            sender
+                .send(EngineEvent::AddPrimitive(parent, shape))
+                .unwrap();
+            ui.close_menu();
+        }
+    }
+
+    if ui.button("Light").clicked() {
+        sender.send(EngineEvent::AddLight(parent)).unwrap();
+        ui.close_menu();
+    }
+}
+
+/// The right click menu of a node in the scene tree
+fn node_context_menu(ui: &mut Ui, node_index: NodeIndex, sender: &Sender<EngineEvent>) {
+    ui.menu_button("Add Node", |ui| {
+        add_node_menu(ui, Some(node_index), sender);
+    });
+
+    if ui.button("Duplicate").clicked() {
+        sender
+            .send(EngineEvent::Duplicate(vec![node_index]))
+            .unwrap();
+        ui.close_menu();
+    }
+
+    if ui.button("Copy").clicked() {
+        sender.send(EngineEvent::Copy(vec![node_index])).unwrap();
+        ui.close_menu();
+    }
+
+    if ui.button("Paste as child").clicked() {
+        sender.send(EngineEvent::Paste(Some(node_index))).unwrap();
+        ui.close_menu();
+    }
+
+    if ui.button("Delete").clicked() {
+        sender
+            .send(EngineEvent::RemoveNode(node_index, true))
+            .unwrap();
+        ui.close_menu();
+    }
+
+    if ui.button("Delete with children").clicked() {
+        sender
+            .send(EngineEvent::RemoveNode(node_index, false))
+            .unwrap();
+        ui.close_menu();
+    }
+}
+
+fn sort_by_name(graph: &StableDiGraph<ModelInstance, ()>, nodes: &mut [NodeIndex]) {
+    nodes.sort_by(|a, b| {
+        graph[*a]
+            .name
+            .to_lowercase()
+            .cmp(&graph[*b].name.to_lowercase())
+    });
+}
+
+/// A node's row in the scene tree, with its icon, name, and visibility and lock toggles
+fn node_row(
+    ui: &mut Ui,
+    graph: &mut StableDiGraph<ModelInstance, ()>,
+    node_index: NodeIndex,
+    has_children: bool,
+    sender: &Sender<EngineEvent>,
+) {
+    let model_instance = &mut graph[node_index];
+    let icon = if has_children { GROUP_ICON } else { MODEL_ICON };
+
+    let response = ui.selectable_label(
+        model_instance.selected,
+        format!("{icon} {}", model_instance.name),
+    );
+
+    if ui
+        .selectable_label(!model_instance.hidden, VISIBLE_ICON)
+        .on_hover_text("Visible")
+        .clicked()
+    {
+        model_instance.hidden = !model_instance.hidden;
+    }
+
+    if ui
+        .selectable_label(model_instance.locked, LOCKED_ICON)
+        .on_hover_text("Locked")
+        .clicked()
+    {
+        model_instance.locked = !model_instance.locked;
+    }
+
+    if response.clicked() {
+        SelectionMode::from_modifiers(ui.input(|input| input.modifiers))
+            .apply(graph, &[node_index]);
+    }
+
+    response.context_menu(|ui| node_context_menu(ui, node_index, sender));
+}
+
+fn make_collapsing_header(
+    ui: &mut Ui,
+    graph: &mut StableDiGraph<ModelInstance, ()>,
+    node_index: NodeIndex,
+    options: OutlinerOptions,
+    sender: &Sender<EngineEvent>,
+) {
+    let mut children = graph
+        .neighbors_directed(node_index, Direction::Outgoing)
+        .collect_vec();
+    let id = ui.make_persistent_id(node_index);
+
+    if options.sort_by_name {
+        sort_by_name(graph, &mut children);
+    }
+
+    if children.is_empty() {
+        ui.indent(id, |ui| {
+            ui.horizontal(|ui| node_row(ui, graph, node_index, false, sender));
+        });
+    } else {
+        egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, false)
+            .show_header(ui, |ui| node_row(ui, graph, node_index, true, sender))
             .body(|ui| {
                 for child in children.into_iter() {
-                    make_collapsing_header(ui, graph, child);
+                    make_collapsing_header(ui, graph, child, options, sender);
                 }
             });
     }
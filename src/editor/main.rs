@@ -3,7 +3,9 @@ use winit::event_loop::EventLoop;
 use common::app::Application;
 use editor::Editor;
 
+mod command;
 mod editor;
+mod viewport;
 
 fn main() {
     // Winit is dodgey on Wayland, prefer to use Xwayland
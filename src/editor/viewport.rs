@@ -0,0 +1,147 @@
+use color_eyre::Result;
+use common::camera::OrbitalCamera;
+use egui_glium::egui_winit::egui::{TextureId, TextureOptions};
+use egui_glium::Painter;
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::glutin::surface::WindowSurface;
+use glium::texture::SrgbTexture2d;
+use glium::uniforms::MagnifySamplerFilter;
+use glium::{BlitTarget, Display, Surface};
+use palette::Srgb;
+use std::rc::Rc;
+
+/// An offscreen texture which the scene is drawn into, and which egui shows as an image
+pub struct Viewport {
+    texture: Rc<SrgbTexture2d>,
+    pub texture_id: TextureId,
+    pub camera: OrbitalCamera,
+    pub render_lights: bool,
+}
+
+impl Viewport {
+    pub fn new(
+        dimensions: (u32, u32),
+        display: &Display<WindowSurface>,
+        painter: &mut Painter,
+    ) -> Result<Self> {
+        let texture = Rc::new(SrgbTexture2d::empty(display, dimensions.0, dimensions.1)?);
+        let texture_id = painter.register_native_texture(texture.clone(), TextureOptions::LINEAR);
+
+        Ok(Self {
+            texture,
+            texture_id,
+            camera: OrbitalCamera::default(),
+            render_lights: true,
+        })
+    }
+
+    /// Recreate the texture if its size has changed, keeping the same texture id
+    pub fn resize(
+        &mut self,
+        dimensions: (u32, u32),
+        display: &Display<WindowSurface>,
+        painter: &mut Painter,
+    ) -> Result<()> {
+        if dimensions == self.dimensions() {
+            return Ok(());
+        }
+
+        self.texture = Rc::new(SrgbTexture2d::empty(display, dimensions.0, dimensions.1)?);
+        painter.replace_native_texture(
+            self.texture_id,
+            self.texture.clone(),
+            TextureOptions::LINEAR,
+        );
+
+        Ok(())
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.texture.dimensions()
+    }
+
+    pub fn framebuffer(&self, display: &Display<WindowSurface>) -> Result<SimpleFrameBuffer> {
+        Ok(SimpleFrameBuffer::new(display, &*self.texture)?)
+    }
+
+    /// The color last drawn at `uv`, where (0, 0) is the top left of the viewport
+    pub fn read_pixel(&self, uv: (f32, f32)) -> Srgb<u8> {
+        let (width, height) = self.dimensions();
+        // Rows are read bottom row first
+        let x = ((uv.0 * width as f32) as usize).min(width as usize - 1);
+        let y = (((1.0 - uv.1) * height as f32) as usize).min(height as usize - 1);
+
+        let pixels: Vec<Vec<(u8, u8, u8, u8)>> = self.texture.read();
+        let (red, green, blue, _) = pixels[y][x];
+
+        Srgb::new(red, green, blue)
+    }
+
+    /// Copy what was last drawn into the viewport into a new texture
+    fn copy_texture(&self, display: &Display<WindowSurface>) -> Result<Rc<SrgbTexture2d>> {
+        let (width, height) = self.dimensions();
+        let copy = SrgbTexture2d::empty(display, width, height)?;
+
+        self.framebuffer(display)?.blit_whole_color_to(
+            &SimpleFrameBuffer::new(display, &copy)?,
+            &BlitTarget {
+                left: 0,
+                bottom: 0,
+                width: width as i32,
+                height: height as i32,
+            },
+            MagnifySamplerFilter::Nearest,
+        );
+
+        Ok(Rc::new(copy))
+    }
+}
+
+/// A still image captured from a viewport, shown over the left of the live viewport to compare changes against
+pub struct Comparison {
+    pub texture_id: TextureId,
+    /// The viewport which was captured, and which the image is shown over
+    pub viewport: usize,
+    /// Fraction of the width of the viewport, from the left, which shows the captured image
+    pub split: f32,
+    pub visible: bool,
+}
+
+impl Comparison {
+    pub fn capture(
+        viewport_index: usize,
+        viewport: &Viewport,
+        display: &Display<WindowSurface>,
+        painter: &mut Painter,
+    ) -> Result<Self> {
+        let texture_id = painter
+            .register_native_texture(viewport.copy_texture(display)?, TextureOptions::LINEAR);
+
+        Ok(Self {
+            texture_id,
+            viewport: viewport_index,
+            split: 0.5,
+            visible: true,
+        })
+    }
+
+    /// Replace the captured image, keeping the same texture id
+    pub fn recapture(
+        &mut self,
+        viewport_index: usize,
+        viewport: &Viewport,
+        display: &Display<WindowSurface>,
+        painter: &mut Painter,
+    ) -> Result<()> {
+        painter.replace_native_texture(
+            self.texture_id,
+            viewport.copy_texture(display)?,
+            TextureOptions::LINEAR,
+        );
+
+        self.viewport = viewport_index;
+        self.visible = true;
+
+        Ok(())
+    }
+}
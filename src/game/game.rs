@@ -1,13 +1,17 @@
 use crate::player::Player;
+use crate::settings::Settings;
 use common::app::Application;
 use common::camera::Camera;
 use common::context::OpenGLContext;
 use common::debug;
 use common::input::Input;
-use common::renderer::Renderer;
+use common::metrics::{FrameMetrics, MetricsRecorder};
+use common::renderer::{GraphicsPreset, RenderSettings, Renderer};
 use common::scene::Scene;
-use std::path::PathBuf;
-use std::time::Instant;
+use common::text::Font;
+use log::error;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use winit::event::{Event, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
 use winit::keyboard::KeyCode;
@@ -44,6 +48,9 @@ pub struct Game {
     scene: Scene,
     player: Player,
     renderer: Renderer,
+    font: Font,
+    /// Only recorded when the game is run with `--metrics`
+    metrics: Option<MetricsRecorder>,
     opengl_context: OpenGLContext,
     state: FrameState,
 }
@@ -55,13 +62,31 @@ impl Game {
 
         let opengl_context = OpenGLContext::new("We shootin now", false, event_loop);
 
-        let renderer = Renderer::new(&opengl_context.display).unwrap();
-        let scene = Scene::from_path(
+        let mut renderer = Renderer::new(&opengl_context.display).unwrap();
+        if std::env::args().any(|argument| argument == "--low") {
+            renderer.settings = RenderSettings::preset(GraphicsPreset::Low);
+        }
+        let mut scene = Scene::from_path(
             &PathBuf::from("assets/game_scenes/map.json"),
             &opengl_context.display,
         )
         .unwrap();
 
+        // A broken settings file should not stop the game from starting
+        let settings = Settings::load(Path::new("settings.json")).unwrap_or_else(|err| {
+            error!("Could not load the settings, using the defaults: {:?}", err);
+            Settings::default()
+        });
+        scene.camera.set_fov(settings.fov.to_radians());
+        renderer.settings.colorblind_filter = settings.colorblind_filter;
+
+        let font = Font::load(
+            Path::new("assets/fonts/DejaVuSans.ttf"),
+            32.0,
+            &opengl_context.display,
+        )
+        .unwrap();
+
         // scene.camera = scene.starting_camera.clone();
 
         // let inner_size = opengl_context.window.inner_size();
@@ -71,6 +96,10 @@ impl Game {
             inner_size.width as f32 / inner_size.height as f32,
         );*/
 
+        let metrics = std::env::args()
+            .any(|argument| argument == "--metrics")
+            .then(|| MetricsRecorder::create(Path::new("metrics.csv")).unwrap());
+
         let state = FrameState::default();
         let input = Input::new();
 
@@ -79,6 +108,8 @@ impl Game {
         Self {
             opengl_context,
             renderer,
+            font,
+            metrics,
             scene,
             state,
             input,
@@ -87,6 +118,23 @@ impl Game {
     }
 }
 
+impl Game {
+    fn record_metrics(&mut self) {
+        if let Some(metrics) = self.metrics.as_mut() {
+            metrics
+                .record(&FrameMetrics {
+                    frame_time: Duration::from_secs_f64(self.state.deltatime),
+                    cpu: self.renderer.stats.cpu.clone(),
+                    gpu: self.renderer.stats.gpu.clone(),
+                    draw_calls: self.renderer.stats.draw_calls,
+                    triangles: self.renderer.stats.triangles,
+                    nodes: self.scene.graph.node_count(),
+                })
+                .unwrap();
+        }
+    }
+}
+
 impl Application for Game {
     // TODO figure out some way to not copy this code from editor
     fn run(mut self, event_loop: EventLoop<()>) {
@@ -120,6 +168,7 @@ impl Application for Game {
                                 self.render();
 
                                 self.state.update_statistics();
+                                self.record_metrics();
                             }
                             _ => (),
                         };
@@ -152,14 +201,52 @@ impl Application for Game {
     }
 
     fn render(&mut self) {
-        let mut target = self.opengl_context.display.draw();
+        let window_size = self.opengl_context.window.inner_size();
+        if window_size.width == 0 || window_size.height == 0 {
+            return;
+        }
+
+        self.renderer.begin_frame();
+
+        let scene_target = self
+            .renderer
+            .scene_target(
+                self.opengl_context.display.get_framebuffer_dimensions(),
+                &self.opengl_context.display,
+            )
+            .unwrap();
+
         {
+            let mut scene_framebuffer = scene_target
+                .framebuffer(&self.opengl_context.display)
+                .unwrap();
+
             self.scene.render(
                 &mut self.renderer,
                 &self.scene.camera.view(),
                 &self.scene.camera.projection(),
                 self.scene.camera.position(),
                 &self.opengl_context.display,
+                &mut scene_framebuffer,
+            );
+        }
+
+        let mut target = self.opengl_context.display.draw();
+        {
+            self.renderer.post_process(
+                &scene_target,
+                &self.scene.post_processing,
+                &self.opengl_context.display,
+                &mut target,
+            );
+
+            self.renderer.render_text(
+                &self.font,
+                &format!("{:.0} FPS", self.state.fps),
+                [10.0, 10.0],
+                20.0,
+                palette::named::WHITE.into_format(),
+                &self.opengl_context.display,
                 &mut target,
             );
         }
@@ -1,5 +1,6 @@
 mod game;
 mod player;
+mod settings;
 
 use common::app::Application;
 use game::Game;
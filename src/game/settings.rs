@@ -0,0 +1,35 @@
+use color_eyre::eyre::WrapErr;
+use color_eyre::Result;
+use common::post_processing::ColorblindFilter;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Player preferences, read from a JSON file in which every field is optional
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Vertical field of view in degrees
+    pub fov: f32,
+    pub colorblind_filter: ColorblindFilter,
+}
+
+impl Settings {
+    /// The defaults are used when there is no settings file
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        serde_json::from_str(&std::fs::read_to_string(path)?)
+            .wrap_err_with(|| format!("Invalid settings in {:?}", path))
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            fov: 90.0,
+            colorblind_filter: ColorblindFilter::default(),
+        }
+    }
+}
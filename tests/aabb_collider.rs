@@ -0,0 +1,61 @@
+mod common;
+
+use crate::common::point;
+use ::common::colliders::{AABBCollider, Collider};
+use cgmath::Vector3;
+use proptest::prelude::*;
+
+fn aabb() -> impl Strategy<Value = AABBCollider> {
+    (point(), point()).prop_map(|(a, b)| AABBCollider {
+        min: Vector3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+        max: Vector3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+    })
+}
+
+/// Two boxes overlap exactly when their intervals overlap on every axis
+fn overlapping(a: &AABBCollider, b: &AABBCollider) -> bool {
+    (0..3).all(|axis| a.min[axis] <= b.max[axis] && a.max[axis] >= b.min[axis])
+}
+
+proptest! {
+    #[test]
+    fn colliding_is_symmetric(a in aabb(), b in aabb()) {
+        prop_assert_eq!(a.colliding(&b), b.colliding(&a));
+    }
+
+    #[test]
+    fn collides_with_itself(a in aabb()) {
+        prop_assert!(a.colliding(&a));
+    }
+
+    #[test]
+    fn matches_interval_overlap(a in aabb(), b in aabb()) {
+        prop_assert_eq!(a.colliding(&b), overlapping(&a, &b));
+    }
+
+    #[test]
+    fn collides_with_contained_box(outer in aabb(), t0 in 0.0_f32..0.9, t1 in 0.0_f32..0.9) {
+        let (t_min, t_max) = (t0.min(t1), t0.max(t1));
+        let inner = AABBCollider {
+            min: outer.min + (outer.max - outer.min) * t_min,
+            max: outer.min + (outer.max - outer.min) * t_max,
+        };
+
+        prop_assert!(outer.colliding(&inner));
+        prop_assert!(inner.colliding(&outer));
+    }
+
+    #[test]
+    fn separated_boxes_do_not_collide(a in aabb(), axis in 0_usize..3, gap in 0.001_f32..100.0) {
+        let mut offset = Vector3::new(0.0, 0.0, 0.0);
+        offset[axis] = a.max[axis] - a.min[axis] + gap;
+
+        let b = AABBCollider {
+            min: a.min + offset,
+            max: a.max + offset,
+        };
+
+        prop_assert!(!a.colliding(&b));
+        prop_assert!(!b.colliding(&a));
+    }
+}
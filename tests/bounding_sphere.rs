@@ -0,0 +1,32 @@
+mod common;
+
+use crate::common::{point, TOLERANCE};
+use ::common::colliders::BoundingSphere;
+use cgmath::InnerSpace;
+use proptest::prelude::*;
+
+fn sphere() -> impl Strategy<Value = BoundingSphere> {
+    (point(), 0.0_f32..100.0).prop_map(|(center, radius)| BoundingSphere { center, radius })
+}
+
+proptest! {
+    #[test]
+    fn contains_every_point(points in prop::collection::vec(point(), 1..50)) {
+        let sphere = BoundingSphere::from_points(&points).unwrap();
+
+        for point in points {
+            prop_assert!((point - sphere.center).magnitude() <= sphere.radius + TOLERANCE);
+        }
+    }
+
+    #[test]
+    fn union_contains_both(a in sphere(), b in sphere()) {
+        let union = a.union(&b);
+
+        for sphere in [a, b] {
+            prop_assert!(
+                (sphere.center - union.center).magnitude() + sphere.radius <= union.radius + TOLERANCE
+            );
+        }
+    }
+}
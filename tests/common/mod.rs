@@ -0,0 +1,12 @@
+// Each test file only uses some of these helpers
+#![allow(dead_code)]
+
+use cgmath::Vector3;
+use proptest::prelude::*;
+
+pub fn point() -> impl Strategy<Value = Vector3<f32>> {
+    (-100.0_f32..100.0, -100.0_f32..100.0, -100.0_f32..100.0).prop_map(Vector3::from)
+}
+
+/// Allows for rounding in anything computed from floats
+pub const TOLERANCE: f32 = 0.01;
@@ -0,0 +1,70 @@
+use common::texture::{Cubemap, TextureLoadError};
+use image::{Rgba, RgbaImage};
+use proptest::prelude::*;
+
+/// An image made of `columns` by `rows` sides of `size` pixels, each filled with its own column and row
+fn grid_image(size: u32, columns: u32, rows: u32) -> RgbaImage {
+    RgbaImage::from_fn(size * columns, size * rows, |x, y| {
+        Rgba([(x / size) as u8, (y / size) as u8, 0, 255])
+    })
+}
+
+/// Every side is square and filled only with the column and row it was cut from
+fn assert_sides(sides: &[RgbaImage], size: u32, cells: [(u8, u8); 6]) -> Result<(), TestCaseError> {
+    prop_assert_eq!(sides.len(), 6);
+
+    for (side, (column, row)) in sides.iter().zip(cells) {
+        prop_assert_eq!(side.dimensions(), (size, size));
+        prop_assert!(side
+            .pixels()
+            .all(|pixel| *pixel == Rgba([column, row, 0, 255])));
+    }
+
+    Ok(())
+}
+
+fn face_size() -> impl Strategy<Value = u32> {
+    (0_u32..6).prop_map(|exponent| 1 << exponent)
+}
+
+proptest! {
+    #[test]
+    fn slices_a_cross(size in face_size()) {
+        let sides = Cubemap::slice_sides(&grid_image(size, 4, 3)).unwrap();
+
+        assert_sides(&sides, size, [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)])?;
+    }
+
+    #[test]
+    fn slices_a_strip(size in face_size()) {
+        let sides = Cubemap::slice_sides(&grid_image(size, 1, 6)).unwrap();
+
+        assert_sides(&sides, size, [(0, 0), (0, 1), (0, 2), (0, 3), (0, 4), (0, 5)])?;
+    }
+
+    #[test]
+    fn rejects_other_layouts(width in 1_u32..128, height in 1_u32..128) {
+        prop_assume!(width * 3 != height * 4 && width * 6 != height);
+
+        prop_assert!(matches!(
+            Cubemap::slice_sides(&RgbaImage::new(width, height)),
+            Err(TextureLoadError::CubemapLayoutError(dimensions)) if dimensions == (width, height)
+        ));
+    }
+
+    #[test]
+    fn rejects_faces_which_are_not_a_power_of_two(size in 3_u32..64, cross in any::<bool>()) {
+        prop_assume!(!size.is_power_of_two());
+
+        let image = if cross {
+            grid_image(size, 4, 3)
+        } else {
+            grid_image(size, 1, 6)
+        };
+
+        prop_assert!(matches!(
+            Cubemap::slice_sides(&image),
+            Err(TextureLoadError::CubemapFaceSizeError(face)) if face == size
+        ));
+    }
+}
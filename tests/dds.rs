@@ -0,0 +1,87 @@
+use common::import::dds::{level_size, Dds, DdsLoadError};
+use glium::texture::CompressedFormat;
+use proptest::prelude::*;
+
+/// A DDS file with the given four character code, holding `mipmap_count` levels filled with zeros
+fn dds_file(four_cc: &[u8; 4], width: u32, height: u32, mipmap_count: u32, data: usize) -> Vec<u8> {
+    let mut bytes = vec![0; 128];
+
+    bytes[..4].copy_from_slice(b"DDS ");
+    bytes[4..8].copy_from_slice(&124_u32.to_le_bytes());
+    bytes[8..12].copy_from_slice(&0x20000_u32.to_le_bytes());
+    bytes[12..16].copy_from_slice(&height.to_le_bytes());
+    bytes[16..20].copy_from_slice(&width.to_le_bytes());
+    bytes[28..32].copy_from_slice(&mipmap_count.to_le_bytes());
+    bytes[84..88].copy_from_slice(four_cc);
+
+    bytes.resize(128 + data, 0);
+    bytes
+}
+
+fn full_mipmap_size(width: u32, height: u32, format: CompressedFormat) -> (u32, usize) {
+    let mut count = 0;
+    let mut size = 0;
+
+    loop {
+        size += level_size(width >> count, height >> count, format);
+        count += 1;
+
+        if width >> (count - 1) <= 1 && height >> (count - 1) <= 1 {
+            return (count, size);
+        }
+    }
+}
+
+#[test]
+fn rejects_other_files() {
+    assert!(matches!(
+        Dds::parse(b"\x89PNG not a DDS file"),
+        Err(DdsLoadError::InvalidHeader)
+    ));
+}
+
+#[test]
+fn rejects_uncompressed_formats() {
+    let bytes = dds_file(b"\0\0\0\0", 4, 4, 1, 64);
+
+    assert!(matches!(
+        Dds::parse(&bytes),
+        Err(DdsLoadError::UnsupportedFormat(_))
+    ));
+}
+
+#[test]
+fn rejects_truncated_data() {
+    // A 4x4 DXT5 image is a single 16 byte block
+    let bytes = dds_file(b"DXT5", 4, 4, 1, 15);
+
+    assert!(matches!(
+        Dds::parse(&bytes),
+        Err(DdsLoadError::MissingData {
+            expected: 16,
+            found: 15
+        })
+    ));
+}
+
+proptest! {
+    #[test]
+    fn reads_every_mipmap(width in 1_u32..2048, height in 1_u32..2048, dxt1 in any::<bool>()) {
+        let (four_cc, format) = if dxt1 {
+            (b"DXT1", CompressedFormat::S3tcDxt1Alpha)
+        } else {
+            (b"DXT5", CompressedFormat::S3tcDxt5Alpha)
+        };
+        let (count, size) = full_mipmap_size(width, height, format);
+
+        let dds = Dds::parse(&dds_file(four_cc, width, height, count, size)).unwrap();
+
+        prop_assert_eq!((dds.width, dds.height), (width, height));
+        prop_assert_eq!(dds.format, format);
+        prop_assert_eq!(dds.levels.len() as u32, count);
+
+        for (level, data) in dds.levels.iter().enumerate() {
+            prop_assert_eq!(data.len(), level_size(width >> level, height >> level, format));
+        }
+    }
+}
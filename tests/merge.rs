@@ -0,0 +1,99 @@
+mod common;
+
+use crate::common::{point, TOLERANCE};
+use ::common::models::merge::{merge_primitives, MergePart};
+use ::common::models::model_vertex::ModelVertex;
+use cgmath::Matrix4;
+use proptest::prelude::*;
+
+fn vertex() -> impl Strategy<Value = ModelVertex> {
+    (
+        prop::array::uniform3(-100.0_f32..100.0),
+        prop::array::uniform2(0.0_f32..1.0),
+    )
+        .prop_map(|(position, tex_coord)| ModelVertex {
+            position,
+            normal: [0.0, 1.0, 0.0],
+            tex_coord,
+            tangent: [1.0, 0.0, 0.0, 1.0],
+            ..ModelVertex::default()
+        })
+}
+
+/// Vertices with whole triangles of indices into them
+fn primitive() -> impl Strategy<Value = (Vec<ModelVertex>, Vec<u32>)> {
+    prop::collection::vec(vertex(), 1..20).prop_flat_map(|vertices| {
+        let count = vertices.len() as u32;
+        (
+            Just(vertices),
+            prop::collection::vec(prop::array::uniform3(0..count), 0..10)
+                .prop_map(|triangles| triangles.concat()),
+        )
+    })
+}
+
+proptest! {
+    #[test]
+    fn keeps_every_vertex_and_index(
+        primitives in prop::collection::vec((primitive(), point()), 1..5),
+    ) {
+        let parts = primitives
+            .iter()
+            .map(|((vertices, indices), translation)| MergePart {
+                transform: Matrix4::from_translation(*translation),
+                vertices,
+                indices,
+            })
+            .collect::<Vec<_>>();
+
+        let (vertices, indices) = merge_primitives(&parts);
+
+        let mut offset = 0;
+        let mut first_index = 0;
+
+        for ((original_vertices, original_indices), translation) in &primitives {
+            for (original, merged) in original_vertices.iter().zip(&vertices[offset..]) {
+                for axis in 0..3 {
+                    prop_assert!(
+                        (original.position[axis] + translation[axis] - merged.position[axis]).abs()
+                            < TOLERANCE
+                    );
+                }
+
+                // Translating does not turn anything
+                prop_assert_eq!(original.normal, merged.normal);
+                prop_assert_eq!(original.tangent, merged.tangent);
+                prop_assert_eq!(original.tex_coord, merged.tex_coord);
+            }
+
+            // Each primitive's indices point at its own vertices
+            for (original, merged) in original_indices
+                .iter()
+                .zip(&indices[first_index..first_index + original_indices.len()])
+            {
+                prop_assert_eq!(original + offset as u32, *merged);
+            }
+
+            offset += original_vertices.len();
+            first_index += original_indices.len();
+        }
+
+        prop_assert_eq!(vertices.len(), offset);
+        prop_assert_eq!(indices.len(), first_index);
+    }
+
+    #[test]
+    fn mirroring_reverses_the_winding((vertices, indices) in primitive()) {
+        let parts = [MergePart {
+            transform: Matrix4::from_nonuniform_scale(-1.0, 1.0, 1.0),
+            vertices: &vertices,
+            indices: &indices,
+        }];
+
+        let (_, merged) = merge_primitives(&parts);
+
+        for (original, merged) in indices.chunks(3).zip(merged.chunks(3)) {
+            prop_assert_eq!([original[2], original[1], original[0]], [merged[0], merged[1], merged[2]]);
+        }
+    }
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use crate::common::{point, TOLERANCE};
+use ::common::colliders::{AABBCollider, BoundingSphere, Ray};
+use cgmath::{InnerSpace, Vector3};
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn hits_sphere_it_points_at(origin in point(), center in point(), radius in 0.1_f32..10.0) {
+        prop_assume!((center - origin).magnitude() > radius);
+
+        let ray = Ray { origin, direction: (center - origin).normalize() };
+        let distance = ray.intersect_sphere(&BoundingSphere { center, radius }).unwrap();
+
+        prop_assert!((distance - ((center - origin).magnitude() - radius)).abs() <= TOLERANCE);
+    }
+
+    #[test]
+    fn misses_sphere_behind(origin in point(), center in point(), radius in 0.1_f32..10.0) {
+        prop_assume!((center - origin).magnitude() > radius);
+
+        let ray = Ray { origin, direction: (origin - center).normalize() };
+
+        prop_assert!(ray.intersect_sphere(&BoundingSphere { center, radius }).is_none());
+    }
+
+    #[test]
+    fn hits_box_it_points_at(origin in point(), center in point(), half_extent in 0.1_f32..10.0) {
+        let aabb = AABBCollider {
+            min: center - Vector3::new(half_extent, half_extent, half_extent),
+            max: center + Vector3::new(half_extent, half_extent, half_extent),
+        };
+        let ray = Ray { origin, direction: (center - origin).normalize() };
+
+        let distance = ray.intersect_aabb(&aabb).unwrap();
+
+        prop_assert!(distance <= (center - origin).magnitude() + TOLERANCE);
+    }
+}
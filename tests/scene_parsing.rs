@@ -0,0 +1,62 @@
+use common::scene::Scene;
+
+const MAP: &str = include_str!("../assets/game_scenes/map.json");
+
+fn corpus() -> Vec<String> {
+    vec![
+        MAP.to_owned(),
+        serde_json::to_string(&Scene::new("Test")).unwrap(),
+    ]
+}
+
+#[test]
+fn valid_scenes_parse() {
+    for scene in corpus() {
+        Scene::parse(&scene).unwrap();
+    }
+}
+
+#[test]
+fn truncated_scenes_are_errors() {
+    for scene in corpus() {
+        for end in (0..scene.len()).filter(|end| scene.is_char_boundary(*end)) {
+            assert!(
+                Scene::parse(&scene[..end]).is_err(),
+                "Truncating at {} should not parse",
+                end
+            );
+        }
+    }
+}
+
+#[test]
+fn mutated_scenes_do_not_panic() {
+    let mut rng = fastrand::Rng::with_seed(0);
+    // Bytes which are likely to change the structure of the JSON
+    let replacements = b"{}[]\":,0-.eatn \\";
+
+    for scene in corpus() {
+        for _ in 0..2000 {
+            let mut bytes = scene.clone().into_bytes();
+
+            for _ in 0..rng.usize(1..=4) {
+                let index = rng.usize(..bytes.len());
+                bytes[index] = replacements[rng.usize(..replacements.len())];
+            }
+
+            // The replacements are all ASCII so the scene is still valid UTF-8
+            let mutated = String::from_utf8(bytes).unwrap();
+            let _ = Scene::parse(&mutated);
+        }
+    }
+}
+
+#[test]
+fn errors_name_the_invalid_field() {
+    let scene = MAP.replacen("\"yaw\":0.0", "\"yaw\":\"north\"", 1);
+
+    let error = Scene::parse(&scene).err().unwrap().to_string();
+
+    assert!(error.contains("camera.yaw"), "{}", error);
+    assert!(error.contains("line 1"), "{}", error);
+}
@@ -0,0 +1,32 @@
+mod common;
+
+use crate::common::TOLERANCE;
+use ::common::transform::Transform;
+use cgmath::{Deg, Euler, InnerSpace, Quaternion, Vector3};
+use proptest::prelude::*;
+
+fn transform() -> impl Strategy<Value = Transform> {
+    (
+        (-100.0_f32..100.0, -100.0_f32..100.0, -100.0_f32..100.0),
+        (-180.0_f32..180.0, -90.0_f32..90.0, -180.0_f32..180.0),
+        0.1_f32..10.0,
+    )
+        .prop_map(|(translation, (x, y, z), scale)| Transform {
+            translation: Vector3::from(translation),
+            rotation: Quaternion::from(Euler::new(Deg(x), Deg(y), Deg(z))),
+            scale,
+        })
+}
+
+proptest! {
+    #[test]
+    fn relative_to_undoes_then(parent in transform(), local in transform()) {
+        let world = parent.then(&local);
+        let relative = world.relative_to(&parent);
+
+        prop_assert!((relative.translation - local.translation).magnitude() <= TOLERANCE);
+        prop_assert!((relative.scale - local.scale).abs() <= TOLERANCE);
+        // q and -q are the same rotation
+        prop_assert!(relative.rotation.dot(local.rotation).abs() >= 1.0 - TOLERANCE);
+    }
+}